@@ -21,6 +21,15 @@ fn main() {
         }
     }
 
+    // Compile the shim that turns exceptions thrown by the runtime into status codes
+    let shim = PathBuf::from(&manifest_dir).join("shim/kunquant_shim.cpp");
+    cc::Build::new()
+        .cpp(true)
+        .std("c++11")
+        .file(&shim)
+        .compile("kunquant_shim");
+    println!("cargo:rerun-if-changed={}", shim.display());
+
     // Tell cargo to tell rustc to link the KunRuntime library
     println!("cargo:rustc-link-lib=dylib=KunRuntime");
 
@@ -116,7 +116,10 @@ def main():
     simple_stream_lib_path = os.path.join(test_libs_dir, "simple_stream_lib")
     simple_stream_lib = cfake.compileit([
         ("simple_stream_test", simple_stream_factor,
-         KunCompilerConfig(input_layout="TS", output_layout="STREAM"))
+         KunCompilerConfig(input_layout="TS", output_layout="STREAM")),
+        # Batch build of the same factor, for stream-vs-batch consistency tests
+        ("simple_stream_batch", create_simple_stream_factor(),
+         KunCompilerConfig(input_layout="TS", output_layout="TS"))
     ], simple_stream_lib_path, cfake.CppCompilerConfig())
 
     print(f"Simple streaming library compiled successfully!")
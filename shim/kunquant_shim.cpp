@@ -0,0 +1,79 @@
+// Thin exception barrier around the KunQuant C API.
+//
+// The runtime is written in C++ and reports some failures by throwing. An
+// exception unwinding into Rust aborts the process, so every wrapper below
+// catches it, keeps its message in a thread-local buffer and returns a non-zero
+// status instead. Failures that abort inside the runtime cannot be caught here.
+
+#include <cstddef>
+#include <exception>
+#include <string>
+
+extern "C" {
+void *kunLoadLibrary(const char *path_or_name);
+void kunRunGraph(void *exec, void *m, void *buffers, size_t num_stocks,
+                 size_t total_time, size_t cur_time, size_t length);
+void kunSetBufferNameMap(void *ptr, const char *name, float *buffer);
+void *kunCreateStream(void *exec, void *m, size_t num_stocks);
+size_t kunQueryBufferHandle(void *context, const char *name);
+void kunStreamPushData(void *context, size_t handle, const float *buffer);
+void kunStreamRun(void *context);
+}
+
+namespace {
+
+thread_local std::string last_error;
+
+template <typename F> int guard(F &&f) noexcept {
+    try {
+        f();
+        return 0;
+    } catch (const std::exception &e) {
+        last_error = e.what();
+    } catch (const char *message) {
+        last_error = message;
+    } catch (const std::string &message) {
+        last_error = message;
+    } catch (...) {
+        last_error = "unknown exception";
+    }
+    return 1;
+}
+
+} // namespace
+
+extern "C" {
+
+const char *kunShimLastError() { return last_error.c_str(); }
+
+int kunShimLoadLibrary(const char *path_or_name, void **out) {
+    return guard([&] { *out = kunLoadLibrary(path_or_name); });
+}
+
+int kunShimRunGraph(void *exec, void *m, void *buffers, size_t num_stocks,
+                    size_t total_time, size_t cur_time, size_t length) {
+    return guard([&] {
+        kunRunGraph(exec, m, buffers, num_stocks, total_time, cur_time, length);
+    });
+}
+
+int kunShimSetBufferNameMap(void *ptr, const char *name, float *buffer) {
+    return guard([&] { kunSetBufferNameMap(ptr, name, buffer); });
+}
+
+int kunShimCreateStream(void *exec, void *m, size_t num_stocks, void **out) {
+    return guard([&] { *out = kunCreateStream(exec, m, num_stocks); });
+}
+
+int kunShimQueryBufferHandle(void *context, const char *name, size_t *out) {
+    return guard([&] { *out = kunQueryBufferHandle(context, name); });
+}
+
+int kunShimStreamPushData(void *context, size_t handle, const float *buffer) {
+    return guard([&] { kunStreamPushData(context, handle, buffer); });
+}
+
+int kunShimStreamRun(void *context) {
+    return guard([&] { kunStreamRun(context); });
+}
+}
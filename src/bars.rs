@@ -0,0 +1,583 @@
+//! Aggregation of trades and quotes into fixed-interval bars for stream modules.
+
+use crate::error::{KunQuantError, Result};
+use crate::owned_stream::OwnedStreamContext;
+use crate::stream::StreamContext;
+use crate::stream_group::StreamGroup;
+
+/// A field of an OHLCV bar, named after the stream module input it feeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarField {
+    /// Price of the first tick in the interval
+    Open,
+    /// Highest price in the interval
+    High,
+    /// Lowest price in the interval
+    Low,
+    /// Price of the last tick in the interval
+    Close,
+    /// Traded volume
+    Volume,
+    /// Traded amount, the sum of `price * volume`
+    Amount,
+}
+
+impl BarField {
+    /// All fields, in the order they are pushed.
+    pub const ALL: [BarField; 6] = [
+        BarField::Open,
+        BarField::High,
+        BarField::Low,
+        BarField::Close,
+        BarField::Volume,
+        BarField::Amount,
+    ];
+
+    /// Input buffer name of this field.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BarField::Open => "open",
+            BarField::High => "high",
+            BarField::Low => "low",
+            BarField::Close => "close",
+            BarField::Volume => "volume",
+            BarField::Amount => "amount",
+        }
+    }
+}
+
+/// How intervals without any tick are turned into bars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmptyBarPolicy {
+    /// Repeat the previous close as open, high, low and close with zero volume
+    #[default]
+    ForwardFill,
+    /// Emit NaN prices with zero volume
+    Nan,
+    /// Do not emit a bar for intervals in which no stock traded
+    Skip,
+}
+
+/// A completed bar for every stock, borrowed from a [`BarBuilder`].
+#[derive(Debug)]
+pub struct Bar<'b> {
+    /// Start of the interval (inclusive)
+    pub start: u64,
+    /// End of the interval (exclusive)
+    pub end: u64,
+    /// Opening prices, one per stock
+    pub open: &'b [f32],
+    /// High prices, one per stock
+    pub high: &'b [f32],
+    /// Low prices, one per stock
+    pub low: &'b [f32],
+    /// Closing prices, one per stock
+    pub close: &'b [f32],
+    /// Traded volumes, one per stock
+    pub volume: &'b [f32],
+    /// Traded amounts, one per stock
+    pub amount: &'b [f32],
+    fields: &'b [BarField],
+}
+
+impl<'b> Bar<'b> {
+    /// Returns the data of one field.
+    pub fn field(&self, field: BarField) -> &'b [f32] {
+        match field {
+            BarField::Open => self.open,
+            BarField::High => self.high,
+            BarField::Low => self.low,
+            BarField::Close => self.close,
+            BarField::Volume => self.volume,
+            BarField::Amount => self.amount,
+        }
+    }
+
+    /// Iterates over the selected fields as `(input name, data)` pairs.
+    pub fn fields(&self) -> impl Iterator<Item = (&'static str, &'b [f32])> + '_ {
+        self.fields.iter().map(|f| (f.name(), self.field(*f)))
+    }
+}
+
+/// Destination of completed bars.
+///
+/// Implemented for the streaming context types, where pushing a bar pushes every
+/// selected field as an input and runs one tick.
+pub trait BarSink {
+    /// Consumes one completed bar.
+    fn push_bar(&mut self, bar: &Bar<'_>) -> Result<()>;
+}
+
+impl BarSink for StreamContext<'_> {
+    fn push_bar(&mut self, bar: &Bar<'_>) -> Result<()> {
+        for (name, data) in bar.fields() {
+            self.push_data(name, data)?;
+        }
+        self.run()
+    }
+}
+
+impl BarSink for OwnedStreamContext {
+    fn push_bar(&mut self, bar: &Bar<'_>) -> Result<()> {
+        for (name, data) in bar.fields() {
+            self.push_data(name, data)?;
+        }
+        self.run()
+    }
+}
+
+impl BarSink for StreamGroup {
+    fn push_bar(&mut self, bar: &Bar<'_>) -> Result<()> {
+        for (name, data) in bar.fields() {
+            self.push_data(name, data)?;
+        }
+        self.run()
+    }
+}
+
+/// Aggregates per-stock ticks into fixed-interval OHLCV bars.
+///
+/// Timestamps are plain `u64` values in any unit (e.g. milliseconds since
+/// midnight); intervals are aligned to multiples of `interval` in the same unit.
+/// A bar is closed and pushed to the [`BarSink`] as soon as a tick, or a call to
+/// [`advance_to`](BarBuilder::advance_to), moves past the end of its interval.
+///
+/// Trades contribute price and volume. Quotes only advance the clock, unless
+/// [`with_quote_mids`](BarBuilder::with_quote_mids) lets their mid price update
+/// open, high, low and close like a trade with zero volume. Stocks without ticks in
+/// a bar that is emitted are filled according to the [`EmptyBarPolicy`]
+/// (forward-filled when the policy is `Skip`, which only drops intervals in which
+/// no stock had a tick). Prices stay NaN until a stock's first tick.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use kunquant_rs::bars::{BarBuilder, EmptyBarPolicy};
+/// use kunquant_rs::{Executor, Library, StreamContext};
+///
+/// # fn main() -> kunquant_rs::Result<()> {
+/// let executor = Executor::single_thread()?;
+/// let library = Library::load("factors.so")?;
+/// let module = library.get_module("alpha_stream")?;
+/// let mut stream = StreamContext::new(&executor, &module, 16)?;
+///
+/// // One-minute bars from millisecond timestamps
+/// let mut bars = BarBuilder::new(16, 60_000)?.with_empty_policy(EmptyBarPolicy::ForwardFill);
+/// bars.on_trade(&mut stream, 3, 34_200_120, 10.5, 200.0)?;
+/// bars.on_trade(&mut stream, 5, 34_200_450, 20.1, 300.0)?;
+/// // Closes the 09:30 bar and runs one tick of the stream
+/// let closed = bars.on_trade(&mut stream, 3, 34_260_010, 10.6, 100.0)?;
+/// assert_eq!(closed, 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct BarBuilder {
+    num_stocks: usize,
+    interval: u64,
+    policy: EmptyBarPolicy,
+    fields: Vec<BarField>,
+    quote_mids: bool,
+    bar_start: Option<u64>,
+    open: Vec<f32>,
+    high: Vec<f32>,
+    low: Vec<f32>,
+    close: Vec<f32>,
+    volume: Vec<f32>,
+    amount: Vec<f32>,
+    ticked: Vec<bool>,
+    last_close: Vec<f32>,
+}
+
+impl BarBuilder {
+    /// Creates a bar builder for `num_stocks` stocks and bars of `interval` time units.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidBarConfig` if `interval` is zero.
+    pub fn new(num_stocks: usize, interval: u64) -> Result<Self> {
+        if interval == 0 {
+            return Err(KunQuantError::InvalidBarConfig {
+                reason: "bar interval must be positive".to_string(),
+            });
+        }
+        Ok(BarBuilder {
+            num_stocks,
+            interval,
+            policy: EmptyBarPolicy::default(),
+            fields: BarField::ALL.to_vec(),
+            quote_mids: false,
+            bar_start: None,
+            open: vec![f32::NAN; num_stocks],
+            high: vec![f32::NAN; num_stocks],
+            low: vec![f32::NAN; num_stocks],
+            close: vec![f32::NAN; num_stocks],
+            volume: vec![0.0; num_stocks],
+            amount: vec![0.0; num_stocks],
+            ticked: vec![false; num_stocks],
+            last_close: vec![f32::NAN; num_stocks],
+        })
+    }
+
+    /// Sets how intervals without ticks are handled.
+    pub fn with_empty_policy(mut self, policy: EmptyBarPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Selects which fields are pushed, for modules that don't declare all of them.
+    pub fn with_fields(mut self, fields: &[BarField]) -> Self {
+        self.fields = fields.to_vec();
+        self
+    }
+
+    /// Lets quotes update prices with their mid price, as a trade with no volume.
+    ///
+    /// By default bars are built from trades only and quotes just close bars that
+    /// ended before them.
+    pub fn with_quote_mids(mut self, quote_mids: bool) -> Self {
+        self.quote_mids = quote_mids;
+        self
+    }
+
+    /// Start of the interval currently being aggregated, if any.
+    pub fn current_bar_start(&self) -> Option<u64> {
+        self.bar_start
+    }
+
+    /// Adds a trade.
+    ///
+    /// # Returns
+    ///
+    /// The number of bars closed and pushed to `sink` before the trade was added,
+    /// or an error if the stock index is out of range, the trade is older than the
+    /// current bar, or the sink fails. A trade rejected as invalid is dropped; a
+    /// trade whose closed bars the sink failed to consume is still added to the new
+    /// bar.
+    pub fn on_trade<S: BarSink + ?Sized>(
+        &mut self,
+        sink: &mut S,
+        stock: usize,
+        time: u64,
+        price: f32,
+        volume: f32,
+    ) -> Result<usize> {
+        self.on_tick(sink, stock, time, Some((price, volume)))
+    }
+
+    /// Adds a quote.
+    ///
+    /// The quote closes the bars that ended before it. Its mid price only updates
+    /// the prices when enabled with [`with_quote_mids`](BarBuilder::with_quote_mids).
+    ///
+    /// See [`on_trade`](BarBuilder::on_trade) for the return value.
+    pub fn on_quote<S: BarSink + ?Sized>(
+        &mut self,
+        sink: &mut S,
+        stock: usize,
+        time: u64,
+        bid: f32,
+        ask: f32,
+    ) -> Result<usize> {
+        let trade = self.quote_mids.then_some(((bid + ask) * 0.5, 0.0));
+        self.on_tick(sink, stock, time, trade)
+    }
+
+    /// Validates a tick, closes the bars before it and adds its `(price, volume)`.
+    fn on_tick<S: BarSink + ?Sized>(
+        &mut self,
+        sink: &mut S,
+        stock: usize,
+        time: u64,
+        trade: Option<(f32, f32)>,
+    ) -> Result<usize> {
+        if stock >= self.num_stocks {
+            return Err(KunQuantError::InvalidTick {
+                reason: format!(
+                    "stock index {} out of range for {} stocks",
+                    stock, self.num_stocks
+                ),
+            });
+        }
+        if let Some(start) = self.bar_start
+            && time < start
+        {
+            return Err(KunQuantError::InvalidTick {
+                reason: format!(
+                    "tick at {} is older than the current bar at {}",
+                    time, start
+                ),
+            });
+        }
+
+        // A sink error is returned after the tick is recorded, as the bars before
+        // it are closed either way
+        let closed = self.advance_to(sink, time);
+        if self.bar_start.is_none() {
+            self.bar_start = Some(self.align(time));
+        }
+        let Some((price, volume)) = trade else {
+            return closed;
+        };
+
+        if self.ticked[stock] {
+            self.high[stock] = self.high[stock].max(price);
+            self.low[stock] = self.low[stock].min(price);
+        } else {
+            self.ticked[stock] = true;
+            self.open[stock] = price;
+            self.high[stock] = price;
+            self.low[stock] = price;
+        }
+        self.close[stock] = price;
+        self.volume[stock] += volume;
+        self.amount[stock] += price * volume;
+        closed
+    }
+
+    /// Closes every bar whose interval ends at or before `time`.
+    ///
+    /// Use this from a timer so bars close on time even when no tick arrives.
+    ///
+    /// # Returns
+    ///
+    /// The number of bars pushed to `sink`, or the first error of the sink. Every
+    /// bar before `time` is closed even if the sink fails on one of them.
+    pub fn advance_to<S: BarSink + ?Sized>(&mut self, sink: &mut S, time: u64) -> Result<usize> {
+        let target = self.align(time);
+        let mut closed = 0;
+        let mut error = None;
+        while let Some(start) = self.bar_start {
+            if start >= target {
+                break;
+            }
+            if self.policy == EmptyBarPolicy::Skip && !self.ticked.contains(&true) {
+                // Nothing to emit until the interval of `time`
+                self.bar_start = Some(target);
+                break;
+            }
+            // Move on even if the sink fails, so the same bar isn't closed again
+            match self.close_bar(sink) {
+                Ok(n) => closed += n,
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+            self.bar_start = Some(start + self.interval);
+        }
+        match error {
+            Some(e) => Err(e),
+            None => Ok(closed),
+        }
+    }
+
+    /// Closes the current bar regardless of time, e.g. at the end of a session.
+    ///
+    /// The next tick starts a new bar without emitting the intervals in between.
+    ///
+    /// # Returns
+    ///
+    /// The number of bars pushed to `sink` (0 or 1).
+    pub fn flush<S: BarSink + ?Sized>(&mut self, sink: &mut S) -> Result<usize> {
+        if self.bar_start.is_none() || !self.ticked.contains(&true) {
+            self.bar_start = None;
+            return Ok(0);
+        }
+        let closed = self.close_bar(sink);
+        self.bar_start = None;
+        closed
+    }
+
+    fn align(&self, time: u64) -> u64 {
+        time - time % self.interval
+    }
+
+    /// Fills stocks without ticks, pushes the bar and resets the accumulators.
+    fn close_bar<S: BarSink + ?Sized>(&mut self, sink: &mut S) -> Result<usize> {
+        let start = self.bar_start.unwrap_or_default();
+        let any_ticked = self.ticked.contains(&true);
+        if !any_ticked && self.policy == EmptyBarPolicy::Skip {
+            return Ok(0);
+        }
+
+        for stock in 0..self.num_stocks {
+            if self.ticked[stock] {
+                continue;
+            }
+            let fill = match self.policy {
+                EmptyBarPolicy::Nan => f32::NAN,
+                EmptyBarPolicy::ForwardFill | EmptyBarPolicy::Skip => self.last_close[stock],
+            };
+            self.open[stock] = fill;
+            self.high[stock] = fill;
+            self.low[stock] = fill;
+            self.close[stock] = fill;
+        }
+
+        let bar = Bar {
+            start,
+            end: start + self.interval,
+            open: &self.open,
+            high: &self.high,
+            low: &self.low,
+            close: &self.close,
+            volume: &self.volume,
+            amount: &self.amount,
+            fields: &self.fields,
+        };
+        let result = sink.push_bar(&bar);
+
+        // Reset even if the sink failed so the next bar starts clean
+        for stock in 0..self.num_stocks {
+            if self.ticked[stock] {
+                self.last_close[stock] = self.close[stock];
+            }
+        }
+        self.open.fill(f32::NAN);
+        self.high.fill(f32::NAN);
+        self.low.fill(f32::NAN);
+        self.close.fill(f32::NAN);
+        self.volume.fill(0.0);
+        self.amount.fill(0.0);
+        self.ticked.fill(false);
+        result.map(|_| 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (start, open, close, volume) of every pushed bar
+    type CollectedBar = (u64, Vec<f32>, Vec<f32>, Vec<f32>);
+
+    #[derive(Default)]
+    struct Collect {
+        bars: Vec<CollectedBar>,
+    }
+
+    impl BarSink for Collect {
+        fn push_bar(&mut self, bar: &Bar<'_>) -> Result<()> {
+            self.bars.push((
+                bar.start,
+                bar.open.to_vec(),
+                bar.close.to_vec(),
+                bar.volume.to_vec(),
+            ));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_aggregates_ohlcv() {
+        let mut sink = Collect::default();
+        let mut bars = BarBuilder::new(2, 10).unwrap();
+        assert_eq!(bars.on_trade(&mut sink, 0, 3, 5.0, 1.0).unwrap(), 0);
+        bars.on_trade(&mut sink, 0, 5, 7.0, 2.0).unwrap();
+        bars.on_trade(&mut sink, 0, 8, 4.0, 1.0).unwrap();
+        bars.on_quote(&mut sink, 1, 9, 1.0, 2.0).unwrap();
+        assert_eq!(bars.on_trade(&mut sink, 0, 10, 6.0, 1.0).unwrap(), 1);
+
+        let (start, open, close, volume) = &sink.bars[0];
+        assert_eq!(*start, 0);
+        assert_eq!(open[0], 5.0);
+        assert_eq!(close[0], 4.0);
+        assert_eq!(volume, &vec![4.0, 0.0]);
+        // Quotes don't set prices by default
+        assert!(open[1].is_nan() && close[1].is_nan());
+        assert_eq!(bars.current_bar_start(), Some(10));
+    }
+
+    #[test]
+    fn test_quote_mids() {
+        let mut sink = Collect::default();
+        let mut bars = BarBuilder::new(1, 10).unwrap().with_quote_mids(true);
+        bars.on_quote(&mut sink, 0, 1, 1.0, 2.0).unwrap();
+        bars.on_quote(&mut sink, 0, 2, 3.0, 4.0).unwrap();
+        assert_eq!(bars.advance_to(&mut sink, 10).unwrap(), 1);
+
+        let (_, open, close, volume) = &sink.bars[0];
+        assert_eq!((open[0], close[0], volume[0]), (1.5, 3.5, 0.0));
+    }
+
+    #[test]
+    fn test_empty_interval_policies() {
+        for (policy, expected_bars) in [
+            (EmptyBarPolicy::ForwardFill, 3),
+            (EmptyBarPolicy::Nan, 3),
+            (EmptyBarPolicy::Skip, 1),
+        ] {
+            let mut sink = Collect::default();
+            let mut bars = BarBuilder::new(2, 10).unwrap().with_empty_policy(policy);
+            bars.on_trade(&mut sink, 0, 1, 5.0, 1.0).unwrap();
+            // Bars [10, 20) and [20, 30) have no ticks
+            let closed = bars.on_trade(&mut sink, 0, 31, 6.0, 1.0).unwrap();
+            assert_eq!(closed, expected_bars, "{:?}", policy);
+
+            let (_, open, close, _) = &sink.bars[0];
+            assert_eq!(open[0], 5.0);
+            // Stock 1 never traded, so it has no price to carry forward
+            assert!(close[1].is_nan());
+            if expected_bars == 3 {
+                let (start, open, close, volume) = &sink.bars[1];
+                assert_eq!(*start, 10);
+                assert_eq!(volume[0], 0.0);
+                match policy {
+                    EmptyBarPolicy::ForwardFill => assert_eq!((open[0], close[0]), (5.0, 5.0)),
+                    _ => assert!(open[0].is_nan() && close[0].is_nan()),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_ticks() {
+        let mut sink = Collect::default();
+        let mut bars = BarBuilder::new(2, 10).unwrap();
+        bars.on_trade(&mut sink, 0, 25, 1.0, 1.0).unwrap();
+        assert!(matches!(
+            bars.on_trade(&mut sink, 0, 15, 1.0, 1.0),
+            Err(KunQuantError::InvalidTick { .. })
+        ));
+        assert!(matches!(
+            bars.on_trade(&mut sink, 2, 26, 1.0, 1.0),
+            Err(KunQuantError::InvalidTick { .. })
+        ));
+        assert_eq!(bars.flush(&mut sink).unwrap(), 1);
+        assert_eq!(bars.current_bar_start(), None);
+    }
+
+    struct Failing;
+
+    impl BarSink for Failing {
+        fn push_bar(&mut self, _bar: &Bar<'_>) -> Result<()> {
+            Err(KunQuantError::InvalidTick {
+                reason: "sink failed".to_string(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_rejects_zero_interval() {
+        assert!(matches!(
+            BarBuilder::new(2, 0),
+            Err(KunQuantError::InvalidBarConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn test_sink_error_advances_bar() {
+        let mut bars = BarBuilder::new(2, 10).unwrap();
+        bars.on_trade(&mut Failing, 0, 3, 5.0, 1.0).unwrap();
+        // Bars [0, 10) and [10, 20) both fail, the trade still opens [20, 30)
+        assert!(bars.on_trade(&mut Failing, 0, 22, 6.0, 1.0).is_err());
+        assert_eq!(bars.current_bar_start(), Some(20));
+
+        // The failed bars are not emitted again once the sink recovers
+        let mut sink = Collect::default();
+        assert_eq!(bars.on_trade(&mut sink, 0, 23, 7.0, 1.0).unwrap(), 0);
+        assert_eq!(bars.advance_to(&mut sink, 30).unwrap(), 1);
+        let (start, open, close, volume) = &sink.bars[0];
+        assert_eq!(*start, 20);
+        assert_eq!((open[0], close[0], volume[0]), (6.0, 7.0, 2.0));
+    }
+}
@@ -1,8 +1,12 @@
 use crate::buffer::BufferNameMap;
-use crate::error::Result;
+use crate::error::{ErrorContext, KunQuantError, Result, ResultExt};
 use crate::executor::Executor;
 use crate::ffi;
 use crate::library::Module;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Instant;
 
 /// Parameters for batch computation of factor values over time series data.
 ///
@@ -141,9 +145,13 @@ impl BatchParams {
 /// Returns `Ok(())` on successful computation, or an error if:
 /// - Input buffers don't contain required data
 /// - Buffer dimensions don't match the parameters
-/// - The computation encounters runtime errors
+/// - The runtime raises an error, e.g. for a buffer the graph needs but `buffers`
+///   doesn't contain (`RuntimeError`, carrying the runtime's message)
 /// - Memory allocation fails during execution
 ///
+/// A `RuntimeError` carries the module, its library and the time steps being
+/// computed, see [`KunQuantError::context`].
+///
 /// # Examples
 ///
 /// ```rust,no_run
@@ -190,8 +198,8 @@ pub fn run_graph(
     buffers: &BufferNameMap,
     params: &BatchParams,
 ) -> Result<()> {
-    unsafe {
-        ffi::kunRunGraph(
+    let status = unsafe {
+        ffi::kunShimRunGraph(
             executor.handle(),
             module.handle(),
             buffers.handle(),
@@ -199,7 +207,297 @@ pub fn run_graph(
             params.total_time,
             params.cur_time,
             params.length,
-        );
+        )
+    };
+    ffi::check(status, "run_graph").context(|| {
+        ErrorContext::new("run_graph")
+            .with_module(module)
+            .with_time_range(params.cur_time..params.cur_time + params.length)
+    })
+}
+
+/// A shareable flag used to request cancellation of a running [`BatchRunner`].
+///
+/// Clones of a token share the same flag, so one clone can be handed to the
+/// thread running the computation while another is kept by the caller (e.g. a UI
+/// thread) to cancel it.
+///
+/// # Examples
+///
+/// ```rust
+/// use kunquant_rs::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// let handle = token.clone();
+///
+/// handle.cancel();
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a new token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation. All clones of this token observe the request.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Returns `true` if cancellation has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
+
+/// Memory layout of a module's batch outputs, chosen with `output_layout` when the
+/// module is compiled.
+///
+/// The runtime does not report the layout of a module, so code that needs to know
+/// it, like chunked [`BatchRunner`] runs, has it declared by the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputLayout {
+    /// `[time, stock]` (`output_layout="TS"`)
+    TS,
+    /// `[stock / 8, time, 8]` (`output_layout="STs"`, KunQuant's default)
+    STs,
+}
+
+/// Runs a batch computation in time chunks that can be cancelled or time-limited.
+///
+/// [`run_graph`] executes the whole time window in a single runtime call, which
+/// cannot be interrupted. `BatchRunner` splits the window into chunks of
+/// `chunk_len` time steps and checks its cancellation token and deadline before
+/// each chunk, so interactive tools can abort runaway computations.
+///
+/// # Data Layout
+///
+/// Chunking works by offsetting the output buffers by whole time rows, so it
+/// requires modules compiled with the TS output layout (`[time, stock]`), declared
+/// with [`with_output_layout`](BatchRunner::with_output_layout).
+///
+/// Each chunk is a separate runtime call starting at its own `cur_time`. Windows
+/// over module inputs still see the history before the chunk, since input buffers
+/// always cover `total_time` rows. Intermediate results are only computed from the
+/// start of the chunk, so windows over intermediate values (e.g. a rolling mean
+/// of a rank) and recursive operators (e.g. exponential moving averages) restart
+/// at every chunk boundary and differ from an unchunked run. Only chunk modules
+/// whose windows all read inputs directly; a
+/// [`DeterminismChecker`](crate::verify::DeterminismChecker) comparing a chunked
+/// and an unchunked configuration tells whether a module qualifies.
+///
+/// # Partial Results
+///
+/// When a run is stopped early it returns `KunQuantError::Cancelled` or
+/// `KunQuantError::DeadlineExceeded` with the number of completed time steps.
+/// Outputs for those steps are valid; the remaining rows are left untouched.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use kunquant_rs::{BatchParams, BatchRunner, CancellationToken, Executor, Library, OutputLayout};
+/// use std::collections::HashMap;
+/// use std::time::{Duration, Instant};
+///
+/// # fn main() -> kunquant_rs::Result<()> {
+/// let executor = Executor::multi_thread(4)?;
+/// let library = Library::load("factors.so")?;
+/// let module = library.get_module("alpha001")?;
+///
+/// let close = vec![1.0f32; 16 * 1000];
+/// let mut alpha = vec![0.0f32; 16 * 1000];
+/// let inputs = HashMap::from([("close", close.as_slice())]);
+/// let mut outputs = HashMap::from([("alpha001", alpha.as_mut_slice())]);
+///
+/// let token = CancellationToken::new();
+/// let runner = BatchRunner::new(&executor, &module)
+///     .with_output_layout(OutputLayout::TS)
+///     .with_chunk_len(100)
+///     .with_cancellation(token.clone())
+///     .with_deadline(Instant::now() + Duration::from_secs(5));
+///
+/// let params = BatchParams::full_range(16, 1000)?;
+/// runner.run(&inputs, &mut outputs, &params)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct BatchRunner<'a> {
+    executor: &'a Executor,
+    module: &'a Module<'a>,
+    chunk_len: Option<usize>,
+    layout: Option<OutputLayout>,
+    deadline: Option<Instant>,
+    cancellation: Option<CancellationToken>,
+    progress: Option<Arc<AtomicUsize>>,
+}
+
+impl<'a> BatchRunner<'a> {
+    /// Creates a runner that executes `module` on `executor`.
+    ///
+    /// Without further configuration the runner behaves like [`run_graph`]: the
+    /// whole time window is computed in one call.
+    pub fn new(executor: &'a Executor, module: &'a Module<'a>) -> Self {
+        BatchRunner {
+            executor,
+            module,
+            chunk_len: None,
+            layout: None,
+            deadline: None,
+            cancellation: None,
+            progress: None,
+        }
+    }
+
+    /// Splits the computation into chunks of `chunk_len` time steps.
+    ///
+    /// Cancellation and the deadline are checked between chunks, so smaller chunks
+    /// react faster at the cost of more runtime calls. Runs split into more than one
+    /// chunk need the module's output layout declared as [`OutputLayout::TS`] and
+    /// give different results for windows over intermediate values, see
+    /// [Data Layout](BatchRunner#data-layout).
+    pub fn with_chunk_len(mut self, chunk_len: usize) -> Self {
+        self.chunk_len = Some(chunk_len);
+        self
+    }
+
+    /// Declares the output layout the module was compiled with.
+    pub fn with_output_layout(mut self, layout: OutputLayout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    /// Stops the computation with `DeadlineExceeded` once `deadline` has passed.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Stops the computation with `Cancelled` once `token` is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Adds the number of time steps of every completed chunk to `counter`, so
+    /// other threads can follow the progress of the computation.
+    pub fn with_progress(mut self, counter: Arc<AtomicUsize>) -> Self {
+        self.progress = Some(counter);
+        self
+    }
+
+    /// Runs the computation over the time window described by `params`.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - Input buffers keyed by name, each `num_stocks * total_time` long
+    /// * `outputs` - Output buffers keyed by name, each `num_stocks * length` long
+    /// * `params` - Batch parameters defining the computation window and dimensions
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` once every chunk has been computed, or an error if:
+    /// - The chunk length is zero, or the run needs more than one chunk and the
+    ///   output layout was not declared as TS (`InvalidBatchConfig`)
+    /// - A buffer has the wrong size (`BufferSizeMismatch`)
+    /// - The cancellation token was triggered (`Cancelled`)
+    /// - The deadline passed before all chunks were computed (`DeadlineExceeded`)
+    /// - A chunk failed in [`run_graph`] (`RuntimeError`, whose
+    ///   [`context`](KunQuantError::context) has the chunk's time steps)
+    pub fn run(
+        &self,
+        inputs: &HashMap<&str, &[f32]>,
+        outputs: &mut HashMap<&str, &mut [f32]>,
+        params: &BatchParams,
+    ) -> Result<()> {
+        let mut buffers = (|| -> Result<BufferNameMap> {
+            if let Some(chunk_len) = self.chunk_len {
+                check_chunk_len(chunk_len, params.length, self.layout)?;
+            }
+            let mut buffers = BufferNameMap::new()?;
+            for (&name, data) in inputs {
+                check_buffer_len(name, data.len(), params.num_stocks * params.total_time)?;
+                // The runtime only reads input buffers
+                unsafe { buffers.set_buffer(name, data.as_ptr() as *mut f32)? };
+            }
+            for (&name, data) in outputs.iter() {
+                check_buffer_len(name, data.len(), params.num_stocks * params.length)?;
+            }
+            Ok(buffers)
+        })()
+        .context(|| {
+            ErrorContext::new("batch_run")
+                .with_module(self.module)
+                .with_time_range(params.cur_time..params.cur_time + params.length)
+        })?;
+
+        let total = params.length;
+        let chunk_len = self.chunk_len.unwrap_or(total).max(1);
+        let mut completed = 0;
+        while completed < total {
+            if self.cancellation.as_ref().is_some_and(|t| t.is_cancelled()) {
+                return Err(KunQuantError::Cancelled { completed, total });
+            }
+            if self.deadline.is_some_and(|d| Instant::now() >= d) {
+                return Err(KunQuantError::DeadlineExceeded { completed, total });
+            }
+
+            let length = chunk_len.min(total - completed);
+            for (&name, data) in outputs.iter_mut() {
+                let chunk = &mut data[completed * params.num_stocks..];
+                buffers.set_buffer_slice(name, chunk)?;
+            }
+            let chunk_params = BatchParams::new(
+                params.num_stocks,
+                params.total_time,
+                params.cur_time + completed,
+                length,
+            )?;
+            run_graph(self.executor, self.module, &buffers, &chunk_params)?;
+            completed += length;
+            if let Some(progress) = &self.progress {
+                progress.fetch_add(length, Ordering::Relaxed);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rejects chunk lengths that can't be computed by offsetting output rows.
+pub(crate) fn check_chunk_len(
+    chunk_len: usize,
+    length: usize,
+    layout: Option<OutputLayout>,
+) -> Result<()> {
+    if chunk_len == 0 {
+        return Err(KunQuantError::InvalidBatchConfig {
+            reason: "chunk length must be greater than zero".to_string(),
+        });
+    }
+    if chunk_len < length && layout != Some(OutputLayout::TS) {
+        return Err(KunQuantError::InvalidBatchConfig {
+            reason: format!(
+                "splitting {} time steps into chunks of {} requires the TS output layout, \
+                 declared with with_output_layout(OutputLayout::TS)",
+                length, chunk_len
+            ),
+        });
+    }
+    Ok(())
+}
+
+pub(crate) fn check_buffer_len(name: &str, actual: usize, expected: usize) -> Result<()> {
+    if actual != expected {
+        return Err(KunQuantError::BufferSizeMismatch {
+            name: name.to_string(),
+            expected,
+            actual,
+            context: None,
+        });
     }
     Ok(())
 }
@@ -229,4 +527,15 @@ mod tests {
         assert!(BatchParams::new(7, 100, 0, 100).is_ok());
         assert!(BatchParams::new(15, 100, 0, 100).is_ok());
     }
+
+    #[test]
+    fn test_cancellation_token_shared_between_clones() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!token.is_cancelled());
+
+        clone.cancel();
+        assert!(token.is_cancelled());
+        assert!(clone.is_cancelled());
+    }
 }
@@ -0,0 +1,13 @@
+//! Worker executable spawned by `IsolatedRunner` to run a factor library in its
+//! own process.
+
+#[cfg(unix)]
+fn main() {
+    std::process::exit(kunquant_rs::isolated::worker_main());
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("kunquant-worker is only supported on Unix platforms");
+    std::process::exit(2);
+}
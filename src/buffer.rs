@@ -120,9 +120,8 @@ impl BufferNameMap {
         let name_str = name.as_ref();
         let c_name = CString::new(name_str)?;
 
-        unsafe {
-            ffi::kunSetBufferNameMap(self.handle, c_name.as_ptr(), buffer);
-        }
+        let status = unsafe { ffi::kunShimSetBufferNameMap(self.handle, c_name.as_ptr(), buffer) };
+        ffi::check(status, "set_buffer")?;
         self._buffer_names.insert(name_str.to_string(), c_name);
 
         Ok(())
@@ -144,7 +143,7 @@ impl BufferNameMap {
     ///
     /// Returns `Ok(())` on success, or an error if:
     /// - The buffer name contains null bytes
-    /// - The underlying C library call fails
+    /// - The runtime rejects the mapping (`RuntimeError`)
     ///
     /// # Examples
     ///
@@ -0,0 +1,635 @@
+use crate::error::{KunQuantError, Result};
+use crate::owned_stream::OwnedStreamContext;
+use crate::stream::{StreamHandle, StreamInputs};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc, oneshot};
+
+/// Owned input data of one streaming time step, sent to a [`StreamEngine`].
+///
+/// Unlike [`StreamInputs`] the data is copied, so a tick can cross channels and
+/// threads.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use kunquant_rs::engine::Tick;
+///
+/// let tick = Tick::new()
+///     .with("close", vec![10.0; 16])
+///     .with("open", vec![9.5; 16]);
+/// assert_eq!(tick.len(), 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tick {
+    inputs: Vec<(String, Vec<f32>)>,
+}
+
+impl Tick {
+    /// Creates an empty tick.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the data of input `name`, replacing any previous data for it.
+    pub fn insert<N: AsRef<str>, D: Into<Vec<f32>>>(&mut self, name: N, data: D) {
+        let name = name.as_ref();
+        let data = data.into();
+        match self.inputs.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => *existing = data,
+            None => self.inputs.push((name.to_string(), data)),
+        }
+    }
+
+    /// Builder-style variant of [`insert`](Tick::insert).
+    pub fn with<N: AsRef<str>, D: Into<Vec<f32>>>(mut self, name: N, data: D) -> Self {
+        self.insert(name, data);
+        self
+    }
+
+    /// Returns the data of input `name`.
+    pub fn get(&self, name: &str) -> Option<&[f32]> {
+        self.inputs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, data)| data.as_slice())
+    }
+
+    /// Iterates over the inputs in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[f32])> {
+        self.inputs
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.as_slice()))
+    }
+
+    /// Number of inputs in the tick.
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Returns `true` if the tick has no inputs.
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    // Folds a newer tick into this one; inputs present in both take the newer data
+    fn conflate(&mut self, newer: Tick) {
+        for (name, data) in newer.inputs {
+            self.insert(name, data);
+        }
+    }
+}
+
+impl From<&StreamInputs<'_>> for Tick {
+    fn from(inputs: &StreamInputs<'_>) -> Self {
+        Tick {
+            inputs: inputs
+                .iter()
+                .map(|(name, data)| (name.to_string(), data.to_vec()))
+                .collect(),
+        }
+    }
+}
+
+/// How a [`StreamEngine`] reacts when ticks arrive faster than it processes them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backpressure {
+    /// Queue up to the builder's queue capacity and make senders wait when it is
+    /// full. No tick is ever dropped.
+    #[default]
+    Block,
+    /// Never make senders wait; keep at most `max_backlog` unprocessed ticks and
+    /// drop the oldest ones beyond that.
+    DropOldest { max_backlog: usize },
+    /// Never make senders wait; fold all unprocessed ticks into one, where each
+    /// input takes its most recent data. Suited to snapshot-style feeds where only
+    /// the latest state matters.
+    Conflate,
+}
+
+/// Outputs of one processed tick, published on the engine's broadcast channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickOutput {
+    tick: u64,
+    outputs: Vec<(String, Vec<f32>)>,
+}
+
+impl TickOutput {
+    /// Zero-based index of the tick in the stream, see
+    /// [`StreamContext::ticks_run`](crate::StreamContext::ticks_run).
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Returns the values of output `name`.
+    pub fn output(&self, name: &str) -> Option<&[f32]> {
+        self.outputs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, data)| data.as_slice())
+    }
+
+    /// Iterates over the published outputs.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[f32])> {
+        self.outputs
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.as_slice()))
+    }
+}
+
+/// Final state of a [`StreamEngine`], returned by [`StreamEngine::shutdown`].
+pub struct EngineShutdown {
+    /// The stream context, positioned after the last processed tick
+    pub context: OwnedStreamContext,
+    /// Checkpoint written by `save_state()`, or `None` if the context has no state
+    /// journal enabled
+    pub state: Option<Vec<u8>>,
+    /// Number of ticks pushed and run successfully
+    pub ticks_processed: u64,
+    /// Number of ticks discarded by the backpressure policy
+    pub ticks_dropped: u64,
+    /// Number of ticks that failed to push or run, including ticks that were queued
+    /// behind the shutdown request and discarded with `EngineStopped`
+    pub errors: u64,
+    /// The most recent error, including a failure to save the final state
+    pub last_error: Option<KunQuantError>,
+}
+
+enum Command {
+    Tick(Tick),
+    Shutdown(oneshot::Sender<EngineShutdown>),
+}
+
+#[derive(Clone)]
+enum CommandSender {
+    Bounded(mpsc::Sender<Command>),
+    Unbounded(mpsc::UnboundedSender<Command>),
+}
+
+enum CommandReceiver {
+    Bounded(mpsc::Receiver<Command>),
+    Unbounded(mpsc::UnboundedReceiver<Command>),
+}
+
+impl CommandReceiver {
+    fn blocking_recv(&mut self) -> Option<Command> {
+        match self {
+            CommandReceiver::Bounded(rx) => rx.blocking_recv(),
+            CommandReceiver::Unbounded(rx) => rx.blocking_recv(),
+        }
+    }
+
+    fn try_recv(&mut self) -> Option<Command> {
+        match self {
+            CommandReceiver::Bounded(rx) => rx.try_recv().ok(),
+            CommandReceiver::Unbounded(rx) => rx.try_recv().ok(),
+        }
+    }
+
+    /// Rejects further sends; commands already queued can still be received.
+    fn close(&mut self) {
+        match self {
+            CommandReceiver::Bounded(rx) => rx.close(),
+            CommandReceiver::Unbounded(rx) => rx.close(),
+        }
+    }
+}
+
+/// Sending half of a [`StreamEngine`]'s tick channel.
+///
+/// Cheap to clone; every feed task can hold its own sender. Under
+/// [`Backpressure::Block`] sends wait for queue capacity, under the dropping
+/// policies they never wait.
+#[derive(Clone)]
+pub struct TickSender {
+    commands: CommandSender,
+    capacity: usize,
+}
+
+impl TickSender {
+    /// Sends a tick, waiting for queue capacity under [`Backpressure::Block`].
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if the engine has stopped (`EngineStopped`).
+    pub async fn send(&self, tick: Tick) -> Result<()> {
+        self.send_command(Command::Tick(tick)).await
+    }
+
+    /// Sends a tick without waiting.
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if the engine has stopped (`EngineStopped`) or, under
+    /// [`Backpressure::Block`], if the queue is full (`EngineQueueFull`).
+    pub fn try_send(&self, tick: Tick) -> Result<()> {
+        match &self.commands {
+            CommandSender::Bounded(tx) => tx.try_send(Command::Tick(tick)).map_err(|e| match e {
+                mpsc::error::TrySendError::Full(_) => KunQuantError::EngineQueueFull {
+                    capacity: self.capacity,
+                },
+                mpsc::error::TrySendError::Closed(_) => KunQuantError::EngineStopped,
+            }),
+            CommandSender::Unbounded(tx) => tx
+                .send(Command::Tick(tick))
+                .map_err(|_| KunQuantError::EngineStopped),
+        }
+    }
+
+    /// Sends a tick from synchronous code, blocking the thread for queue capacity.
+    ///
+    /// Must not be called from within an async runtime.
+    pub fn blocking_send(&self, tick: Tick) -> Result<()> {
+        self.blocking_send_command(Command::Tick(tick))
+    }
+
+    async fn send_command(&self, command: Command) -> Result<()> {
+        match &self.commands {
+            CommandSender::Bounded(tx) => tx.send(command).await.map_err(|_| ()),
+            CommandSender::Unbounded(tx) => tx.send(command).map_err(|_| ()),
+        }
+        .map_err(|_| KunQuantError::EngineStopped)
+    }
+
+    fn blocking_send_command(&self, command: Command) -> Result<()> {
+        match &self.commands {
+            CommandSender::Bounded(tx) => tx.blocking_send(command).map_err(|_| ()),
+            CommandSender::Unbounded(tx) => tx.send(command).map_err(|_| ()),
+        }
+        .map_err(|_| KunQuantError::EngineStopped)
+    }
+}
+
+/// Configures and spawns a [`StreamEngine`], see [`StreamEngine::builder`].
+pub struct StreamEngineBuilder {
+    context: OwnedStreamContext,
+    outputs: Vec<String>,
+    backpressure: Backpressure,
+    queue_capacity: usize,
+    broadcast_capacity: usize,
+}
+
+impl StreamEngineBuilder {
+    /// Sets the outputs published after every tick. Defaults to none, in which case
+    /// subscribers only learn which ticks were processed.
+    pub fn with_outputs<N: AsRef<str>>(mut self, names: &[N]) -> Self {
+        self.outputs = names.iter().map(|n| n.as_ref().to_string()).collect();
+        self
+    }
+
+    /// Sets the backpressure policy. Defaults to [`Backpressure::Block`].
+    pub fn with_backpressure(mut self, backpressure: Backpressure) -> Self {
+        self.backpressure = backpressure;
+        self
+    }
+
+    /// Sets the number of ticks queued before senders wait under
+    /// [`Backpressure::Block`]. Defaults to 1024; values below 1 are raised to 1.
+    pub fn with_queue_capacity(mut self, capacity: usize) -> Self {
+        self.queue_capacity = capacity.max(1);
+        self
+    }
+
+    /// Sets how many published outputs a slow subscriber may lag behind before it
+    /// misses some (see `tokio::sync::broadcast`). Defaults to 1024.
+    pub fn with_broadcast_capacity(mut self, capacity: usize) -> Self {
+        self.broadcast_capacity = capacity.max(1);
+        self
+    }
+
+    /// Starts the engine on a dedicated thread.
+    ///
+    /// Does not need to be called from within a tokio runtime.
+    ///
+    /// # Returns
+    ///
+    /// Returns the engine, or an error if an output name is not found or the
+    /// engine thread cannot be spawned.
+    pub fn spawn(mut self) -> Result<StreamEngine> {
+        let outputs = self
+            .outputs
+            .into_iter()
+            .map(|name| {
+                let handle = self.context.resolve(&name)?;
+                Ok((name, handle))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let (commands, receiver) = match self.backpressure {
+            Backpressure::Block => {
+                let (tx, rx) = mpsc::channel(self.queue_capacity);
+                (CommandSender::Bounded(tx), CommandReceiver::Bounded(rx))
+            }
+            // The engine drains the channel before every tick, so it only holds
+            // what arrives while one tick is being computed
+            Backpressure::DropOldest { .. } | Backpressure::Conflate => {
+                let (tx, rx) = mpsc::unbounded_channel();
+                (CommandSender::Unbounded(tx), CommandReceiver::Unbounded(rx))
+            }
+        };
+        let (publisher, _) = broadcast::channel(self.broadcast_capacity);
+
+        let worker = Worker {
+            context: self.context,
+            inputs: HashMap::new(),
+            outputs,
+            publisher: publisher.clone(),
+            backpressure: self.backpressure,
+            ticks_processed: 0,
+            ticks_dropped: 0,
+            errors: 0,
+            last_error: None,
+        };
+        std::thread::Builder::new()
+            .name("kunquant-stream-engine".to_string())
+            .spawn(move || worker.run(receiver))?;
+
+        Ok(StreamEngine {
+            sender: TickSender {
+                commands,
+                capacity: self.queue_capacity,
+            },
+            publisher,
+        })
+    }
+}
+
+/// A stream context driven by channels.
+///
+/// The engine owns an [`OwnedStreamContext`] on a dedicated thread. Ticks sent
+/// through a [`TickSender`] are pushed and run in order, and the configured outputs
+/// of every processed tick are published as an `Arc<TickOutput>` on a
+/// `tokio::sync::broadcast` channel. How a backlog of unprocessed ticks is handled
+/// is set by [`Backpressure`].
+///
+/// A tick that fails to push or run is counted and skipped; the engine keeps
+/// going. [`shutdown`](StreamEngine::shutdown) processes every tick sent before it,
+/// stops the engine and returns the context together with a state checkpoint.
+/// Dropping the engine without shutting it down stops it once every
+/// [`TickSender`] is dropped as well.
+///
+/// Requires the `async` feature.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use kunquant_rs::engine::{Backpressure, StreamEngine, Tick};
+/// use kunquant_rs::{Executor, Library, OwnedStreamContext};
+/// use std::sync::Arc;
+///
+/// # async fn example() -> kunquant_rs::Result<()> {
+/// let executor = Arc::new(Executor::single_thread()?);
+/// let library = Arc::new(Library::load("factors.so")?);
+/// let mut context = OwnedStreamContext::new(executor, library, "alpha_stream", 16)?;
+/// context.enable_state_journal(240);
+/// context.set_lookback(240);
+///
+/// let engine = StreamEngine::builder(context)
+///     .with_outputs(&["alpha"])
+///     .with_backpressure(Backpressure::DropOldest { max_backlog: 8 })
+///     .spawn()?;
+/// let mut outputs = engine.subscribe();
+///
+/// engine.send(Tick::new().with("close", vec![10.0; 16])).await?;
+/// let output = outputs.recv().await.unwrap();
+/// println!("tick {}: {:?}", output.tick(), output.output("alpha"));
+///
+/// let shutdown = engine.shutdown().await?;
+/// std::fs::write("alpha.state", shutdown.state.unwrap())?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct StreamEngine {
+    sender: TickSender,
+    publisher: broadcast::Sender<Arc<TickOutput>>,
+}
+
+impl StreamEngine {
+    /// Starts configuring an engine for `context`.
+    ///
+    /// Enable the state journal on the context beforehand to get a checkpoint on
+    /// shutdown.
+    pub fn builder(context: OwnedStreamContext) -> StreamEngineBuilder {
+        StreamEngineBuilder {
+            context,
+            outputs: Vec::new(),
+            backpressure: Backpressure::Block,
+            queue_capacity: 1024,
+            broadcast_capacity: 1024,
+        }
+    }
+
+    /// Returns a new sender for ticks.
+    pub fn sender(&self) -> TickSender {
+        self.sender.clone()
+    }
+
+    /// Sends a tick, see [`TickSender::send`].
+    pub async fn send(&self, tick: Tick) -> Result<()> {
+        self.sender.send(tick).await
+    }
+
+    /// Subscribes to the outputs of ticks processed from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<TickOutput>> {
+        self.publisher.subscribe()
+    }
+
+    /// Stops the engine after processing every tick sent before this call.
+    ///
+    /// Ticks sent afterwards are rejected with `EngineStopped`; ticks that were
+    /// already queued behind the shutdown request are discarded and counted in
+    /// [`EngineShutdown::errors`]. Under a dropping backpressure policy the policy
+    /// still applies to the remaining backlog.
+    ///
+    /// # Returns
+    ///
+    /// Returns the final context, state checkpoint and counters, or
+    /// `EngineStopped` if the engine thread is gone (e.g. it panicked).
+    pub async fn shutdown(self) -> Result<EngineShutdown> {
+        let (reply, result) = oneshot::channel();
+        self.sender.send_command(Command::Shutdown(reply)).await?;
+        result.await.map_err(|_| KunQuantError::EngineStopped)
+    }
+
+    /// Variant of [`shutdown`](StreamEngine::shutdown) for synchronous code.
+    ///
+    /// Must not be called from within an async runtime.
+    pub fn blocking_shutdown(self) -> Result<EngineShutdown> {
+        let (reply, result) = oneshot::channel();
+        self.sender
+            .blocking_send_command(Command::Shutdown(reply))?;
+        result
+            .blocking_recv()
+            .map_err(|_| KunQuantError::EngineStopped)
+    }
+}
+
+struct Worker {
+    context: OwnedStreamContext,
+    // Input handles, resolved on first use
+    inputs: HashMap<String, StreamHandle>,
+    outputs: Vec<(String, StreamHandle)>,
+    publisher: broadcast::Sender<Arc<TickOutput>>,
+    backpressure: Backpressure,
+    ticks_processed: u64,
+    ticks_dropped: u64,
+    errors: u64,
+    last_error: Option<KunQuantError>,
+}
+
+impl Worker {
+    fn run(mut self, mut commands: CommandReceiver) {
+        let mut backlog = VecDeque::new();
+        let mut shutdown = None;
+        while shutdown.is_none() {
+            if backlog.is_empty() {
+                match commands.blocking_recv() {
+                    Some(Command::Tick(tick)) => self.enqueue(&mut backlog, tick),
+                    Some(Command::Shutdown(reply)) => shutdown = Some(reply),
+                    // Every sender is gone
+                    None => break,
+                }
+            }
+            if self.backpressure != Backpressure::Block {
+                while shutdown.is_none()
+                    && let Some(command) = commands.try_recv()
+                {
+                    match command {
+                        Command::Tick(tick) => self.enqueue(&mut backlog, tick),
+                        Command::Shutdown(reply) => shutdown = Some(reply),
+                    }
+                }
+            }
+            if let Some(tick) = backlog.pop_front() {
+                self.process(&tick);
+            }
+        }
+        // Senders fail from now on; whatever raced the shutdown request is rejected
+        commands.close();
+        while let Some(command) = commands.try_recv() {
+            if let Command::Tick(_) = command {
+                self.errors += 1;
+                self.last_error = Some(KunQuantError::EngineStopped);
+            }
+        }
+        while let Some(tick) = backlog.pop_front() {
+            self.process(&tick);
+        }
+
+        if let Some(reply) = shutdown {
+            let _ = reply.send(self.finish());
+        }
+    }
+
+    fn enqueue(&mut self, backlog: &mut VecDeque<Tick>, tick: Tick) {
+        match self.backpressure {
+            Backpressure::Block => backlog.push_back(tick),
+            Backpressure::DropOldest { max_backlog } => {
+                backlog.push_back(tick);
+                while backlog.len() > max_backlog.max(1) {
+                    backlog.pop_front();
+                    self.ticks_dropped += 1;
+                }
+            }
+            Backpressure::Conflate => match backlog.back_mut() {
+                Some(pending) => {
+                    pending.conflate(tick);
+                    self.ticks_dropped += 1;
+                }
+                None => backlog.push_back(tick),
+            },
+        }
+    }
+
+    fn process(&mut self, tick: &Tick) {
+        match self.step(tick) {
+            Ok(output) => {
+                self.ticks_processed += 1;
+                // No subscribers is not an error
+                let _ = self.publisher.send(Arc::new(output));
+            }
+            Err(e) => {
+                self.errors += 1;
+                self.last_error = Some(e);
+            }
+        }
+    }
+
+    fn step(&mut self, tick: &Tick) -> Result<TickOutput> {
+        let result = self.run_tick(tick);
+        if result.is_err() {
+            // Inputs of a failed tick must not count for the next one
+            self.context.discard_pushes();
+        }
+        result
+    }
+
+    fn run_tick(&mut self, tick: &Tick) -> Result<TickOutput> {
+        self.push_tick(tick)?;
+        self.context.run()?;
+
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|(name, handle)| Ok((name.clone(), self.context.output(*handle)?.to_vec())))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(TickOutput {
+            tick: self.context.ticks_run() - 1,
+            outputs,
+        })
+    }
+
+    fn push_tick(&mut self, tick: &Tick) -> Result<()> {
+        for (name, data) in tick.iter() {
+            let handle = match self.inputs.get(name) {
+                Some(handle) => *handle,
+                None => {
+                    let handle = self.context.resolve(name)?;
+                    self.inputs.insert(name.to_string(), handle);
+                    handle
+                }
+            };
+            self.context.push(handle, data)?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> EngineShutdown {
+        let state = if self.context.has_state_journal() {
+            let mut state = Vec::new();
+            match self.context.save_state(&mut state) {
+                Ok(()) => Some(state),
+                Err(e) => {
+                    self.last_error = Some(e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        EngineShutdown {
+            context: self.context,
+            state,
+            ticks_processed: self.ticks_processed,
+            ticks_dropped: self.ticks_dropped,
+            errors: self.errors,
+            last_error: self.last_error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_conflate_keeps_latest_inputs() {
+        let mut pending = Tick::new().with("close", [1.0]).with("volume", [5.0]);
+        pending.conflate(Tick::new().with("close", [2.0]).with("open", [1.5]));
+
+        assert_eq!(pending.len(), 3);
+        assert_eq!(pending.get("close"), Some(&[2.0][..]));
+        assert_eq!(pending.get("volume"), Some(&[5.0][..]));
+        assert_eq!(pending.get("open"), Some(&[1.5][..]));
+    }
+}
@@ -1,3 +1,7 @@
+use crate::library::Module;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 /// Comprehensive error types for KunQuant operations.
@@ -46,19 +50,58 @@ pub enum KunQuantError {
     #[error("Failed to create executor")]
     ExecutorCreationFailed,
 
-    /// Failed to load a KunQuant factor library from the specified path.
+    /// An executor configuration was rejected before creating the executor.
     ///
-    /// This error indicates that the library file could not be loaded, either
-    /// because it doesn't exist, isn't accessible, or isn't a valid KunQuant library.
+    /// **Common Causes:**
+    /// - A thread count of zero, or one too large for the runtime
+    /// - An unparsable thread count in the environment variable given to
+    ///   `ExecutorBuilder::with_threads_from_env`
+    /// - An empty CPU set, a CPU id beyond the platform limit, or CPU affinity on a
+    ///   platform other than Linux
+    /// - A thread name containing null bytes
+    #[error("Invalid executor configuration: {reason}")]
+    InvalidExecutorConfig { reason: String },
+
+    /// No factor library exists at the specified path.
+    ///
+    /// **Solution:** Check the path (relative paths are resolved against the current
+    /// working directory) and that the library has been compiled.
+    #[error("Library not found: {path}")]
+    LibraryNotFound { path: String },
+
+    /// The file at the specified path exists but could not be loaded as a KunQuant
+    /// factor library.
+    ///
+    /// `reason` is the dynamic loader's message (as reported by `dlerror()`), or a
+    /// description of why the runtime rejected the library.
     ///
     /// **Common Causes:**
-    /// - File doesn't exist at the specified path
     /// - Insufficient permissions to read the file
-    /// - Library compiled for incompatible architecture
-    /// - Missing shared library dependencies
-    /// - Corrupted or invalid library file
-    #[error("Failed to load library: {path}")]
-    LibraryLoadFailed { path: String },
+    /// - Not a shared library, or compiled for an incompatible architecture
+    ///   (e.g. "invalid ELF header", "wrong ELF class")
+    /// - Missing shared library dependencies or undefined symbols
+    /// - A shared library that isn't a KunQuant factor library, or was built for a
+    ///   different runtime version
+    #[error("Failed to load library {path}: {reason}")]
+    LibraryLoadFailed { path: String, reason: String },
+
+    /// The factor library was built for a different KunQuant runtime ABI than the
+    /// loaded `libKunRuntime`, and was rejected before the runtime used it.
+    ///
+    /// A side without a `kunquant_abi_version` tag is reported as `"untagged"`. See
+    /// [`runtime_version`](crate::runtime_version).
+    ///
+    /// **Solution:** Recompile the factor library with the KunQuant release the
+    /// runtime belongs to, or link against the runtime the library was built for.
+    #[error(
+        "Incompatible library: built for runtime ABI {library_version}, but the loaded runtime is {runtime_version}{}",
+        in_context(.context)
+    )]
+    IncompatibleLibrary {
+        library_version: String,
+        runtime_version: String,
+        context: Option<Box<ErrorContext>>,
+    },
 
     /// The requested module was not found in the loaded library.
     ///
@@ -71,8 +114,11 @@ pub enum KunQuantError {
     /// - Module not included during library compilation
     /// - Library compiled with different module names
     /// - Using wrong library file
-    #[error("Module not found: {name}")]
-    ModuleNotFound { name: String },
+    #[error("Module not found: {name}{}", in_context(.context))]
+    ModuleNotFound {
+        name: String,
+        context: Option<Box<ErrorContext>>,
+    },
 
     /// Failed to create a buffer name map for data management.
     ///
@@ -95,6 +141,7 @@ pub enum KunQuantError {
     /// - Buffer name contains null bytes ('\0')
     /// - Empty buffer name
     /// - Non-UTF8 characters in buffer name
+    /// - A name pushed to a `StreamGroup` that isn't one of its declared inputs
     #[error("Invalid buffer name: {name}")]
     InvalidBufferName { name: String },
 
@@ -104,11 +151,15 @@ pub enum KunQuantError {
     /// expected size based on the number of stocks and time points.
     ///
     /// **Expected Size:** `num_stocks * total_time` for time-series data
-    #[error("Buffer size mismatch for '{name}': expected {expected}, got {actual}")]
+    #[error(
+        "Buffer size mismatch for '{name}': expected {expected}, got {actual}{}",
+        in_context(.context)
+    )]
     BufferSizeMismatch {
         name: String,
         expected: usize,
         actual: usize,
+        context: Option<Box<ErrorContext>>,
     },
 
     /// Failed to create a streaming computation context.
@@ -132,8 +183,74 @@ pub enum KunQuantError {
     /// - Buffer name doesn't match module definition
     /// - Buffer not properly initialized in streaming context
     /// - Typo in buffer name (names are case-sensitive)
-    #[error("Buffer handle not found: {name}")]
-    BufferHandleNotFound { name: String },
+    #[error("Buffer handle not found: {name}{}", in_context(.context))]
+    BufferHandleNotFound {
+        name: String,
+        context: Option<Box<ErrorContext>>,
+    },
+
+    /// A `StreamHandle` was used with a streaming context other than the one that
+    /// resolved it.
+    ///
+    /// Buffer handles are only meaningful for the context (and module) they were
+    /// resolved from, so they are checked on every use.
+    ///
+    /// **Solution:** Resolve handles separately for each `StreamContext`.
+    #[error(
+        "Stream handle #{index} belongs to a different stream context{}",
+        in_context(.context)
+    )]
+    ForeignStreamHandle {
+        index: usize,
+        context: Option<Box<ErrorContext>>,
+    },
+
+    /// A `StreamGroup` member was added under a name that is already taken.
+    ///
+    /// **Solution:** Use `StreamGroup::add` with a distinct name when the same module
+    /// is added more than once.
+    #[error("Stream group already has a member named '{name}'")]
+    DuplicateStreamMember { name: String },
+
+    /// A `StreamGroup` has no member with the requested name.
+    ///
+    /// **Solution:** Use one of the names listed by `StreamGroup::members`; members
+    /// added with `add_module` are named after their module.
+    #[error("Stream group has no member named '{name}'")]
+    UnknownStreamMember { name: String },
+
+    /// One or more members of a `StreamGroup` failed to run a tick.
+    ///
+    /// `failures` holds the name and error of every failed member, in member order.
+    /// The other members completed the tick.
+    #[error("Stream group members failed: {}", list_failures(.failures))]
+    StreamGroupFailed {
+        failures: Vec<(String, KunQuantError)>,
+    },
+
+    /// `StreamContext::run()` was called before every required input was pushed.
+    ///
+    /// The streaming context tracks which inputs were pushed since the last `run()`.
+    /// Running with a missing input would silently reuse the previous tick's data, so
+    /// the run is rejected and nothing is computed.
+    ///
+    /// **Common Causes:**
+    /// - Forgetting to push one of the module inputs in a tick
+    /// - Pushing to a misspelled input name that happens to exist as another buffer
+    #[error("Stream inputs not pushed since last run: {}", missing.join(", "))]
+    MissingStreamInputs { missing: Vec<String> },
+
+    /// An output was read by name from a stream context with a
+    /// `MissingDataPolicy::Suspend` input before its handle was resolved.
+    ///
+    /// The context holds the previous values of suspended stocks only for outputs
+    /// it knows about, and the runtime's own buffer has already advanced them.
+    ///
+    /// **Solution:** Resolve every output read from the context with
+    /// `StreamContext::resolve` (or register its history or subscription) before the
+    /// first `run()`.
+    #[error("Stream output '{name}' must be resolved before it is read with suspended stocks")]
+    UnresolvedStreamOutput { name: String },
 
     /// A null pointer was encountered during C library interaction.
     ///
@@ -148,6 +265,24 @@ pub enum KunQuantError {
     #[error("Null pointer encountered")]
     NullPointer,
 
+    /// The KunQuant runtime raised an error while performing `operation`.
+    ///
+    /// `message` is the runtime's own description (the text of the C++ exception).
+    /// Crashes that abort inside the runtime cannot be reported this way; run
+    /// untrusted libraries with [`IsolatedRunner`](crate::isolated::IsolatedRunner)
+    /// to survive them.
+    ///
+    /// **Common Causes:**
+    /// - A buffer name the module doesn't know, or a buffer the graph needs is not set
+    /// - Batch parameters out of the range the module was compiled for
+    /// - A library compiled against an incompatible runtime
+    #[error("KunQuant runtime error in {operation}: {message}{}", in_context(.context))]
+    RuntimeError {
+        operation: String,
+        message: String,
+        context: Option<Box<ErrorContext>>,
+    },
+
     /// Error converting Rust string to C string (contains null bytes).
     ///
     /// This error occurs when a Rust string contains null bytes ('\0'),
@@ -168,6 +303,322 @@ pub enum KunQuantError {
     /// - Memory corruption
     #[error("UTF-8 conversion error: {0}")]
     Utf8Conversion(#[from] std::str::Utf8Error),
+
+    /// A symbol is not part of the universe of a `SymbolStream`.
+    ///
+    /// **Solution:** Add the symbol with `SymbolStream::add_symbol` before pushing
+    /// data for it or reading its outputs.
+    #[error("Unknown symbol: {symbol}")]
+    UnknownSymbol { symbol: String },
+
+    /// A trade or quote passed to a `BarBuilder` cannot be aggregated.
+    ///
+    /// **Common Causes:**
+    /// - The stock index is not below the builder's number of stocks
+    /// - The tick is older than the bar currently being built (out-of-order feed)
+    #[error("Invalid tick: {reason}")]
+    InvalidTick { reason: String },
+
+    /// A `BarBuilder` was configured with parameters it cannot aggregate with.
+    ///
+    /// **Common Causes:**
+    /// - A bar interval of zero
+    ///
+    /// **Solution:** Pass a positive interval in the unit of the tick timestamps.
+    #[error("Invalid bar configuration: {reason}")]
+    InvalidBarConfig { reason: String },
+
+    /// A saved stream state could not be written or does not fit the target context.
+    ///
+    /// **Common Causes:**
+    /// - `save_state()` called without enabling the state journal first
+    /// - `save_state()` called after the journal dropped ticks, with no lookback
+    ///   declared or a lookback longer than the journal
+    /// - The state was saved for a different module or library build
+    /// - The state data is truncated or not a stream state at all
+    #[error("Invalid stream state: {reason}")]
+    InvalidStreamState { reason: String },
+
+    /// Historical input data for a stream replay could not be parsed.
+    ///
+    /// `line` is the 1-based line number in the input file.
+    ///
+    /// **Common Causes:**
+    /// - Missing or malformed `time,stock,...` header
+    /// - A row with a different number of fields than the header
+    /// - A stock index outside `0..num_stocks` or a non-numeric value
+    #[error("Invalid replay data at line {line}: {reason}")]
+    InvalidReplayData { line: usize, reason: String },
+
+    /// An I/O error occurred while reading or writing persisted data.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// An isolated worker process died or was killed before finishing its job.
+    ///
+    /// This error is only produced by [`IsolatedRunner`](crate::isolated::IsolatedRunner).
+    /// The parent process is unaffected; the worker's outputs are discarded.
+    ///
+    /// **Common Causes:**
+    /// - The factor library crashed (e.g. segmentation fault) inside `kunRunGraph`
+    /// - The computation exceeded the configured timeout
+    /// - The worker process could not be spawned or shared memory could not be mapped
+    /// - The worker panicked or exited without reporting an error
+    #[error("Worker process crashed: {reason}")]
+    WorkerCrashed { reason: String },
+
+    /// A chunked batch computation was cancelled through its cancellation token.
+    ///
+    /// `completed` time steps (starting at the requested `cur_time`) have been
+    /// computed and written to the output buffers; the remaining outputs are
+    /// left untouched.
+    #[error("Batch computation cancelled after {completed} of {total} time steps")]
+    Cancelled { completed: usize, total: usize },
+
+    /// A chunked batch computation did not finish before its deadline.
+    ///
+    /// `completed` time steps (starting at the requested `cur_time`) have been
+    /// computed and written to the output buffers; the remaining outputs are
+    /// left untouched.
+    #[error("Batch computation exceeded its deadline after {completed} of {total} time steps")]
+    DeadlineExceeded { completed: usize, total: usize },
+
+    /// A batch runner or job configuration was rejected before computing anything.
+    ///
+    /// **Common Causes:**
+    /// - A chunk length of zero
+    /// - Chunked or sliced runs of a module not declared with the TS output layout,
+    ///   see [`OutputLayout`](crate::batch::OutputLayout)
+    #[error("Invalid batch configuration: {reason}")]
+    InvalidBatchConfig { reason: String },
+
+    /// A tick was sent to an `engine::StreamEngine` that is no longer running.
+    ///
+    /// **Common Causes:**
+    /// - The engine was shut down or dropped while senders were still in use
+    /// - The engine thread panicked
+    #[error("Stream engine has stopped")]
+    EngineStopped,
+
+    /// A tick could not be queued without waiting.
+    ///
+    /// Only returned by `TickSender::try_send` under `Backpressure::Block` when all
+    /// `capacity` queue slots are taken.
+    ///
+    /// **Solution:** Use `send().await` or `blocking_send()` to wait for capacity,
+    /// or pick a dropping backpressure policy.
+    #[error("Stream engine queue is full ({capacity} ticks)")]
+    EngineQueueFull { capacity: usize },
+}
+
+impl KunQuantError {
+    /// Returns the stable, machine-readable code of the error, e.g.
+    /// `"MODULE_NOT_FOUND"`.
+    ///
+    /// Codes never change once released and are meant for alerting and metrics;
+    /// the display message is meant for humans and may be reworded. Errors with
+    /// context have the code of the underlying error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            KunQuantError::ExecutorCreationFailed => "EXECUTOR_CREATION_FAILED",
+            KunQuantError::InvalidExecutorConfig { .. } => "INVALID_EXECUTOR_CONFIG",
+            KunQuantError::LibraryNotFound { .. } => "LIBRARY_NOT_FOUND",
+            KunQuantError::LibraryLoadFailed { .. } => "LIBRARY_LOAD_FAILED",
+            KunQuantError::IncompatibleLibrary { .. } => "INCOMPATIBLE_LIBRARY",
+            KunQuantError::ModuleNotFound { .. } => "MODULE_NOT_FOUND",
+            KunQuantError::BufferNameMapCreationFailed => "BUFFER_NAME_MAP_CREATION_FAILED",
+            KunQuantError::InvalidBufferName { .. } => "INVALID_BUFFER_NAME",
+            KunQuantError::BufferSizeMismatch { .. } => "BUFFER_SIZE_MISMATCH",
+            KunQuantError::StreamCreationFailed => "STREAM_CREATION_FAILED",
+            KunQuantError::BufferHandleNotFound { .. } => "BUFFER_HANDLE_NOT_FOUND",
+            KunQuantError::ForeignStreamHandle { .. } => "FOREIGN_STREAM_HANDLE",
+            KunQuantError::DuplicateStreamMember { .. } => "DUPLICATE_STREAM_MEMBER",
+            KunQuantError::UnknownStreamMember { .. } => "UNKNOWN_STREAM_MEMBER",
+            KunQuantError::StreamGroupFailed { .. } => "STREAM_GROUP_FAILED",
+            KunQuantError::MissingStreamInputs { .. } => "MISSING_STREAM_INPUTS",
+            KunQuantError::UnresolvedStreamOutput { .. } => "UNRESOLVED_STREAM_OUTPUT",
+            KunQuantError::NullPointer => "NULL_POINTER",
+            KunQuantError::RuntimeError { .. } => "RUNTIME_ERROR",
+            KunQuantError::StringConversion(_) => "STRING_CONVERSION",
+            KunQuantError::Utf8Conversion(_) => "UTF8_CONVERSION",
+            KunQuantError::UnknownSymbol { .. } => "UNKNOWN_SYMBOL",
+            KunQuantError::InvalidTick { .. } => "INVALID_TICK",
+            KunQuantError::InvalidBarConfig { .. } => "INVALID_BAR_CONFIG",
+            KunQuantError::InvalidStreamState { .. } => "INVALID_STREAM_STATE",
+            KunQuantError::InvalidReplayData { .. } => "INVALID_REPLAY_DATA",
+            KunQuantError::Io(_) => "IO",
+            KunQuantError::WorkerCrashed { .. } => "WORKER_CRASHED",
+            KunQuantError::Cancelled { .. } => "CANCELLED",
+            KunQuantError::DeadlineExceeded { .. } => "DEADLINE_EXCEEDED",
+            KunQuantError::InvalidBatchConfig { .. } => "INVALID_BATCH_CONFIG",
+            KunQuantError::EngineStopped => "ENGINE_STOPPED",
+            KunQuantError::EngineQueueFull { .. } => "ENGINE_QUEUE_FULL",
+        }
+    }
+
+    /// Returns `true` if the error is transient, so that retrying the same call
+    /// unchanged may succeed.
+    ///
+    /// Retryable errors are a full engine queue, a missed deadline, and interrupted
+    /// or timed out I/O. Everything else is caused by the inputs, the factor library
+    /// or the installation and fails again on retry. This includes a crashed
+    /// isolated worker: the same library fed the same inputs crashes the same way.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            KunQuantError::EngineQueueFull { .. } | KunQuantError::DeadlineExceeded { .. } => true,
+            KunQuantError::Io(e) => matches!(
+                e.kind(),
+                std::io::ErrorKind::Interrupted
+                    | std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::WouldBlock
+            ),
+            _ => false,
+        }
+    }
+
+    /// Returns where the error happened, if the error carries context.
+    ///
+    /// Errors raised for a specific module by the runtime or by buffer validation
+    /// carry the operation, module, library and (for batch runs) time steps
+    /// involved: `RuntimeError`, `BufferSizeMismatch`, `BufferHandleNotFound`,
+    /// `ForeignStreamHandle` and `IncompatibleLibrary`. The context is also part of
+    /// their display message.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use kunquant_rs::{BatchRunner, BatchParams, KunQuantError};
+    /// # use std::collections::HashMap;
+    ///
+    /// # fn example(runner: BatchRunner, inputs: HashMap<&str, &[f32]>,
+    /// #     mut outputs: HashMap<&str, &mut [f32]>, params: BatchParams) {
+    /// if let Err(e) = runner.run(&inputs, &mut outputs, &params) {
+    ///     match e.context().and_then(|context| context.time_range.clone()) {
+    ///         Some(steps) => eprintln!("[{}] time steps {:?} failed: {}", e.code(), steps, e),
+    ///         None => eprintln!("[{}] {}", e.code(), e),
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            KunQuantError::IncompatibleLibrary { context, .. }
+            | KunQuantError::ModuleNotFound { context, .. }
+            | KunQuantError::BufferSizeMismatch { context, .. }
+            | KunQuantError::BufferHandleNotFound { context, .. }
+            | KunQuantError::ForeignStreamHandle { context, .. }
+            | KunQuantError::RuntimeError { context, .. } => context.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Attaches `context` if the error can carry context and doesn't have any yet
+    /// from a more specific call.
+    pub(crate) fn with_context(mut self, context: ErrorContext) -> Self {
+        match &mut self {
+            KunQuantError::IncompatibleLibrary { context: slot, .. }
+            | KunQuantError::ModuleNotFound { context: slot, .. }
+            | KunQuantError::BufferSizeMismatch { context: slot, .. }
+            | KunQuantError::BufferHandleNotFound { context: slot, .. }
+            | KunQuantError::ForeignStreamHandle { context: slot, .. }
+            | KunQuantError::RuntimeError { context: slot, .. } => {
+                slot.get_or_insert_with(|| Box::new(context));
+            }
+            _ => {}
+        }
+        self
+    }
+}
+
+/// Where an error happened: the operation, and the module, library and time steps
+/// it was working on.
+///
+/// Returned by [`KunQuantError::context`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// Name of the failed operation, e.g. `"run_graph"` or `"push_data"`
+    pub operation: String,
+    /// Name of the module involved
+    pub module: Option<String>,
+    /// Path of the library involved
+    pub library: Option<PathBuf>,
+    /// Time steps being computed, for batch runs
+    pub time_range: Option<Range<usize>>,
+}
+
+impl ErrorContext {
+    /// Creates a context for `operation`.
+    pub fn new<O: AsRef<str>>(operation: O) -> Self {
+        ErrorContext {
+            operation: operation.as_ref().to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the module and the library it belongs to.
+    pub fn with_module(mut self, module: &Module<'_>) -> Self {
+        self.module = Some(module.name().to_string());
+        self.library = Some(module.library().path().to_path_buf());
+        self
+    }
+
+    /// Sets the library.
+    pub fn with_library<P: AsRef<Path>>(mut self, library: P) -> Self {
+        self.library = Some(library.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets the time steps being computed.
+    pub fn with_time_range(mut self, time_range: Range<usize>) -> Self {
+        self.time_range = Some(time_range);
+        self
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "in {}", self.operation)?;
+        if let Some(module) = &self.module {
+            write!(f, " of module '{}'", module)?;
+        }
+        if let Some(library) = &self.library {
+            write!(f, " from {}", library.display())?;
+        }
+        if let Some(range) = &self.time_range {
+            write!(f, " at time steps {}..{}", range.start, range.end)?;
+        }
+        Ok(())
+    }
+}
+
+// Formats the context of an error as a suffix of its message
+fn in_context(context: &Option<Box<ErrorContext>>) -> String {
+    match context {
+        Some(context) => format!(" ({})", context),
+        None => String::new(),
+    }
+}
+
+// Formats the failed members of a stream group as `member: error` pairs
+fn list_failures(failures: &[(String, KunQuantError)]) -> String {
+    failures
+        .iter()
+        .map(|(member, error)| format!("{}: {}", member, error))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Attaches context to the error of a `Result`.
+pub(crate) trait ResultExt<T> {
+    /// Attaches the context built by `context` to the error, see
+    /// [`KunQuantError::with_context`].
+    fn context<F: FnOnce() -> ErrorContext>(self, context: F) -> Result<T>;
+}
+
+impl<T> ResultExt<T> for Result<T> {
+    fn context<F: FnOnce() -> ErrorContext>(self, context: F) -> Result<T> {
+        self.map_err(|error| error.with_context(context()))
+    }
 }
 
 /// Type alias for Results using KunQuantError.
@@ -187,3 +638,66 @@ pub enum KunQuantError {
 /// }
 /// ```
 pub type Result<T> = std::result::Result<T, KunQuantError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context() {
+        let error = KunQuantError::RuntimeError {
+            operation: "run_graph".to_string(),
+            message: "buffer not found".to_string(),
+            context: None,
+        }
+        .with_context(
+            ErrorContext::new("run_graph")
+                .with_library("factors.so")
+                .with_time_range(10..20),
+        );
+
+        assert_eq!(error.code(), "RUNTIME_ERROR");
+        assert!(!error.is_retryable());
+        assert_eq!(error.context().unwrap().time_range, Some(10..20));
+        assert_eq!(
+            error.to_string(),
+            "KunQuant runtime error in run_graph: buffer not found \
+             (in run_graph from factors.so at time steps 10..20)"
+        );
+
+        // The innermost context wins
+        let error = error.with_context(ErrorContext::new("batch_run"));
+        assert_eq!(error.context().unwrap().operation, "run_graph");
+
+        // Errors that don't carry context are unchanged
+        let error = KunQuantError::MissingStreamInputs {
+            missing: vec!["close".to_string()],
+        }
+        .with_context(ErrorContext::new("run"));
+        assert!(error.context().is_none());
+        assert_eq!(
+            error.to_string(),
+            "Stream inputs not pushed since last run: close"
+        );
+    }
+
+    #[test]
+    fn test_retryable() {
+        assert!(KunQuantError::EngineQueueFull { capacity: 1 }.is_retryable());
+        assert!(
+            !KunQuantError::Cancelled {
+                completed: 0,
+                total: 1
+            }
+            .is_retryable()
+        );
+        let io = std::io::Error::from(std::io::ErrorKind::TimedOut);
+        assert!(KunQuantError::Io(io).is_retryable());
+        assert!(
+            !KunQuantError::WorkerCrashed {
+                reason: "terminated by signal 11".to_string()
+            }
+            .is_retryable()
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use crate::error::{KunQuantError, Result};
 use crate::ffi;
+use std::num::NonZeroUsize;
 
 /// A KunQuant executor responsible for running factor computations.
 ///
@@ -81,16 +82,15 @@ impl Executor {
     /// # Arguments
     ///
     /// * `num_threads` - Number of worker threads to create. Should typically match
-    ///                   the number of CPU cores for optimal performance. Values
-    ///                   less than 1 will result in creation failure.
+    ///   the number of CPU cores for optimal performance. Must be at least 1.
     ///
     /// # Returns
     ///
-    /// Returns `Ok(Executor)` on success, or `Err(KunQuantError::ExecutorCreationFailed)`
-    /// if:
-    /// - `num_threads` is less than 1
-    /// - The underlying C library fails to create the executor
-    /// - System resources are insufficient for the requested thread count
+    /// Returns `Ok(Executor)` on success, or an error if:
+    /// - `num_threads` is less than 1 (`InvalidExecutorConfig`)
+    /// - The underlying C library fails to create the executor, e.g. because system
+    ///   resources are insufficient for the requested thread count
+    ///   (`ExecutorCreationFailed`)
     ///
     /// # Examples
     ///
@@ -115,7 +115,15 @@ impl Executor {
     /// - Thread synchronization adds latency overhead
     /// - Best suited for batch processing of large datasets
     /// - Diminishing returns beyond CPU core count due to memory bandwidth limits
+    ///
+    /// Use [`Executor::builder`] to derive the thread count from the machine or the
+    /// environment, or to pin the worker threads to a set of CPUs.
     pub fn multi_thread(num_threads: i32) -> Result<Self> {
+        if num_threads < 1 {
+            return Err(KunQuantError::InvalidExecutorConfig {
+                reason: format!("thread count must be at least 1, got {}", num_threads),
+            });
+        }
         let handle = unsafe { ffi::kunCreateMultiThreadExecutor(num_threads) };
         if handle.is_null() {
             return Err(KunQuantError::ExecutorCreationFailed);
@@ -123,6 +131,11 @@ impl Executor {
         Ok(Executor { handle })
     }
 
+    /// Starts configuring a multi-threaded executor, see [`ExecutorBuilder`].
+    pub fn builder() -> ExecutorBuilder {
+        ExecutorBuilder::new()
+    }
+
     /// Get the raw handle (for internal use)
     pub(crate) fn handle(&self) -> ffi::KunExecutorHandle {
         self.handle
@@ -142,3 +155,254 @@ impl Drop for Executor {
 // Executor is thread-safe according to KunQuant documentation
 unsafe impl Send for Executor {}
 unsafe impl Sync for Executor {}
+
+/// Builder for multi-threaded executors with validated thread counts and thread
+/// placement.
+///
+/// The thread count is taken from, in order of precedence:
+/// 1. [`with_threads`](ExecutorBuilder::with_threads)
+/// 2. the environment variable named by
+///    [`with_threads_from_env`](ExecutorBuilder::with_threads_from_env), if set
+/// 3. the size of the CPU set given to [`with_cpus`](ExecutorBuilder::with_cpus)
+/// 4. [`std::thread::available_parallelism`]
+///
+/// # Thread Placement
+///
+/// The runtime offers no hooks into its worker threads, so placement relies on
+/// threads inheriting the attributes of the thread that creates them: the executor
+/// is created on a temporary thread that is pinned to the CPU set and carries the
+/// thread name, and the runtime's workers start with the same CPU mask and name.
+/// This is Linux-specific for the CPU set. The thread that submits a computation
+/// is not affected and may take part in it, so latency-critical threads should stay
+/// off the executor's CPUs rather than rely on the pinning alone.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use kunquant_rs::Executor;
+///
+/// # fn main() -> kunquant_rs::Result<()> {
+/// // Factor jobs on CPUs 4-11, leaving 0-3 to the trading threads
+/// let executor = Executor::builder()
+///     .with_threads_from_env("KUNQUANT_THREADS")
+///     .with_cpus(4..12)
+///     .with_thread_name("kq-factor")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExecutorBuilder {
+    threads: Option<usize>,
+    threads_env: Option<String>,
+    cpus: Option<Vec<usize>>,
+    thread_name: Option<String>,
+}
+
+impl ExecutorBuilder {
+    /// Creates a builder with the default thread count and no placement.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of worker threads.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Reads the number of worker threads from environment variable `name` when
+    /// [`with_threads`](ExecutorBuilder::with_threads) is not used.
+    ///
+    /// An unset or empty variable falls back to the next source; any other value
+    /// that is not a positive integer makes [`build`](ExecutorBuilder::build) fail.
+    pub fn with_threads_from_env<N: AsRef<str>>(mut self, name: N) -> Self {
+        self.threads_env = Some(name.as_ref().to_string());
+        self
+    }
+
+    /// Pins the worker threads to the given CPU ids (Linux only).
+    ///
+    /// An empty set makes [`build`](ExecutorBuilder::build) and
+    /// [`resolve_threads`](ExecutorBuilder::resolve_threads) fail.
+    pub fn with_cpus<I: IntoIterator<Item = usize>>(mut self, cpus: I) -> Self {
+        let mut cpus: Vec<usize> = cpus.into_iter().collect();
+        cpus.sort_unstable();
+        cpus.dedup();
+        self.cpus = Some(cpus);
+        self
+    }
+
+    /// Names the worker threads, as shown by `top -H` or debuggers.
+    ///
+    /// Linux truncates thread names to 15 bytes.
+    pub fn with_thread_name<N: AsRef<str>>(mut self, name: N) -> Self {
+        self.thread_name = Some(name.as_ref().to_string());
+        self
+    }
+
+    /// Returns the thread count `build()` would use.
+    ///
+    /// # Returns
+    ///
+    /// Returns the count, or an error if it is zero, does not fit the runtime's
+    /// thread count type, the environment variable holds an invalid value, or the
+    /// CPU set is empty.
+    pub fn resolve_threads(&self) -> Result<usize> {
+        if self.cpus.as_ref().is_some_and(Vec::is_empty) {
+            return Err(KunQuantError::InvalidExecutorConfig {
+                reason: "CPU set given to with_cpus is empty".to_string(),
+            });
+        }
+        let from_env = match &self.threads_env {
+            Some(name) => parse_threads_env(name, std::env::var(name).ok())?,
+            None => None,
+        };
+        let threads = self
+            .threads
+            .or(from_env)
+            .or_else(|| self.cpus.as_ref().map(Vec::len))
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, NonZeroUsize::get));
+
+        if threads == 0 {
+            return Err(KunQuantError::InvalidExecutorConfig {
+                reason: "thread count must be at least 1, got 0".to_string(),
+            });
+        }
+        if i32::try_from(threads).is_err() {
+            return Err(KunQuantError::InvalidExecutorConfig {
+                reason: format!("thread count {} is too large", threads),
+            });
+        }
+        Ok(threads)
+    }
+
+    /// Creates the executor.
+    ///
+    /// # Returns
+    ///
+    /// Returns the executor, or an error if:
+    /// - The configuration is invalid (`InvalidExecutorConfig`)
+    /// - The CPU mask cannot be applied, e.g. none of the CPUs is available to the
+    ///   process (`Io`)
+    /// - The runtime fails to create the executor (`ExecutorCreationFailed`)
+    pub fn build(self) -> Result<Executor> {
+        let threads = self.resolve_threads()? as i32;
+        if self.cpus.is_none() && self.thread_name.is_none() {
+            return Executor::multi_thread(threads);
+        }
+
+        let mut spawner = std::thread::Builder::new();
+        if let Some(name) = self.thread_name {
+            if name.contains('\0') {
+                return Err(KunQuantError::InvalidExecutorConfig {
+                    reason: format!("thread name {:?} contains a null byte", name),
+                });
+            }
+            spawner = spawner.name(name);
+        }
+        let cpus = self.cpus;
+        spawner
+            .spawn(move || {
+                if let Some(cpus) = &cpus {
+                    pin_current_thread(cpus)?;
+                }
+                Executor::multi_thread(threads)
+            })?
+            .join()
+            .map_err(|_| KunQuantError::ExecutorCreationFailed)?
+    }
+}
+
+fn parse_threads_env(name: &str, value: Option<String>) -> Result<Option<usize>> {
+    match value.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(value) => match value.parse::<usize>() {
+            Ok(threads) if threads > 0 => Ok(Some(threads)),
+            _ => Err(KunQuantError::InvalidExecutorConfig {
+                reason: format!("{}={:?} is not a positive thread count", name, value),
+            }),
+        },
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn pin_current_thread(cpus: &[usize]) -> Result<()> {
+    let max_cpus = libc::CPU_SETSIZE as usize;
+    if let Some(cpu) = cpus.iter().find(|&&cpu| cpu >= max_cpus) {
+        return Err(KunQuantError::InvalidExecutorConfig {
+            reason: format!("CPU id {} exceeds the limit of {}", cpu, max_cpus),
+        });
+    }
+
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_ZERO(&mut set);
+        for &cpu in cpus {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_current_thread(_cpus: &[usize]) -> Result<()> {
+    Err(KunQuantError::InvalidExecutorConfig {
+        reason: "CPU affinity is only supported on Linux".to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thread_count_precedence() {
+        let builder = ExecutorBuilder::new().with_cpus([3, 1, 3, 2]);
+        assert_eq!(builder.resolve_threads().unwrap(), 3);
+        assert_eq!(builder.with_threads(8).resolve_threads().unwrap(), 8);
+        assert!(ExecutorBuilder::new().resolve_threads().unwrap() >= 1);
+    }
+
+    #[test]
+    fn test_invalid_thread_counts() {
+        assert!(matches!(
+            ExecutorBuilder::new().with_threads(0).resolve_threads(),
+            Err(KunQuantError::InvalidExecutorConfig { .. })
+        ));
+        assert!(
+            ExecutorBuilder::new()
+                .with_threads(i32::MAX as usize + 1)
+                .resolve_threads()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_empty_cpu_set() {
+        for builder in [
+            ExecutorBuilder::new().with_cpus([]),
+            ExecutorBuilder::new().with_cpus([]).with_threads(4),
+        ] {
+            match builder.build() {
+                Err(KunQuantError::InvalidExecutorConfig { reason }) => {
+                    assert!(reason.contains("with_cpus"), "{}", reason)
+                }
+                other => panic!("Expected InvalidExecutorConfig, got {:?}", other.err()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_threads_env() {
+        assert_eq!(parse_threads_env("T", None).unwrap(), None);
+        assert_eq!(parse_threads_env("T", Some(" ".into())).unwrap(), None);
+        assert_eq!(parse_threads_env("T", Some("6".into())).unwrap(), Some(6));
+        for invalid in ["0", "-2", "four"] {
+            assert!(parse_threads_env("T", Some(invalid.into())).is_err());
+        }
+    }
+}
@@ -54,3 +54,69 @@ unsafe extern "C" {
     pub fn kunStreamRun(context: KunStreamContextHandle);
     pub fn kunDestoryStream(context: KunStreamContextHandle);
 }
+
+// Exception-catching wrappers from shim/kunquant_shim.cpp, compiled by build.rs.
+// Each returns 0 on success, or non-zero with the message available from
+// `kunShimLastError` on the same thread.
+unsafe extern "C" {
+    pub fn kunShimLastError() -> *const c_char;
+
+    pub fn kunShimLoadLibrary(path_or_name: *const c_char, out: *mut KunLibraryHandle) -> c_int;
+
+    pub fn kunShimRunGraph(
+        exec: KunExecutorHandle,
+        m: KunModuleHandle,
+        buffers: KunBufferNameMapHandle,
+        num_stocks: size_t,
+        total_time: size_t,
+        cur_time: size_t,
+        length: size_t,
+    ) -> c_int;
+
+    pub fn kunShimSetBufferNameMap(
+        ptr: KunBufferNameMapHandle,
+        name: *const c_char,
+        buffer: *mut f32,
+    ) -> c_int;
+
+    pub fn kunShimCreateStream(
+        exec: KunExecutorHandle,
+        m: KunModuleHandle,
+        num_stocks: size_t,
+        out: *mut KunStreamContextHandle,
+    ) -> c_int;
+
+    pub fn kunShimQueryBufferHandle(
+        context: KunStreamContextHandle,
+        name: *const c_char,
+        out: *mut size_t,
+    ) -> c_int;
+
+    pub fn kunShimStreamPushData(
+        context: KunStreamContextHandle,
+        handle: size_t,
+        buffer: *const f32,
+    ) -> c_int;
+
+    pub fn kunShimStreamRun(context: KunStreamContextHandle) -> c_int;
+}
+
+/// Turns the status returned by a shim wrapper into a `RuntimeError` carrying the
+/// runtime's message.
+pub(crate) fn check(status: c_int, operation: &str) -> crate::error::Result<()> {
+    if status == 0 {
+        return Ok(());
+    }
+    Err(crate::error::KunQuantError::RuntimeError {
+        operation: operation.to_string(),
+        message: last_error(),
+        context: None,
+    })
+}
+
+/// Message of the last exception caught by a shim wrapper on this thread.
+pub(crate) fn last_error() -> String {
+    unsafe { std::ffi::CStr::from_ptr(kunShimLastError()) }
+        .to_string_lossy()
+        .into_owned()
+}
@@ -0,0 +1,143 @@
+/// Ring buffer of the most recent ticks of one stream output.
+///
+/// Every row is stored twice, at `slot` and `slot + capacity`, so the last `len`
+/// rows are always contiguous in memory. This makes [`as_slice`](OutputHistory::as_slice)
+/// a zero-copy `[N][stock]` view, oldest tick first, at the cost of twice the memory
+/// and two row copies per tick.
+///
+/// Obtained from [`StreamContext::output_history`](crate::StreamContext::output_history)
+/// after enabling it with
+/// [`enable_output_history`](crate::StreamContext::enable_output_history).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use kunquant_rs::{StreamContext, Result};
+/// # fn example(mut stream: StreamContext) -> Result<()> {
+/// stream.enable_output_history("alpha", 20)?;
+/// // ... push and run ticks ...
+/// let history = stream.output_history("alpha").unwrap();
+/// let latest = history.lag(0).unwrap();
+/// let matrix = history.as_slice(); // history.len() rows of num_stocks values
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OutputHistory {
+    num_stocks: usize,
+    capacity: usize,
+    data: Vec<f32>,
+    // Slot the next row is written to
+    next: usize,
+    len: usize,
+}
+
+impl OutputHistory {
+    pub(crate) fn new(num_stocks: usize, capacity: usize) -> Self {
+        OutputHistory {
+            num_stocks,
+            capacity,
+            data: vec![f32::NAN; 2 * capacity * num_stocks],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    pub(crate) fn record(&mut self, row: &[f32]) {
+        if self.capacity == 0 {
+            return;
+        }
+        let n = self.num_stocks;
+        let first = self.next * n;
+        let second = (self.next + self.capacity) * n;
+        self.data[first..first + n].copy_from_slice(row);
+        self.data[second..second + n].copy_from_slice(row);
+        self.next = (self.next + 1) % self.capacity;
+        self.len = (self.len + 1).min(self.capacity);
+    }
+
+    /// Number of ticks currently held.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no tick has been recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Maximum number of ticks held.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of stocks per tick.
+    pub fn num_stocks(&self) -> usize {
+        self.num_stocks
+    }
+
+    /// Returns the held ticks as a `[len][stock]` matrix, oldest tick first.
+    pub fn as_slice(&self) -> &[f32] {
+        if self.len == 0 {
+            return &[];
+        }
+        let start = (self.next + self.capacity - self.len) % self.capacity;
+        &self.data[start * self.num_stocks..(start + self.len) * self.num_stocks]
+    }
+
+    /// Returns the tick `lag` steps back, where 0 is the latest tick.
+    pub fn lag(&self, lag: usize) -> Option<&[f32]> {
+        if lag >= self.len {
+            return None;
+        }
+        self.row(self.len - 1 - lag)
+    }
+
+    /// Returns the `index`-th held tick, where 0 is the oldest.
+    pub fn row(&self, index: usize) -> Option<&[f32]> {
+        if index >= self.len {
+            return None;
+        }
+        let n = self.num_stocks;
+        Some(&self.as_slice()[index * n..(index + 1) * n])
+    }
+
+    /// Iterates over the held ticks, oldest first.
+    pub fn rows(&self) -> impl Iterator<Item = &[f32]> {
+        self.as_slice().chunks_exact(self.num_stocks.max(1))
+    }
+
+    /// Iterates over one stock's values, oldest first.
+    pub fn stock(&self, stock: usize) -> impl Iterator<Item = f32> + '_ {
+        self.rows().map(move |row| row[stock])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_view_is_contiguous_after_wrap() {
+        let mut history = OutputHistory::new(2, 3);
+        assert!(history.as_slice().is_empty());
+        for t in 0..5 {
+            history.record(&[t as f32, 10.0 + t as f32]);
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.as_slice(), &[2.0, 12.0, 3.0, 13.0, 4.0, 14.0]);
+        assert_eq!(history.lag(0), Some(&[4.0, 14.0][..]));
+        assert_eq!(history.row(0), Some(&[2.0, 12.0][..]));
+        assert_eq!(history.lag(3), None);
+        assert_eq!(history.stock(1).collect::<Vec<_>>(), vec![12.0, 13.0, 14.0]);
+    }
+
+    #[test]
+    fn test_partial_history() {
+        let mut history = OutputHistory::new(1, 4);
+        history.record(&[1.0]);
+        history.record(&[2.0]);
+        assert_eq!(history.as_slice(), &[1.0, 2.0]);
+        assert_eq!(history.rows().count(), 2);
+    }
+}
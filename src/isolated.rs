@@ -0,0 +1,728 @@
+use crate::batch::{BatchParams, check_buffer_len, run_graph};
+use crate::buffer::BufferNameMap;
+use crate::error::{ErrorContext, KunQuantError, Result, ResultExt};
+use crate::executor::Executor;
+use crate::library::Library;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::process::ExitStatusExt;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Name of the worker executable built from `src/bin/kunquant-worker.rs`.
+pub const WORKER_NAME: &str = "kunquant-worker";
+
+/// Environment variable overriding the location of the worker executable.
+pub const WORKER_ENV: &str = "KUNQUANT_WORKER";
+
+/// Size of the error message area shared between the worker and the parent.
+const MESSAGE_CAPACITY: usize = 1024;
+
+/// Alignment of the data area inside the shared memory region.
+const DATA_ALIGNMENT: usize = 64;
+
+/// How often the parent checks whether a worker with a timeout has finished.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Exit code of a worker that was started with malformed arguments.
+const WORKER_USAGE_ERROR: i32 = 2;
+
+/// Separator between the fields of an error in the shared message area.
+const FIELD_SEPARATOR: char = '\0';
+
+// Error kinds reported by the worker through the shared header. The message area
+// holds the fields of the error, separated by `FIELD_SEPARATOR`.
+const WORKER_OK: u32 = 0;
+const WORKER_LIBRARY_LOAD_FAILED: u32 = 1;
+const WORKER_MODULE_NOT_FOUND: u32 = 2;
+const WORKER_FAILED: u32 = 3;
+const WORKER_LIBRARY_NOT_FOUND: u32 = 4;
+const WORKER_INCOMPATIBLE_LIBRARY: u32 = 5;
+const WORKER_RUNTIME_ERROR: u32 = 6;
+const WORKER_BUFFER_SIZE_MISMATCH: u32 = 7;
+const WORKER_EXECUTOR_CREATION_FAILED: u32 = 8;
+const WORKER_INVALID_EXECUTOR_CONFIG: u32 = 9;
+const WORKER_BUFFER_NAME_MAP_CREATION_FAILED: u32 = 10;
+const WORKER_INVALID_BATCH_CONFIG: u32 = 11;
+const WORKER_CRASHED: u32 = 12;
+
+/// Counter making shared memory file names unique within the process.
+static REGION_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Header at the start of the shared memory region, written by the worker.
+#[repr(C)]
+struct SharedHeader {
+    error_kind: u32,
+    message_len: u32,
+    message: [u8; MESSAGE_CAPACITY],
+}
+
+/// Location of one named buffer inside the shared data area (in `f32` units).
+struct BufferSlot {
+    name: String,
+    offset: usize,
+    len: usize,
+}
+
+/// A file-backed shared memory mapping, opened by path in the worker process.
+struct SharedRegion {
+    ptr: *mut u8,
+    len: usize,
+    /// Set in the parent, which removes the file when the region is dropped.
+    owned_path: Option<PathBuf>,
+}
+
+impl SharedRegion {
+    /// Creates a zero-filled region of `len` bytes backed by a new temporary file.
+    fn create(len: usize) -> Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "kunquant-isolated-{}-{}",
+            std::process::id(),
+            REGION_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)
+            .map_err(|e| region_error("create", e))?;
+        let mapped = file
+            .set_len(len as u64)
+            .map_err(|e| region_error("resize", e))
+            .and_then(|()| SharedRegion::map(&file, len));
+        match mapped {
+            Ok(mut region) => {
+                region.owned_path = Some(path);
+                Ok(region)
+            }
+            Err(e) => {
+                let _ = std::fs::remove_file(&path);
+                Err(e)
+            }
+        }
+    }
+
+    /// Maps the region created by the parent at `path`.
+    fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .map_err(|e| region_error("open", e))?;
+        let len = file.metadata().map_err(|e| region_error("open", e))?.len() as usize;
+        if len < data_offset() {
+            return Err(KunQuantError::WorkerCrashed {
+                reason: format!("shared memory file {} is truncated", path.display()),
+            });
+        }
+        SharedRegion::map(&file, len)
+    }
+
+    fn map(file: &File, len: usize) -> Result<Self> {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(region_error("map", std::io::Error::last_os_error()));
+        }
+        Ok(SharedRegion {
+            ptr: ptr as *mut u8,
+            len,
+            owned_path: None,
+        })
+    }
+
+    fn header(&self) -> *mut SharedHeader {
+        self.ptr as *mut SharedHeader
+    }
+
+    fn data(&self) -> *mut f32 {
+        unsafe { self.ptr.add(data_offset()) as *mut f32 }
+    }
+
+    /// Number of `f32` values that fit in the data area.
+    fn data_len(&self) -> usize {
+        (self.len - data_offset()) / std::mem::size_of::<f32>()
+    }
+}
+
+impl Drop for SharedRegion {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, self.len);
+        }
+        if let Some(path) = &self.owned_path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn region_error(action: &str, err: std::io::Error) -> KunQuantError {
+    KunQuantError::WorkerCrashed {
+        reason: format!("failed to {} shared memory: {}", action, err),
+    }
+}
+
+fn data_offset() -> usize {
+    std::mem::size_of::<SharedHeader>().div_ceil(DATA_ALIGNMENT) * DATA_ALIGNMENT
+}
+
+/// Runs batch factor computations in a separate worker process.
+///
+/// `IsolatedRunner` is an opt-in alternative to [`run_graph`] for factor libraries
+/// that are untrusted or experimental. For every call to [`run`](IsolatedRunner::run)
+/// it spawns a fresh worker executable, which loads the library, creates its own
+/// executor and runs the graph. Input and output buffers are shipped through a
+/// file-backed shared memory mapping, so no data is serialized.
+///
+/// If the worker crashes (e.g. a segmentation fault inside the factor code) or
+/// exceeds the configured timeout, the parent process survives and receives
+/// `KunQuantError::WorkerCrashed`. Errors the worker reports itself, such as a
+/// library that fails to load or an exception thrown by the runtime, are passed
+/// back to the parent and returned as the same `KunQuantError` variant an
+/// in-process [`run_graph`] call would return.
+///
+/// # Worker Executable
+///
+/// The worker is the `kunquant-worker` binary of this crate (install it with
+/// `cargo install kunquant_rs`), or any executable whose `main` calls
+/// [`worker_main`]. It is located in this order:
+///
+/// 1. The path given to [`with_worker`](IsolatedRunner::with_worker)
+/// 2. The `KUNQUANT_WORKER` environment variable
+/// 3. `kunquant-worker` next to the current executable, or one directory up
+///    (which covers test binaries under `target/*/deps`)
+///
+/// # Platform Support
+///
+/// Only available on Unix platforms, as it relies on `mmap()` and Unix exit statuses.
+///
+/// # Caveats
+///
+/// - Every run pays for a process spawn and a library load. Use it where fault
+///   isolation matters more than latency.
+/// - Output buffers are only written back when the worker exits successfully.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use kunquant_rs::{BatchParams, IsolatedRunner};
+/// use std::collections::HashMap;
+/// use std::time::Duration;
+///
+/// # fn main() -> kunquant_rs::Result<()> {
+/// let runner = IsolatedRunner::new("research/experimental.so", "alpha_x")
+///     .with_timeout(Duration::from_secs(30));
+///
+/// let close = vec![1.0f32; 16 * 100];
+/// let mut alpha = vec![0.0f32; 16 * 100];
+///
+/// let inputs = HashMap::from([("close", close.as_slice())]);
+/// let mut outputs = HashMap::from([("alpha_x", alpha.as_mut_slice())]);
+///
+/// let params = BatchParams::full_range(16, 100)?;
+/// runner.run(&inputs, &mut outputs, &params)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct IsolatedRunner {
+    library_path: PathBuf,
+    module_name: String,
+    num_threads: Option<i32>,
+    timeout: Option<Duration>,
+    worker: Option<PathBuf>,
+}
+
+impl IsolatedRunner {
+    /// Creates a runner for the named module of the library at `library_path`.
+    ///
+    /// The library is not loaded in the calling process; it is only opened inside
+    /// the worker processes spawned by [`run`](IsolatedRunner::run).
+    ///
+    /// # Arguments
+    ///
+    /// * `library_path` - Path to the compiled factor library
+    /// * `module_name` - Name of the module inside the library
+    pub fn new<P: AsRef<Path>, N: AsRef<str>>(library_path: P, module_name: N) -> Self {
+        IsolatedRunner {
+            library_path: library_path.as_ref().to_path_buf(),
+            module_name: module_name.as_ref().to_string(),
+            num_threads: None,
+            timeout: None,
+            worker: None,
+        }
+    }
+
+    /// Runs the worker with a multi-threaded executor of `num_threads` threads.
+    ///
+    /// By default the worker uses a single-threaded executor.
+    pub fn with_threads(mut self, num_threads: i32) -> Self {
+        self.num_threads = Some(num_threads);
+        self
+    }
+
+    /// Kills the worker if it has not finished within `timeout`.
+    ///
+    /// By default the parent waits for the worker indefinitely.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Uses the executable at `path` as the worker instead of searching for
+    /// `kunquant-worker`.
+    pub fn with_worker<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.worker = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Runs the module on the given buffers inside a worker process.
+    ///
+    /// Inputs are copied into shared memory before the worker is spawned, and the
+    /// outputs are copied back once the worker has exited successfully.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - Input buffers keyed by name, each `num_stocks * total_time` long
+    /// * `outputs` - Output buffers keyed by name, each `num_stocks * length` long
+    /// * `params` - Batch parameters passed to the worker's `run_graph` call
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if:
+    /// - A buffer has the wrong size (`BufferSizeMismatch`)
+    /// - The worker could not find or load the library (`LibraryNotFound`,
+    ///   `LibraryLoadFailed`)
+    /// - The module was not found in the library (`ModuleNotFound`)
+    /// - The library was built for another runtime version (`IncompatibleLibrary`)
+    /// - The runtime rejected the run, e.g. a missing buffer (`RuntimeError`)
+    /// - The worker could not be spawned, crashed, was killed, panicked, or exceeded
+    ///   the timeout (`WorkerCrashed`)
+    pub fn run(
+        &self,
+        inputs: &HashMap<&str, &[f32]>,
+        outputs: &mut HashMap<&str, &mut [f32]>,
+        params: &BatchParams,
+    ) -> Result<()> {
+        (|| -> Result<()> {
+            for (&name, data) in inputs {
+                check_buffer_len(name, data.len(), params.num_stocks * params.total_time)?;
+            }
+            for (&name, data) in outputs.iter() {
+                check_buffer_len(name, data.len(), params.num_stocks * params.length)?;
+            }
+            Ok(())
+        })()
+        .context(|| self.error_context(params))?;
+
+        let mut slots = Vec::with_capacity(inputs.len() + outputs.len());
+        let mut total_len = 0;
+        for (name, data) in inputs {
+            slots.push(BufferSlot {
+                name: name.to_string(),
+                offset: total_len,
+                len: data.len(),
+            });
+            total_len += data.len();
+        }
+        let num_inputs = slots.len();
+        for (name, data) in outputs.iter() {
+            slots.push(BufferSlot {
+                name: name.to_string(),
+                offset: total_len,
+                len: data.len(),
+            });
+            total_len += data.len();
+        }
+
+        let region = SharedRegion::create(data_offset() + total_len * std::mem::size_of::<f32>())?;
+        unsafe {
+            for slot in &slots[..num_inputs] {
+                let src = inputs[slot.name.as_str()];
+                std::ptr::copy_nonoverlapping(
+                    src.as_ptr(),
+                    region.data().add(slot.offset),
+                    slot.len,
+                );
+            }
+        }
+
+        let worker = self.worker_path()?;
+        let child = Command::new(&worker)
+            .args(self.worker_args(&region, &slots, params))
+            .stdin(Stdio::null())
+            .spawn()
+            .map_err(|e| KunQuantError::WorkerCrashed {
+                reason: format!("failed to spawn worker {}: {}", worker.display(), e),
+            })?;
+
+        let status = self.wait_for_worker(child)?;
+        self.check_worker_status(&region, status)
+            .context(|| self.error_context(params))?;
+
+        unsafe {
+            for slot in &slots[num_inputs..] {
+                let dst = outputs.get_mut(slot.name.as_str()).unwrap();
+                std::ptr::copy_nonoverlapping(
+                    region.data().add(slot.offset),
+                    dst.as_mut_ptr(),
+                    slot.len,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Context attached to errors of a run with `params`.
+    fn error_context(&self, params: &BatchParams) -> ErrorContext {
+        let mut context = ErrorContext::new("isolated_run")
+            .with_library(&self.library_path)
+            .with_time_range(params.cur_time..params.cur_time + params.length);
+        context.module = Some(self.module_name.clone());
+        context
+    }
+
+    /// Locates the worker executable as described in the type-level docs.
+    fn worker_path(&self) -> Result<PathBuf> {
+        if let Some(path) = &self.worker {
+            return Ok(path.clone());
+        }
+        if let Some(path) = std::env::var_os(WORKER_ENV) {
+            return Ok(PathBuf::from(path));
+        }
+        let exe = std::env::current_exe().map_err(|e| KunQuantError::WorkerCrashed {
+            reason: format!("failed to locate the current executable: {}", e),
+        })?;
+        exe.ancestors()
+            .skip(1)
+            .take(2)
+            .map(|dir| dir.join(WORKER_NAME))
+            .find(|path| path.is_file())
+            .ok_or_else(|| KunQuantError::WorkerCrashed {
+                reason: format!(
+                    "worker executable `{}` not found; set {} or call with_worker",
+                    WORKER_NAME, WORKER_ENV
+                ),
+            })
+    }
+
+    /// Builds the worker command line, parsed back by [`worker_main`].
+    ///
+    /// Layout: `<region> <library> <module> <threads> <num_stocks> <total_time>
+    /// <cur_time> <length>` followed by `<offset> <len> <name>` for every buffer.
+    /// A thread count of 0 selects the single-threaded executor.
+    fn worker_args(
+        &self,
+        region: &SharedRegion,
+        slots: &[BufferSlot],
+        params: &BatchParams,
+    ) -> Vec<OsString> {
+        let mut args: Vec<OsString> = vec![
+            region.owned_path.clone().unwrap_or_default().into(),
+            self.library_path.clone().into(),
+            self.module_name.clone().into(),
+            self.num_threads.unwrap_or(0).to_string().into(),
+        ];
+        for value in [
+            params.num_stocks,
+            params.total_time,
+            params.cur_time,
+            params.length,
+        ] {
+            args.push(value.to_string().into());
+        }
+        for slot in slots {
+            args.push(slot.offset.to_string().into());
+            args.push(slot.len.to_string().into());
+            args.push(slot.name.clone().into());
+        }
+        args
+    }
+
+    /// Waits for the worker to exit, killing it if the timeout elapses.
+    fn wait_for_worker(&self, mut child: Child) -> Result<ExitStatus> {
+        let wait_error = |e: std::io::Error| KunQuantError::WorkerCrashed {
+            reason: format!("failed to wait for worker: {}", e),
+        };
+        let Some(timeout) = self.timeout else {
+            return child.wait().map_err(wait_error);
+        };
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(status) = child.try_wait().map_err(wait_error)? {
+                return Ok(status);
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(KunQuantError::WorkerCrashed {
+                    reason: format!("timed out after {:?}", timeout),
+                });
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Converts the worker's exit status and recorded error into a `KunQuantError`.
+    fn check_worker_status(&self, region: &SharedRegion, status: ExitStatus) -> Result<()> {
+        if let Some(signal) = status.signal() {
+            return Err(KunQuantError::WorkerCrashed {
+                reason: format!("terminated by signal {}", signal),
+            });
+        }
+        let header = unsafe { &*region.header() };
+        let len = (header.message_len as usize).min(MESSAGE_CAPACITY);
+        let message = String::from_utf8_lossy(&header.message[..len]).into_owned();
+        match header.error_kind {
+            WORKER_OK if status.success() => Ok(()),
+            WORKER_OK => Err(KunQuantError::WorkerCrashed {
+                reason: format!("exited with status {}", status.code().unwrap_or(-1)),
+            }),
+            kind => Err(self.decode_error(kind, &message)),
+        }
+    }
+
+    /// Rebuilds an error reported by the worker from its kind and fields.
+    fn decode_error(&self, kind: u32, message: &str) -> KunQuantError {
+        let mut fields = message.split(FIELD_SEPARATOR);
+        let mut field = || fields.next().unwrap_or_default().to_string();
+        match kind {
+            WORKER_LIBRARY_NOT_FOUND => KunQuantError::LibraryNotFound {
+                path: self.library_path.display().to_string(),
+            },
+            WORKER_LIBRARY_LOAD_FAILED => KunQuantError::LibraryLoadFailed {
+                path: self.library_path.display().to_string(),
+                reason: field(),
+            },
+            WORKER_MODULE_NOT_FOUND => KunQuantError::ModuleNotFound {
+                name: self.module_name.clone(),
+                context: None,
+            },
+            WORKER_INCOMPATIBLE_LIBRARY => KunQuantError::IncompatibleLibrary {
+                library_version: field(),
+                runtime_version: field(),
+                context: None,
+            },
+            WORKER_RUNTIME_ERROR => KunQuantError::RuntimeError {
+                operation: field(),
+                message: field(),
+                context: None,
+            },
+            WORKER_BUFFER_SIZE_MISMATCH => KunQuantError::BufferSizeMismatch {
+                name: field(),
+                expected: field().parse().unwrap_or_default(),
+                actual: field().parse().unwrap_or_default(),
+                context: None,
+            },
+            WORKER_EXECUTOR_CREATION_FAILED => KunQuantError::ExecutorCreationFailed,
+            WORKER_INVALID_EXECUTOR_CONFIG => {
+                KunQuantError::InvalidExecutorConfig { reason: field() }
+            }
+            WORKER_BUFFER_NAME_MAP_CREATION_FAILED => KunQuantError::BufferNameMapCreationFailed,
+            WORKER_INVALID_BATCH_CONFIG => KunQuantError::InvalidBatchConfig { reason: field() },
+            WORKER_FAILED => KunQuantError::RuntimeError {
+                operation: "isolated_run".to_string(),
+                message: field(),
+                context: None,
+            },
+            WORKER_CRASHED => KunQuantError::WorkerCrashed { reason: field() },
+            kind => KunQuantError::WorkerCrashed {
+                reason: format!("reported unknown error kind {}", kind),
+            },
+        }
+    }
+}
+
+/// A job decoded from the worker command line.
+struct WorkerJob {
+    region: PathBuf,
+    library_path: PathBuf,
+    module_name: String,
+    num_threads: i32,
+    params: BatchParams,
+    slots: Vec<BufferSlot>,
+}
+
+impl WorkerJob {
+    fn parse(args: Vec<OsString>) -> std::result::Result<Self, String> {
+        let mut args = args.into_iter();
+        let mut next = |what: &str| args.next().ok_or_else(|| format!("missing {}", what));
+        let region = PathBuf::from(next("shared memory path")?);
+        let library_path = PathBuf::from(next("library path")?);
+        let module_name = next("module name")?
+            .into_string()
+            .map_err(|_| "module name is not valid UTF-8".to_string())?;
+        let num_threads = parse_number(next("thread count")?)?;
+        let params = BatchParams {
+            num_stocks: parse_number(next("num_stocks")?)?,
+            total_time: parse_number(next("total_time")?)?,
+            cur_time: parse_number(next("cur_time")?)?,
+            length: parse_number(next("length")?)?,
+        };
+        let mut slots = Vec::new();
+        while let Some(offset) = args.next() {
+            let len = args.next().ok_or("missing buffer length")?;
+            let name = args.next().ok_or("missing buffer name")?;
+            slots.push(BufferSlot {
+                name: name
+                    .into_string()
+                    .map_err(|_| "buffer name is not valid UTF-8".to_string())?,
+                offset: parse_number(offset)?,
+                len: parse_number(len)?,
+            });
+        }
+        Ok(WorkerJob {
+            region,
+            library_path,
+            module_name,
+            num_threads,
+            params,
+            slots,
+        })
+    }
+
+    fn run(&self, region: &SharedRegion) -> Result<()> {
+        for slot in &self.slots {
+            if slot
+                .offset
+                .checked_add(slot.len)
+                .is_none_or(|end| end > region.data_len())
+            {
+                return Err(KunQuantError::WorkerCrashed {
+                    reason: format!("buffer '{}' lies outside shared memory", slot.name),
+                });
+            }
+        }
+        let library = Library::load(&self.library_path)?;
+        let module = library.get_module(&self.module_name)?;
+        let executor = match self.num_threads {
+            0 => Executor::single_thread()?,
+            n => Executor::multi_thread(n)?,
+        };
+        let mut buffers = BufferNameMap::new()?;
+        for slot in &self.slots {
+            unsafe {
+                buffers.set_buffer(&slot.name, region.data().add(slot.offset))?;
+            }
+        }
+        let params = BatchParams::new(
+            self.params.num_stocks,
+            self.params.total_time,
+            self.params.cur_time,
+            self.params.length,
+        )?;
+        run_graph(&executor, &module, &buffers, &params)
+    }
+}
+
+/// Splits an error into the kind and fields written to the shared header.
+fn encode_error(error: KunQuantError) -> (u32, Vec<String>) {
+    match error {
+        KunQuantError::LibraryNotFound { path } => (WORKER_LIBRARY_NOT_FOUND, vec![path]),
+        KunQuantError::LibraryLoadFailed { reason, .. } => {
+            (WORKER_LIBRARY_LOAD_FAILED, vec![reason])
+        }
+        KunQuantError::ModuleNotFound { name, .. } => (WORKER_MODULE_NOT_FOUND, vec![name]),
+        KunQuantError::IncompatibleLibrary {
+            library_version,
+            runtime_version,
+            ..
+        } => (
+            WORKER_INCOMPATIBLE_LIBRARY,
+            vec![library_version, runtime_version],
+        ),
+        KunQuantError::RuntimeError {
+            operation, message, ..
+        } => (WORKER_RUNTIME_ERROR, vec![operation, message]),
+        KunQuantError::BufferSizeMismatch {
+            name,
+            expected,
+            actual,
+            ..
+        } => (
+            WORKER_BUFFER_SIZE_MISMATCH,
+            vec![name, expected.to_string(), actual.to_string()],
+        ),
+        KunQuantError::ExecutorCreationFailed => (WORKER_EXECUTOR_CREATION_FAILED, Vec::new()),
+        KunQuantError::InvalidExecutorConfig { reason } => {
+            (WORKER_INVALID_EXECUTOR_CONFIG, vec![reason])
+        }
+        KunQuantError::BufferNameMapCreationFailed => {
+            (WORKER_BUFFER_NAME_MAP_CREATION_FAILED, Vec::new())
+        }
+        KunQuantError::InvalidBatchConfig { reason } => (WORKER_INVALID_BATCH_CONFIG, vec![reason]),
+        KunQuantError::WorkerCrashed { reason } => (WORKER_CRASHED, vec![reason]),
+        other => (WORKER_FAILED, vec![other.to_string()]),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(arg: OsString) -> std::result::Result<T, String> {
+    arg.to_str()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| format!("invalid number {:?}", arg))
+}
+
+/// Entry point of the isolated worker process. Returns the process exit code.
+///
+/// The `kunquant-worker` binary is a thin wrapper around this function. An
+/// application can also serve as its own worker by calling it from `main` when
+/// started with the worker arguments, and pointing
+/// [`IsolatedRunner::with_worker`] at its own executable. The arguments are
+/// produced by [`IsolatedRunner::run`] and are not a stable interface.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// // src/bin/my-worker.rs
+/// std::process::exit(kunquant_rs::isolated::worker_main());
+/// ```
+pub fn worker_main() -> i32 {
+    let job = match WorkerJob::parse(std::env::args_os().skip(1).collect()) {
+        Ok(job) => job,
+        Err(reason) => {
+            eprintln!("{}: {}", WORKER_NAME, reason);
+            return WORKER_USAGE_ERROR;
+        }
+    };
+    let region = match SharedRegion::open(&job.region) {
+        Ok(region) => region,
+        Err(e) => {
+            eprintln!("{}: {}", WORKER_NAME, e);
+            return WORKER_USAGE_ERROR;
+        }
+    };
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| job.run(&region))).unwrap_or_else(|_| {
+        Err(KunQuantError::WorkerCrashed {
+            reason: "worker panicked".to_string(),
+        })
+    });
+    let (kind, fields) = match result {
+        Ok(()) => return 0,
+        Err(e) => encode_error(e),
+    };
+    let message = fields.join(&FIELD_SEPARATOR.to_string());
+    let bytes = message.as_bytes();
+    let len = bytes.len().min(MESSAGE_CAPACITY);
+    unsafe {
+        let header = &mut *region.header();
+        header.message[..len].copy_from_slice(&bytes[..len]);
+        header.message_len = len as u32;
+        header.error_kind = kind;
+    }
+    1
+}
@@ -0,0 +1,368 @@
+use crate::error::Result;
+use std::fmt;
+use std::io::Write;
+use std::time::Duration;
+
+// Values below 2^SUB_BUCKET_BITS nanoseconds are counted exactly; above that every
+// power of two is split into 2^(SUB_BUCKET_BITS - 1) buckets, which bounds the
+// relative error of a recorded value to 1/32 (about 3%)
+const SUB_BUCKET_BITS: u32 = 6;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+const HALF_SUB_BUCKETS: usize = SUB_BUCKETS / 2;
+const NUM_BUCKETS: usize = SUB_BUCKETS + (64 - SUB_BUCKET_BITS as usize) * HALF_SUB_BUCKETS;
+
+/// A stream operation whose latency is tracked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StreamOperation {
+    /// `push_data()`, `push()` and `push_masked()`, including validation and the
+    /// name lookup; `push_all()` once per call; every input pushed by `warm_up()`
+    PushData,
+    /// `run()`, up to and including the runtime computation; history recording,
+    /// subscriber callbacks and runs rejected for missing inputs are not included
+    Run,
+    /// `get_current_buffer()` and `output()`
+    GetCurrentBuffer,
+}
+
+impl StreamOperation {
+    /// All tracked operations.
+    pub const ALL: [StreamOperation; 3] = [
+        StreamOperation::PushData,
+        StreamOperation::Run,
+        StreamOperation::GetCurrentBuffer,
+    ];
+
+    /// Name of the operation, as used in exports.
+    pub fn name(&self) -> &'static str {
+        match self {
+            StreamOperation::PushData => "push_data",
+            StreamOperation::Run => "run",
+            StreamOperation::GetCurrentBuffer => "get_current_buffer",
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            StreamOperation::PushData => 0,
+            StreamOperation::Run => 1,
+            StreamOperation::GetCurrentBuffer => 2,
+        }
+    }
+}
+
+/// Histogram of latencies with logarithmic buckets, in the style of HdrHistogram.
+///
+/// Latencies are recorded in nanoseconds. Quantiles are exact below 64ns and
+/// within about 3% above, with a fixed memory footprint of about 15KB and
+/// constant-time recording.
+#[derive(Debug, Clone)]
+pub struct LatencyHistogram {
+    counts: Vec<u64>,
+    count: u64,
+    sum: u128,
+    min: u64,
+    max: u64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LatencyHistogram {
+    /// Creates an empty histogram.
+    pub fn new() -> Self {
+        LatencyHistogram {
+            counts: vec![0; NUM_BUCKETS],
+            count: 0,
+            sum: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+
+    /// Records one latency.
+    pub fn record(&mut self, latency: Duration) {
+        let nanos = u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX);
+        self.counts[bucket_index(nanos)] += 1;
+        self.count += 1;
+        self.sum += nanos as u128;
+        self.min = self.min.min(nanos);
+        self.max = self.max.max(nanos);
+    }
+
+    /// Number of recorded latencies.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns `true` if nothing has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Smallest recorded latency, zero if empty.
+    pub fn min(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos(self.min)
+        }
+    }
+
+    /// Largest recorded latency, zero if empty.
+    pub fn max(&self) -> Duration {
+        Duration::from_nanos(self.max)
+    }
+
+    /// Mean of the recorded latencies, zero if empty.
+    pub fn mean(&self) -> Duration {
+        match self.count {
+            0 => Duration::ZERO,
+            count => Duration::from_nanos((self.sum / count as u128) as u64),
+        }
+    }
+
+    /// Returns the latency below or at which `quantile` of the recorded latencies
+    /// fall, e.g. `0.99` for p99.
+    ///
+    /// The result is the upper bound of the bucket holding that latency, capped at
+    /// [`max`](LatencyHistogram::max). Returns zero if empty.
+    pub fn quantile(&self, quantile: f64) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+        let rank = ((quantile.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, &count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Duration::from_nanos(bucket_upper(index).min(self.max));
+            }
+        }
+        self.max()
+    }
+
+    /// Iterates over the non-empty buckets as `(lowest, highest, count)`, where
+    /// `lowest..=highest` is the range of latencies counted by the bucket.
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, Duration, u64)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(index, &count)| {
+                (
+                    Duration::from_nanos(bucket_lower(index)),
+                    Duration::from_nanos(bucket_upper(index)),
+                    count,
+                )
+            })
+    }
+
+    /// Adds the latencies recorded in `other`.
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    /// Removes all recorded latencies.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Returns the usual summary statistics.
+    pub fn summary(&self) -> LatencySummary {
+        LatencySummary {
+            count: self.count,
+            min: self.min(),
+            p50: self.quantile(0.5),
+            p90: self.quantile(0.9),
+            p99: self.quantile(0.99),
+            p999: self.quantile(0.999),
+            max: self.max(),
+            mean: self.mean(),
+        }
+    }
+}
+
+fn bucket_index(nanos: u64) -> usize {
+    if nanos < SUB_BUCKETS as u64 {
+        return nanos as usize;
+    }
+    let shift = 63 - nanos.leading_zeros() - (SUB_BUCKET_BITS - 1);
+    let top = (nanos >> shift) as usize;
+    SUB_BUCKETS + (shift as usize - 1) * HALF_SUB_BUCKETS + (top - HALF_SUB_BUCKETS)
+}
+
+fn bucket_lower(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+    let offset = index - SUB_BUCKETS;
+    let shift = offset / HALF_SUB_BUCKETS + 1;
+    let top = (offset % HALF_SUB_BUCKETS + HALF_SUB_BUCKETS) as u64;
+    top << shift
+}
+
+fn bucket_upper(index: usize) -> u64 {
+    if index + 1 == NUM_BUCKETS {
+        u64::MAX
+    } else {
+        bucket_lower(index + 1) - 1
+    }
+}
+
+/// Summary statistics of a [`LatencyHistogram`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencySummary {
+    /// Number of recorded latencies
+    pub count: u64,
+    /// Smallest latency
+    pub min: Duration,
+    /// Median
+    pub p50: Duration,
+    /// 90th percentile
+    pub p90: Duration,
+    /// 99th percentile
+    pub p99: Duration,
+    /// 99.9th percentile
+    pub p999: Duration,
+    /// Largest latency
+    pub max: Duration,
+    /// Mean latency
+    pub mean: Duration,
+}
+
+impl fmt::Display for LatencySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "n={} min={:?} p50={:?} p90={:?} p99={:?} p99.9={:?} max={:?} mean={:?}",
+            self.count, self.min, self.p50, self.p90, self.p99, self.p999, self.max, self.mean
+        )
+    }
+}
+
+/// Latency histograms of the operations of one stream context.
+///
+/// Obtained from [`StreamContext::latency`](crate::StreamContext::latency) after
+/// enabling tracking with
+/// [`enable_latency_tracking`](crate::StreamContext::enable_latency_tracking).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use kunquant_rs::{StreamContext, Result};
+/// use kunquant_rs::latency::StreamOperation;
+///
+/// # fn example(mut stream: StreamContext) -> Result<()> {
+/// stream.enable_latency_tracking();
+/// // ... push and run ticks ...
+/// let latency = stream.latency().unwrap();
+/// println!("run: {}", latency.histogram(StreamOperation::Run).summary());
+/// latency.write_csv(std::io::stdout())?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StreamLatency {
+    histograms: [LatencyHistogram; 3],
+}
+
+impl StreamLatency {
+    /// Returns the histogram of one operation.
+    pub fn histogram(&self, operation: StreamOperation) -> &LatencyHistogram {
+        &self.histograms[operation.index()]
+    }
+
+    pub(crate) fn record(&mut self, operation: StreamOperation, latency: Duration) {
+        self.histograms[operation.index()].record(latency);
+    }
+
+    /// Removes all recorded latencies.
+    pub fn reset(&mut self) {
+        self.histograms.iter_mut().for_each(LatencyHistogram::reset);
+    }
+
+    /// Writes the summary of every operation as CSV, with a header row and one row
+    /// per operation. Latencies are in nanoseconds.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> Result<()> {
+        writeln!(
+            writer,
+            "operation,count,min_ns,p50_ns,p90_ns,p99_ns,p999_ns,max_ns,mean_ns"
+        )?;
+        for operation in StreamOperation::ALL {
+            let s = self.histogram(operation).summary();
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{}",
+                operation.name(),
+                s.count,
+                s.min.as_nanos(),
+                s.p50.as_nanos(),
+                s.p90.as_nanos(),
+                s.p99.as_nanos(),
+                s.p999.as_nanos(),
+                s.max.as_nanos(),
+                s.mean.as_nanos()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_cover_every_value() {
+        for nanos in [0, 1, 63, 64, 65, 127, 128, 1_000, 123_456_789, u64::MAX] {
+            let index = bucket_index(nanos);
+            assert!(index < NUM_BUCKETS);
+            assert!(bucket_lower(index) <= nanos && nanos <= bucket_upper(index));
+        }
+        for index in 0..NUM_BUCKETS - 1 {
+            assert_eq!(bucket_upper(index) + 1, bucket_lower(index + 1));
+        }
+    }
+
+    #[test]
+    fn test_quantiles_within_precision() {
+        let mut histogram = LatencyHistogram::new();
+        for micros in 1..=1000 {
+            histogram.record(Duration::from_micros(micros));
+        }
+        assert_eq!(histogram.count(), 1000);
+        assert_eq!(histogram.min(), Duration::from_micros(1));
+        assert_eq!(histogram.max(), Duration::from_micros(1000));
+
+        for (quantile, expected) in [(0.5, 500_000.0), (0.99, 990_000.0)] {
+            let value = histogram.quantile(quantile).as_nanos() as f64;
+            assert!((value - expected).abs() / expected < 1.0 / 32.0, "{value}");
+        }
+        assert_eq!(histogram.quantile(1.0), histogram.max());
+
+        let mut merged = LatencyHistogram::new();
+        merged.merge(&histogram);
+        assert_eq!(merged.summary(), histogram.summary());
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let mut latency = StreamLatency::default();
+        latency.record(StreamOperation::Run, Duration::from_nanos(50));
+        let mut csv = Vec::new();
+        latency.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[2], "run,1,50,50,50,50,50,50,50");
+    }
+}
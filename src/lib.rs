@@ -40,18 +40,46 @@
 //! }
 //! ```
 
+pub mod bars;
 pub mod batch;
 pub mod buffer;
+#[cfg(feature = "async")]
+pub mod engine;
 pub mod error;
 pub mod executor;
 pub mod ffi;
+pub mod history;
+#[cfg(unix)]
+pub mod isolated;
+pub mod latency;
 pub mod library;
+pub mod missing;
+pub mod owned_stream;
+pub mod registry;
+pub mod replay;
+pub mod scheduler;
+mod snapshot;
 pub mod stream;
+pub mod stream_group;
+pub mod subscription;
+pub mod universe;
+pub mod verify;
+mod version;
 
 // Re-export main types for convenience
-pub use batch::{BatchParams, run_graph};
+pub use batch::{BatchParams, BatchRunner, CancellationToken, OutputLayout, run_graph};
 pub use buffer::BufferNameMap;
-pub use error::{KunQuantError, Result};
-pub use executor::Executor;
+pub use error::{ErrorContext, KunQuantError, Result};
+pub use executor::{Executor, ExecutorBuilder};
+pub use history::OutputHistory;
+#[cfg(unix)]
+pub use isolated::IsolatedRunner;
 pub use library::{Library, Module};
-pub use stream::StreamContext;
+pub use missing::MissingDataPolicy;
+pub use owned_stream::OwnedStreamContext;
+pub use replay::{ReplayData, ReplayResult, StreamReplay};
+pub use stream::{StreamContext, StreamHandle, StreamInputs, WarmUpReport};
+pub use stream_group::StreamGroup;
+pub use subscription::{OutputReceiver, SubscriptionId};
+pub use universe::SymbolStream;
+pub use version::runtime_version;
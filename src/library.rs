@@ -1,7 +1,9 @@
-use crate::error::{KunQuantError, Result};
+use crate::error::{ErrorContext, KunQuantError, Result, ResultExt};
 use crate::ffi;
-use std::ffi::CString;
-use std::path::Path;
+use crate::version;
+use std::ffi::{CStr, CString};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 /// A loaded KunQuant library containing compiled factor modules.
 ///
@@ -25,6 +27,9 @@ use std::path::Path;
 /// Multiple modules can be retrieved and used concurrently from the same library.
 pub struct Library {
     handle: ffi::KunLibraryHandle,
+    path: PathBuf,
+    // Content hash of the library file, computed on first use
+    fingerprint: OnceLock<u64>,
 }
 
 impl Library {
@@ -37,15 +42,19 @@ impl Library {
     /// # Arguments
     ///
     /// * `path` - Path to the compiled library file. Can be any type that implements
-    ///           `AsRef<str>` (e.g., `&str`, `String`, `PathBuf`, etc.)
+    ///   `AsRef<Path>` (e.g., `&str`, `String`, `PathBuf`, `OsString`, etc.); on
+    ///   Unix the path doesn't need to be valid UTF-8
     ///
     /// # Returns
     ///
     /// Returns `Ok(Library)` on successful loading, or an error if:
-    /// - The file doesn't exist or isn't accessible
-    /// - The file isn't a valid KunQuant library
-    /// - The library has incompatible version or architecture
-    /// - System resources are insufficient for loading
+    /// - The file doesn't exist (`LibraryNotFound`)
+    /// - The file can't be loaded (`LibraryLoadFailed`), with the dynamic loader's
+    ///   reason: not a shared library, incompatible architecture, missing
+    ///   dependencies or undefined symbols, or rejected by the runtime
+    /// - The library was built for a different runtime ABI than the loaded
+    ///   runtime, or only one of them carries an ABI tag (`IncompatibleLibrary`),
+    ///   see [`runtime_version`](crate::runtime_version)
     ///
     /// # Examples
     ///
@@ -59,13 +68,29 @@ impl Library {
     /// // Load library from absolute path
     /// let library = Library::load("/opt/factors/alpha_factors.so")?;
     ///
-    /// // Works with String as well
+    /// // Works with String and PathBuf as well
     /// let path = String::from("./test_factors.so");
     /// let library = Library::load(path)?;
+    /// let library = Library::load(std::env::temp_dir().join("factors.so"))?;
     /// # Ok(())
     /// # }
     /// ```
     ///
+    /// Load failures carry the loader's reason:
+    ///
+    /// ```rust,no_run
+    /// use kunquant_rs::{KunQuantError, Library};
+    ///
+    /// match Library::load("factors.so") {
+    ///     Ok(library) => println!("loaded {}", library.path().display()),
+    ///     Err(KunQuantError::LibraryNotFound { path }) => eprintln!("{} is missing", path),
+    ///     Err(KunQuantError::LibraryLoadFailed { path, reason }) => {
+    ///         eprintln!("cannot load {}: {}", path, reason)
+    ///     }
+    ///     Err(e) => eprintln!("{}", e),
+    /// }
+    /// ```
+    ///
     /// # Library Requirements
     ///
     /// - Must be compiled with compatible KunQuant version
@@ -78,23 +103,58 @@ impl Library {
     /// - Library loading is a one-time cost during initialization
     /// - Loaded libraries are cached by the system loader
     /// - Multiple `Library` instances of the same file share underlying resources
-    pub fn load<P: AsRef<str>>(path: P) -> Result<Self> {
-        if !Path::new(path.as_ref()).exists() {
-            return Err(KunQuantError::LibraryLoadFailed {
-                path: path.as_ref().to_string(),
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Err(KunQuantError::LibraryNotFound {
+                path: path.display().to_string(),
             });
         }
-        let path_str = path.as_ref();
-        let c_path = CString::new(path_str)?;
+        let c_path = path_to_cstring(path)?;
+        version::check_library(path)
+            .context(|| ErrorContext::new("load_library").with_library(path))?;
 
-        let handle = unsafe { ffi::kunLoadLibrary(c_path.as_ptr()) };
-        if handle.is_null() {
+        let mut handle = std::ptr::null_mut();
+        let status = unsafe { ffi::kunShimLoadLibrary(c_path.as_ptr(), &mut handle) };
+        let reason = if status != 0 {
+            Some(ffi::last_error())
+        } else if handle.is_null() {
+            Some(load_failure_reason(&c_path))
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
             return Err(KunQuantError::LibraryLoadFailed {
-                path: path_str.to_string(),
+                path: path.display().to_string(),
+                reason,
             });
         }
 
-        Ok(Library { handle })
+        Ok(Library {
+            handle,
+            path: path.to_path_buf(),
+            fingerprint: OnceLock::new(),
+        })
+    }
+
+    /// Returns the path the library was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns a 64-bit FNV-1a hash of the library file's contents.
+    ///
+    /// Used to check that persisted state belongs to the same build of a factor
+    /// library. The file is read once; later calls return the cached value.
+    pub(crate) fn fingerprint(&self) -> Result<u64> {
+        if let Some(&fingerprint) = self.fingerprint.get() {
+            return Ok(fingerprint);
+        }
+        let bytes = std::fs::read(&self.path)?;
+        let fingerprint = bytes.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, &b| {
+            (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+        Ok(*self.fingerprint.get_or_init(|| fingerprint))
     }
 
     /// Retrieves a named factor module from the loaded library.
@@ -106,7 +166,7 @@ impl Library {
     /// # Arguments
     ///
     /// * `name` - The name of the module as defined during compilation. Can be any
-    ///           type that implements `AsRef<str>` (e.g., `&str`, `String`, etc.)
+    ///   type that implements `AsRef<str>` (e.g., `&str`, `String`, etc.)
     ///
     /// # Returns
     ///
@@ -145,7 +205,7 @@ impl Library {
     ///
     /// The returned `Module` maintains a reference to the parent `Library`,
     /// ensuring the library remains loaded for the module's lifetime.
-    pub fn get_module<N: AsRef<str>>(&self, name: N) -> Result<Module> {
+    pub fn get_module<N: AsRef<str>>(&self, name: N) -> Result<Module<'_>> {
         let name_str = name.as_ref();
         let c_name = CString::new(name_str)?;
 
@@ -153,16 +213,72 @@ impl Library {
         if module_handle.is_null() {
             return Err(KunQuantError::ModuleNotFound {
                 name: name_str.to_string(),
+                context: Some(Box::new(
+                    ErrorContext::new("get_module").with_library(&self.path),
+                )),
             });
         }
 
         Ok(Module {
             handle: module_handle,
-            _library: self, // Keep library alive
+            name: name_str.to_string(),
+            library: self, // Keep library alive
         })
     }
 }
 
+#[cfg(unix)]
+fn path_to_cstring(path: &Path) -> Result<CString> {
+    use std::os::unix::ffi::OsStrExt;
+    Ok(CString::new(path.as_os_str().as_bytes())?)
+}
+
+#[cfg(not(unix))]
+fn path_to_cstring(path: &Path) -> Result<CString> {
+    // The runtime takes a narrow string, which can only represent Unicode paths here
+    let path_str = path
+        .to_str()
+        .ok_or_else(|| KunQuantError::LibraryLoadFailed {
+            path: path.display().to_string(),
+            reason: "path is not valid Unicode".to_string(),
+        })?;
+    Ok(CString::new(path_str)?)
+}
+
+/// Explains why the runtime returned no handle for `path`.
+///
+/// The runtime doesn't pass on the loader's error, so the file is opened again
+/// with `dlopen` to obtain it. If that succeeds, the file itself loads fine and it
+/// was the runtime that rejected it.
+#[cfg(unix)]
+fn load_failure_reason(path: &CStr) -> String {
+    unsafe {
+        // Clear any error left over from an earlier call
+        libc::dlerror();
+        let handle = libc::dlopen(path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL);
+        if handle.is_null() {
+            let error = libc::dlerror();
+            if error.is_null() {
+                return "the dynamic loader could not open the file".to_string();
+            }
+            return CStr::from_ptr(error).to_string_lossy().into_owned();
+        }
+        libc::dlclose(handle);
+    }
+    "the runtime rejected the library; it is not a KunQuant factor library or was \
+     built for a different runtime version"
+        .to_string()
+}
+
+#[cfg(not(unix))]
+fn load_failure_reason(_path: &CStr) -> String {
+    "the runtime could not load the library".to_string()
+}
+
+// Library handles are not tied to the loading thread, see "Thread Safety" above
+unsafe impl Send for Library {}
+unsafe impl Sync for Library {}
+
 impl Drop for Library {
     fn drop(&mut self) {
         if !self.handle.is_null() {
@@ -201,12 +317,27 @@ impl Drop for Library {
 /// errors and ensures computation integrity.
 pub struct Module<'a> {
     handle: ffi::KunModuleHandle,
-    _library: &'a Library, // Keep library alive
+    name: String,
+    library: &'a Library, // Keep library alive
 }
 
 impl<'a> Module<'a> {
+    /// Returns the name the module was looked up with.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the library this module belongs to.
+    pub fn library(&self) -> &Library {
+        self.library
+    }
+
     /// Get the raw handle (for internal use)
     pub(crate) fn handle(&self) -> ffi::KunModuleHandle {
         self.handle
     }
 }
+
+// Modules are immutable descriptions of a computation graph, see "Thread Safety" above
+unsafe impl Send for Module<'_> {}
+unsafe impl Sync for Module<'_> {}
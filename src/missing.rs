@@ -0,0 +1,100 @@
+/// What a stream input receives for stocks marked invalid in a masked push.
+///
+/// The runtime advances every stock's state on each `run()`, so a stock can't be
+/// left out of a tick; it can only be fed a substitute value, and with `Suspend`
+/// have its published outputs held.
+///
+/// Set per input with [`StreamContext::set_missing_policy`](crate::StreamContext::set_missing_policy)
+/// and applied by [`StreamContext::push_masked`](crate::StreamContext::push_masked).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingDataPolicy {
+    /// Repeat the stock's last valid value of this input (NaN if there is none yet).
+    #[default]
+    ForwardFill,
+    /// Feed NaN, which propagates into every window containing this tick.
+    Nan,
+    /// Repeat the last valid value like `ForwardFill` and hold the stock's outputs
+    /// at their values of the previous run, see
+    /// [`StreamContext::suspended_stocks`](crate::StreamContext::suspended_stocks).
+    ///
+    /// Only the values read through the context are held: the runtime's windows
+    /// still advance over the repeated value, so a stock resuming after a
+    /// suspension sees it in its windows. Outputs must be resolved before the
+    /// first `run()` to be held.
+    Suspend,
+}
+
+/// Per-input state of the missing-data handling of a stream context.
+pub(crate) struct MissingInput {
+    pub(crate) policy: MissingDataPolicy,
+    // Last value pushed for each stock, NaN before the first push
+    last: Vec<f32>,
+}
+
+impl MissingInput {
+    pub(crate) fn new(policy: MissingDataPolicy, num_stocks: usize) -> Self {
+        MissingInput {
+            policy,
+            last: vec![f32::NAN; num_stocks],
+        }
+    }
+
+    /// Writes `data` with invalid stocks replaced according to the policy into
+    /// `filled`, and sets `suspended[stock]` for stocks suspended by it.
+    pub(crate) fn fill(
+        &self,
+        data: &[f32],
+        valid: &[bool],
+        filled: &mut Vec<f32>,
+        suspended: &mut [bool],
+    ) {
+        filled.clear();
+        filled.extend(data.iter().zip(valid).zip(&self.last).map(
+            |((&value, &valid), &last)| match (valid, self.policy) {
+                (true, _) => value,
+                (false, MissingDataPolicy::Nan) => f32::NAN,
+                (false, MissingDataPolicy::ForwardFill | MissingDataPolicy::Suspend) => last,
+            },
+        ));
+        if self.policy == MissingDataPolicy::Suspend {
+            for (flag, &valid) in suspended.iter_mut().zip(valid) {
+                *flag |= !valid;
+            }
+        }
+    }
+
+    /// Remembers the data pushed for this tick.
+    pub(crate) fn record(&mut self, data: &[f32]) {
+        self.last.copy_from_slice(data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_policies() {
+        let valid = [true, false, true];
+        let mut filled = Vec::new();
+        let mut suspended = [false; 3];
+
+        let mut input = MissingInput::new(MissingDataPolicy::ForwardFill, 3);
+        input.fill(&[1.0, 2.0, 3.0], &valid, &mut filled, &mut suspended);
+        assert!(filled[1].is_nan());
+        input.record(&[1.0, 2.0, 3.0]);
+        input.fill(&[4.0, 0.0, 6.0], &valid, &mut filled, &mut suspended);
+        assert_eq!(filled, vec![4.0, 2.0, 6.0]);
+        assert_eq!(suspended, [false; 3]);
+
+        input.policy = MissingDataPolicy::Nan;
+        input.fill(&[4.0, 0.0, 6.0], &valid, &mut filled, &mut suspended);
+        assert_eq!(filled[0], 4.0);
+        assert!(filled[1].is_nan());
+
+        input.policy = MissingDataPolicy::Suspend;
+        input.fill(&[4.0, 0.0, 6.0], &valid, &mut filled, &mut suspended);
+        assert_eq!(filled, vec![4.0, 2.0, 6.0]);
+        assert_eq!(suspended, [false, true, false]);
+    }
+}
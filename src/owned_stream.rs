@@ -0,0 +1,359 @@
+use crate::error::Result;
+use crate::executor::Executor;
+use crate::history::OutputHistory;
+use crate::latency::StreamLatency;
+use crate::library::{Library, Module};
+use crate::missing::MissingDataPolicy;
+use crate::stream::{StreamContext, StreamHandle, StreamInputs, WarmUpReport};
+use crate::subscription::{OutputReceiver, SubscriptionId};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+/// A streaming context that owns its executor and library and can move between threads.
+///
+/// [`StreamContext`] borrows its executor and module, which ties it to the scope that
+/// created them. `OwnedStreamContext` instead holds `Arc`s to the executor and the
+/// library (and owns the module looked up from it), so it is `'static` and `Send`:
+/// streams can be created in a setup thread and handed to per-market worker threads.
+///
+/// The methods of the wrapped [`StreamContext`] are forwarded, and
+/// [`stream`](OwnedStreamContext::stream) borrows it for code written against
+/// `StreamContext`. References handed out by the context, including the module and
+/// the library it points to, borrow the context and cannot outlive it:
+///
+/// ```rust,compile_fail,E0515
+/// use kunquant_rs::{Library, OwnedStreamContext};
+///
+/// fn leak(stream: OwnedStreamContext) -> &'static Library {
+///     stream.module().library()
+/// }
+/// ```
+///
+/// # Concurrency
+///
+/// - `OwnedStreamContext` is `Send` but not `Sync`: one thread at a time drives a
+///   stream, and moving it to another thread between ticks is safe.
+/// - Any number of streams may share one `Arc<Executor>` and one `Arc<Library>`
+///   and run concurrently on different threads; each stream keeps its own state.
+/// - For latency-critical streams prefer one single-threaded executor per worker
+///   thread, so ticks of different streams never queue on the same executor.
+/// - Output slices borrow the context and are invalidated by the next push or run,
+///   so they cannot be observed from another thread while the stream advances.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use kunquant_rs::{Executor, Library, OwnedStreamContext};
+/// use std::sync::Arc;
+///
+/// # fn main() -> kunquant_rs::Result<()> {
+/// let executor = Arc::new(Executor::single_thread()?);
+/// let library = Arc::new(Library::load("factors.so")?);
+///
+/// let mut stream = OwnedStreamContext::new(executor, library, "alpha_stream", 16)?;
+/// let worker = std::thread::spawn(move || -> kunquant_rs::Result<()> {
+///     stream.push_data("close", &[100.0; 16])?;
+///     stream.run()?;
+///     println!("{:?}", stream.get_current_buffer("alpha")?);
+///     Ok(())
+/// });
+/// worker.join().unwrap()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct OwnedStreamContext {
+    // Field order matters: the stream must be dropped before the module, and the
+    // module before the library and executor it borrows from.
+    stream: StreamContext<'static>,
+    module: Box<Module<'static>>,
+    library: Arc<Library>,
+    executor: Arc<Executor>,
+}
+
+// The stream and module only refer to heap data kept alive by the `Arc`s and the
+// `Box`, none of which is tied to the creating thread.
+unsafe impl Send for OwnedStreamContext {}
+
+impl OwnedStreamContext {
+    /// Creates a streaming context for the named module of `library`.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Executor shared with other contexts
+    /// * `library` - Library containing the streaming module
+    /// * `module_name` - Name of the module inside the library
+    /// * `num_stocks` - Number of stocks to process
+    ///
+    /// # Returns
+    ///
+    /// Returns the context, or an error if the module is not found or the stream
+    /// cannot be created.
+    pub fn new<N: AsRef<str>>(
+        executor: Arc<Executor>,
+        library: Arc<Library>,
+        module_name: N,
+        num_stocks: usize,
+    ) -> Result<Self> {
+        Self::build(
+            executor,
+            library,
+            module_name.as_ref(),
+            |executor, module| StreamContext::new(executor, module, num_stocks),
+        )
+    }
+
+    /// Creates a streaming context from a checkpoint, see [`StreamContext::restore`].
+    pub fn restore<N: AsRef<str>, R: Read>(
+        executor: Arc<Executor>,
+        library: Arc<Library>,
+        module_name: N,
+        reader: R,
+    ) -> Result<Self> {
+        Self::build(
+            executor,
+            library,
+            module_name.as_ref(),
+            |executor, module| StreamContext::restore(executor, module, reader),
+        )
+    }
+
+    fn build<F>(
+        executor: Arc<Executor>,
+        library: Arc<Library>,
+        module_name: &str,
+        create: F,
+    ) -> Result<Self>
+    where
+        F: FnOnce(&'static Executor, &'static Module<'static>) -> Result<StreamContext<'static>>,
+    {
+        // Safety: the references point into `Arc`/`Box` allocations owned by the
+        // returned value, which outlive `stream` thanks to the field order.
+        let library_ref: &'static Library = unsafe { &*Arc::as_ptr(&library) };
+        let executor_ref: &'static Executor = unsafe { &*Arc::as_ptr(&executor) };
+        let module = Box::new(library_ref.get_module(module_name)?);
+        let module_ref: &'static Module<'static> = unsafe { &*(module.as_ref() as *const _) };
+
+        let stream = create(executor_ref, module_ref)?;
+        Ok(OwnedStreamContext {
+            stream,
+            module,
+            library,
+            executor,
+        })
+    }
+
+    /// Returns the shared executor.
+    pub fn executor(&self) -> &Arc<Executor> {
+        &self.executor
+    }
+
+    /// Returns the shared library.
+    pub fn library(&self) -> &Arc<Library> {
+        &self.library
+    }
+
+    /// Returns the module this context runs.
+    pub fn module(&self) -> &Module<'_> {
+        &self.module
+    }
+
+    /// Borrows the wrapped stream context.
+    pub fn stream(&self) -> &StreamContext<'_> {
+        &self.stream
+    }
+
+    /// See [`StreamContext::get_current_buffer`].
+    pub fn get_current_buffer<N: AsRef<str>>(&self, name: N) -> Result<&[f32]> {
+        self.stream.get_current_buffer(name)
+    }
+
+    /// See [`StreamContext::output`].
+    pub fn output(&self, handle: StreamHandle) -> Result<&[f32]> {
+        self.stream.output(handle)
+    }
+
+    /// See [`StreamContext::missing_inputs`].
+    pub fn missing_inputs(&self) -> Vec<&str> {
+        self.stream.missing_inputs()
+    }
+
+    /// See [`StreamContext::has_state_journal`].
+    pub fn has_state_journal(&self) -> bool {
+        self.stream.has_state_journal()
+    }
+
+    /// See [`StreamContext::output_history`].
+    pub fn output_history<N: AsRef<str>>(&self, name: N) -> Option<&OutputHistory> {
+        self.stream.output_history(name)
+    }
+
+    /// See [`StreamContext::save_state`].
+    pub fn save_state<W: Write>(&self, writer: W) -> Result<()> {
+        self.stream.save_state(writer)
+    }
+
+    /// See [`StreamContext::suspended_stocks`].
+    pub fn suspended_stocks(&self) -> impl Iterator<Item = usize> + '_ {
+        self.stream.suspended_stocks()
+    }
+
+    /// See [`StreamContext::is_suspended`].
+    pub fn is_suspended(&self, stock: usize) -> bool {
+        self.stream.is_suspended(stock)
+    }
+
+    /// See [`StreamContext::latency`].
+    pub fn latency(&self) -> Option<StreamLatency> {
+        self.stream.latency()
+    }
+
+    /// See [`StreamContext::reset_latency`].
+    pub fn reset_latency(&self) {
+        self.stream.reset_latency()
+    }
+
+    /// See [`StreamContext::ticks_run`].
+    pub fn ticks_run(&self) -> u64 {
+        self.stream.ticks_run()
+    }
+
+    /// See [`StreamContext::remaining_warm_up`].
+    pub fn remaining_warm_up(&self) -> Option<usize> {
+        self.stream.remaining_warm_up()
+    }
+
+    /// See [`StreamContext::num_stocks`].
+    pub fn num_stocks(&self) -> usize {
+        self.stream.num_stocks()
+    }
+
+    /// See [`StreamContext::get_buffer_handle`].
+    pub fn get_buffer_handle<N: AsRef<str>>(&mut self, name: N) -> Result<usize> {
+        self.stream.get_buffer_handle(name)
+    }
+
+    /// See [`StreamContext::resolve`].
+    pub fn resolve<N: AsRef<str>>(&mut self, name: N) -> Result<StreamHandle> {
+        self.stream.resolve(name)
+    }
+
+    /// See [`StreamContext::push_data`].
+    pub fn push_data<N: AsRef<str>>(&mut self, name: N, data: &[f32]) -> Result<()> {
+        self.stream.push_data(name, data)
+    }
+
+    /// See [`StreamContext::push`].
+    pub fn push(&mut self, handle: StreamHandle, data: &[f32]) -> Result<()> {
+        self.stream.push(handle, data)
+    }
+
+    /// See [`StreamContext::push_all`].
+    pub fn push_all(&mut self, inputs: &StreamInputs) -> Result<()> {
+        self.stream.push_all(inputs)
+    }
+
+    /// See [`StreamContext::discard_pushes`].
+    pub fn discard_pushes(&mut self) {
+        self.stream.discard_pushes()
+    }
+
+    /// See [`StreamContext::set_missing_policy`].
+    pub fn set_missing_policy<N: AsRef<str>>(
+        &mut self,
+        name: N,
+        policy: MissingDataPolicy,
+    ) -> Result<()> {
+        self.stream.set_missing_policy(name, policy)
+    }
+
+    /// See [`StreamContext::push_masked`].
+    pub fn push_masked<N: AsRef<str>>(
+        &mut self,
+        name: N,
+        data: &[f32],
+        valid: &[bool],
+    ) -> Result<()> {
+        self.stream.push_masked(name, data, valid)
+    }
+
+    /// See [`StreamContext::set_required_inputs`].
+    pub fn set_required_inputs<N: AsRef<str>>(&mut self, names: &[N]) -> Result<()> {
+        self.stream.set_required_inputs(names)
+    }
+
+    /// See [`StreamContext::run`].
+    pub fn run(&mut self) -> Result<()> {
+        self.stream.run()
+    }
+
+    /// See [`StreamContext::enable_state_journal`].
+    pub fn enable_state_journal(&mut self, capacity: usize) {
+        self.stream.enable_state_journal(capacity)
+    }
+
+    /// See [`StreamContext::enable_output_history`].
+    pub fn enable_output_history<N: AsRef<str>>(&mut self, name: N, capacity: usize) -> Result<()> {
+        self.stream.enable_output_history(name, capacity)
+    }
+
+    /// See [`StreamContext::disable_output_history`].
+    pub fn disable_output_history<N: AsRef<str>>(&mut self, name: N) -> bool {
+        self.stream.disable_output_history(name)
+    }
+
+    /// See [`StreamContext::subscribe`].
+    pub fn subscribe<N, F>(&mut self, name: N, callback: F) -> Result<SubscriptionId>
+    where
+        N: AsRef<str>,
+        F: FnMut(u64, &[f32]) + Send + 'static,
+    {
+        self.stream.subscribe(name, callback)
+    }
+
+    /// See [`StreamContext::subscribe_changes`].
+    pub fn subscribe_changes<N, F>(&mut self, name: N, callback: F) -> Result<SubscriptionId>
+    where
+        N: AsRef<str>,
+        F: FnMut(u64, &[f32], &[usize]) + Send + 'static,
+    {
+        self.stream.subscribe_changes(name, callback)
+    }
+
+    /// See [`StreamContext::subscribe_channel`].
+    pub fn subscribe_channel<N: AsRef<str>>(
+        &mut self,
+        name: N,
+    ) -> Result<(SubscriptionId, OutputReceiver)> {
+        self.stream.subscribe_channel(name)
+    }
+
+    /// See [`StreamContext::unsubscribe`].
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        self.stream.unsubscribe(id)
+    }
+
+    /// See [`StreamContext::enable_latency_tracking`].
+    pub fn enable_latency_tracking(&mut self) {
+        self.stream.enable_latency_tracking()
+    }
+
+    /// See [`StreamContext::disable_latency_tracking`].
+    pub fn disable_latency_tracking(&mut self) {
+        self.stream.disable_latency_tracking()
+    }
+
+    /// See [`StreamContext::set_lookback`].
+    pub fn set_lookback(&mut self, lookback: usize) {
+        self.stream.set_lookback(lookback)
+    }
+
+    /// See [`StreamContext::warm_up`].
+    pub fn warm_up(
+        &mut self,
+        history: &HashMap<&str, &[f32]>,
+        num_times: usize,
+    ) -> Result<WarmUpReport> {
+        self.stream.warm_up(history, num_times)
+    }
+}
@@ -0,0 +1,163 @@
+use crate::error::{KunQuantError, Result};
+use crate::executor::{Executor, ExecutorBuilder};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, Weak};
+
+/// Name of the pool returned by [`Executor::shared`].
+pub const DEFAULT_POOL: &str = "default";
+
+/// Environment variable read for the thread count of the default pool, unless it
+/// is configured with [`Executor::configure_shared`].
+pub const DEFAULT_POOL_THREADS_ENV: &str = "KUNQUANT_NUM_THREADS";
+
+/// A shared executor pool, as listed by [`pools`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolInfo {
+    /// Name of the pool
+    pub name: String,
+    /// Worker threads of the live executor, or of the next one if none is alive
+    pub threads: Option<usize>,
+    /// Number of `Arc`s currently holding the executor; 0 if it is not alive
+    pub references: usize,
+}
+
+struct Pool {
+    builder: ExecutorBuilder,
+    executor: Weak<Executor>,
+    threads: Option<usize>,
+}
+
+fn registry() -> &'static Mutex<HashMap<String, Pool>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, Pool>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let builder = ExecutorBuilder::new().with_threads_from_env(DEFAULT_POOL_THREADS_ENV);
+        let mut pools = HashMap::new();
+        pools.insert(
+            DEFAULT_POOL.to_string(),
+            Pool {
+                threads: builder.resolve_threads().ok(),
+                builder,
+                executor: Weak::new(),
+            },
+        );
+        Mutex::new(pools)
+    })
+}
+
+/// Lists the configured pools, sorted by name.
+pub fn pools() -> Vec<PoolInfo> {
+    let pools = registry().lock().unwrap_or_else(PoisonError::into_inner);
+    let mut info: Vec<PoolInfo> = pools
+        .iter()
+        .map(|(name, pool)| PoolInfo {
+            name: name.clone(),
+            threads: pool.threads,
+            references: pool.executor.strong_count(),
+        })
+        .collect();
+    info.sort_by(|a, b| a.name.cmp(&b.name));
+    info
+}
+
+impl Executor {
+    /// Returns the process-wide default executor, creating it on first use.
+    ///
+    /// Equivalent to `Executor::shared_named("default")`. Unless configured with
+    /// [`configure_shared`](Executor::configure_shared), the default pool has as
+    /// many threads as `KUNQUANT_NUM_THREADS` says, or one per available CPU.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use kunquant_rs::{Executor, Library, OwnedStreamContext};
+    /// use std::sync::Arc;
+    ///
+    /// # fn main() -> kunquant_rs::Result<()> {
+    /// // Every component asks for the shared executor instead of creating its own
+    /// let executor = Executor::shared()?;
+    /// let library = Arc::new(Library::load("factors.so")?);
+    /// let stream = OwnedStreamContext::new(executor, library, "alpha_stream", 16)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn shared() -> Result<Arc<Executor>> {
+        Self::shared_named(DEFAULT_POOL)
+    }
+
+    /// Returns the executor of a named pool, creating it if no reference to it is
+    /// alive.
+    ///
+    /// Pools are reference counted: the executor is destroyed when the last `Arc`
+    /// is dropped and created again by the next call.
+    ///
+    /// # Returns
+    ///
+    /// Returns the executor, or an error if no pool of that name is configured
+    /// (`InvalidExecutorConfig`) or the executor cannot be created.
+    pub fn shared_named<N: AsRef<str>>(name: N) -> Result<Arc<Executor>> {
+        let name = name.as_ref();
+        let mut pools = registry().lock().unwrap_or_else(PoisonError::into_inner);
+        let pool = pools
+            .get_mut(name)
+            .ok_or_else(|| KunQuantError::InvalidExecutorConfig {
+                reason: format!("no shared executor pool named '{}'", name),
+            })?;
+        if let Some(executor) = pool.executor.upgrade() {
+            return Ok(executor);
+        }
+
+        let threads = pool.builder.resolve_threads()?;
+        let executor = Arc::new(pool.builder.clone().with_threads(threads).build()?);
+        pool.executor = Arc::downgrade(&executor);
+        pool.threads = Some(threads);
+        Ok(executor)
+    }
+
+    /// Configures the named pool, or adds it if it doesn't exist yet.
+    ///
+    /// Only configured pools (and the default pool) can be requested, which keeps
+    /// the set of executors in the process bounded. If the pool's executor is
+    /// alive, it keeps running and the new configuration applies once every
+    /// reference to it has been dropped.
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if the builder's thread count is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use kunquant_rs::{Executor, ExecutorBuilder};
+    ///
+    /// # fn main() -> kunquant_rs::Result<()> {
+    /// Executor::configure_shared("default", ExecutorBuilder::new().with_threads(8))?;
+    /// Executor::configure_shared("research", ExecutorBuilder::new().with_cpus(8..16))?;
+    ///
+    /// let research = Executor::shared_named("research")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn configure_shared<N: AsRef<str>>(name: N, builder: ExecutorBuilder) -> Result<()> {
+        let threads = builder.resolve_threads()?;
+        let mut pools = registry().lock().unwrap_or_else(PoisonError::into_inner);
+        match pools.get_mut(name.as_ref()) {
+            Some(pool) => {
+                pool.builder = builder;
+                if pool.executor.strong_count() == 0 {
+                    pool.threads = Some(threads);
+                }
+            }
+            None => {
+                pools.insert(
+                    name.as_ref().to_string(),
+                    Pool {
+                        builder,
+                        executor: Weak::new(),
+                        threads: Some(threads),
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,348 @@
+use crate::error::{KunQuantError, Result};
+use crate::executor::Executor;
+use crate::library::Module;
+use crate::stream::StreamContext;
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// Replays historical inputs through a stream module and collects every tick's output.
+///
+/// Each replay runs on a fresh [`StreamContext`], pushing one row of the time-series
+/// inputs per tick and copying the outputs of that tick into a `[time][stock]`
+/// matrix. The result has the same layout as the outputs of a batch
+/// [`run_graph`](crate::run_graph) over the same inputs with `cur_time = 0`, so the
+/// two can be compared element by element.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use kunquant_rs::{Executor, Library, StreamReplay};
+/// use std::collections::HashMap;
+///
+/// # fn main() -> kunquant_rs::Result<()> {
+/// let executor = Executor::single_thread()?;
+/// let library = Library::load("factors.so")?;
+/// let module = library.get_module("alpha_stream")?;
+///
+/// let num_time = 240;
+/// let close = vec![100.0f32; num_time * 16];
+/// let open = vec![99.0f32; num_time * 16];
+/// let inputs = HashMap::from([("close", close.as_slice()), ("open", open.as_slice())]);
+///
+/// let result = StreamReplay::new(&executor, &module, 16).run(&inputs, &["alpha"], num_time)?;
+/// let alpha = result.output("alpha").unwrap();
+/// println!("last tick: {:?}", &alpha[(num_time - 1) * 16..]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct StreamReplay<'a> {
+    executor: &'a Executor,
+    module: &'a Module<'a>,
+    num_stocks: usize,
+}
+
+impl<'a> StreamReplay<'a> {
+    /// Creates a replay driver for a stream module.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Executor used for the stream contexts
+    /// * `module` - Module compiled with `output_layout="STREAM"`
+    /// * `num_stocks` - Number of stocks per tick
+    pub fn new(executor: &'a Executor, module: &'a Module<'a>, num_stocks: usize) -> Self {
+        StreamReplay {
+            executor,
+            module,
+            num_stocks,
+        }
+    }
+
+    /// Replays `num_time` ticks and returns the requested outputs.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - Input time series in `[time][stock]` layout, `num_time * num_stocks` each
+    /// * `output_names` - Outputs to collect
+    /// * `num_time` - Number of ticks to replay
+    ///
+    /// # Returns
+    ///
+    /// The collected outputs, or an error if an input has the wrong size, a buffer
+    /// name is unknown or a tick fails to run.
+    pub fn run(
+        &self,
+        inputs: &HashMap<&str, &[f32]>,
+        output_names: &[&str],
+        num_time: usize,
+    ) -> Result<ReplayResult> {
+        let mut data: Vec<Vec<f32>> = output_names
+            .iter()
+            .map(|_| vec![0.0; num_time * self.num_stocks])
+            .collect();
+        {
+            let mut outputs: HashMap<&str, &mut [f32]> = output_names
+                .iter()
+                .copied()
+                .zip(data.iter_mut().map(|d| d.as_mut_slice()))
+                .collect();
+            self.run_into(inputs, &mut outputs, num_time)?;
+        }
+
+        Ok(ReplayResult {
+            num_time,
+            num_stocks: self.num_stocks,
+            outputs: output_names
+                .iter()
+                .map(|name| name.to_string())
+                .zip(data)
+                .collect(),
+        })
+    }
+
+    /// Replays `num_time` ticks, writing the outputs into caller-provided buffers.
+    ///
+    /// Every output buffer must hold `num_time * num_stocks` values, like the output
+    /// buffers of a batch computation.
+    pub fn run_into(
+        &self,
+        inputs: &HashMap<&str, &[f32]>,
+        outputs: &mut HashMap<&str, &mut [f32]>,
+        num_time: usize,
+    ) -> Result<()> {
+        let expected = num_time * self.num_stocks;
+        for (name, buffer) in outputs.iter() {
+            if buffer.len() != expected {
+                return Err(KunQuantError::BufferSizeMismatch {
+                    name: name.to_string(),
+                    expected,
+                    actual: buffer.len(),
+                    context: None,
+                });
+            }
+        }
+
+        let mut stream = StreamContext::new(self.executor, self.module, self.num_stocks)?;
+        let mut handles = Vec::with_capacity(outputs.len());
+        for (name, buffer) in outputs.iter_mut() {
+            handles.push((stream.resolve(name)?, buffer));
+        }
+
+        let num_stocks = self.num_stocks;
+        stream.warm_up_with(inputs, num_time, |t, stream| {
+            let row = t * num_stocks..(t + 1) * num_stocks;
+            for (handle, buffer) in handles.iter_mut() {
+                buffer[row.clone()].copy_from_slice(stream.output(*handle)?);
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Reads bars from CSV (see [`ReplayData::from_csv`]) and replays them.
+    pub fn run_csv<R: BufRead>(&self, reader: R, output_names: &[&str]) -> Result<ReplayResult> {
+        let data = ReplayData::from_csv(reader, self.num_stocks)?;
+        self.run(&data.inputs(), output_names, data.num_time())
+    }
+}
+
+/// Outputs of a [`StreamReplay`], one `[time][stock]` matrix per output.
+#[derive(Debug, Clone)]
+pub struct ReplayResult {
+    num_time: usize,
+    num_stocks: usize,
+    outputs: Vec<(String, Vec<f32>)>,
+}
+
+impl ReplayResult {
+    /// Number of replayed ticks.
+    pub fn num_time(&self) -> usize {
+        self.num_time
+    }
+
+    /// Number of stocks per tick.
+    pub fn num_stocks(&self) -> usize {
+        self.num_stocks
+    }
+
+    /// Returns the `[time][stock]` matrix of an output, flattened row by row.
+    pub fn output(&self, name: &str) -> Option<&[f32]> {
+        self.outputs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, data)| data.as_slice())
+    }
+
+    /// Returns one tick of an output.
+    pub fn row(&self, name: &str, time: usize) -> Option<&[f32]> {
+        if time >= self.num_time {
+            return None;
+        }
+        self.output(name)
+            .map(|data| &data[time * self.num_stocks..(time + 1) * self.num_stocks])
+    }
+
+    /// Consumes the result, returning the output matrices keyed by name.
+    pub fn into_outputs(self) -> HashMap<String, Vec<f32>> {
+        self.outputs.into_iter().collect()
+    }
+}
+
+/// Time-series inputs loaded from a file, in `[time][stock]` layout.
+#[derive(Debug, Clone)]
+pub struct ReplayData {
+    num_time: usize,
+    num_stocks: usize,
+    inputs: Vec<(String, Vec<f32>)>,
+}
+
+impl ReplayData {
+    /// Parses bars in long CSV format.
+    ///
+    /// The first line is a header `time,stock,<input>,<input>,...`. Every following
+    /// line holds one bar: an opaque time label, the stock index (`0..num_stocks`)
+    /// and one value per input. Lines must be grouped by time in chronological
+    /// order; a new time label starts a new tick. Stocks without a bar in a tick
+    /// and empty fields are filled with NaN. Blank lines are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kunquant_rs::ReplayData;
+    ///
+    /// let csv = "time,stock,close,open\n09:31,0,10.5,10.0\n09:31,1,20.5,20.0\n09:32,0,10.7,10.5\n";
+    /// let data = ReplayData::from_csv(csv.as_bytes(), 2).unwrap();
+    /// assert_eq!(data.num_time(), 2);
+    /// assert_eq!(&data.input("close").unwrap()[..2], &[10.5, 20.5]);
+    /// assert!(data.input("close").unwrap()[3].is_nan());
+    /// ```
+    pub fn from_csv<R: BufRead>(reader: R, num_stocks: usize) -> Result<Self> {
+        let mut lines = reader.lines().enumerate();
+        let header = match lines.next() {
+            Some((_, line)) => line?,
+            None => return Err(invalid(1, "missing header")),
+        };
+        let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+        if columns.len() < 3 || columns[0] != "time" || columns[1] != "stock" {
+            return Err(invalid(1, "header must be `time,stock,<input>,...`"));
+        }
+
+        let mut inputs: Vec<(String, Vec<f32>)> = columns[2..]
+            .iter()
+            .map(|name| (name.to_string(), Vec::new()))
+            .collect();
+        let mut num_time = 0;
+        let mut current_time: Option<String> = None;
+
+        for (idx, line) in lines {
+            let line_no = idx + 1;
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() != columns.len() {
+                return Err(invalid(
+                    line_no,
+                    format!("expected {} fields, got {}", columns.len(), fields.len()),
+                ));
+            }
+
+            if current_time.as_deref() != Some(fields[0]) {
+                current_time = Some(fields[0].to_string());
+                num_time += 1;
+                for (_, data) in inputs.iter_mut() {
+                    data.resize(num_time * num_stocks, f32::NAN);
+                }
+            }
+
+            let stock: usize = fields[1]
+                .parse()
+                .map_err(|_| invalid(line_no, format!("invalid stock index '{}'", fields[1])))?;
+            if stock >= num_stocks {
+                return Err(invalid(
+                    line_no,
+                    format!(
+                        "stock index {} out of range for {} stocks",
+                        stock, num_stocks
+                    ),
+                ));
+            }
+
+            let offset = (num_time - 1) * num_stocks + stock;
+            for ((name, data), field) in inputs.iter_mut().zip(&fields[2..]) {
+                if field.is_empty() {
+                    continue;
+                }
+                data[offset] = field
+                    .parse()
+                    .map_err(|_| invalid(line_no, format!("invalid {} value '{}'", name, field)))?;
+            }
+        }
+
+        Ok(ReplayData {
+            num_time,
+            num_stocks,
+            inputs,
+        })
+    }
+
+    /// Number of ticks.
+    pub fn num_time(&self) -> usize {
+        self.num_time
+    }
+
+    /// Number of stocks per tick.
+    pub fn num_stocks(&self) -> usize {
+        self.num_stocks
+    }
+
+    /// Returns the `[time][stock]` series of an input.
+    pub fn input(&self, name: &str) -> Option<&[f32]> {
+        self.inputs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, data)| data.as_slice())
+    }
+
+    /// Returns all inputs in the form accepted by [`StreamReplay::run`] and batch runs.
+    pub fn inputs(&self) -> HashMap<&str, &[f32]> {
+        self.inputs
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.as_slice()))
+            .collect()
+    }
+}
+
+fn invalid<S: Into<String>>(line: usize, reason: S) -> KunQuantError {
+    KunQuantError::InvalidReplayData {
+        line,
+        reason: reason.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_fills_missing_bars_with_nan() {
+        let csv = "time,stock,close\n1,1,2.0\n\n2,0,3.0\n2,1,\n";
+        let data = ReplayData::from_csv(csv.as_bytes(), 2).unwrap();
+        assert_eq!(data.num_time(), 2);
+        let close = data.input("close").unwrap();
+        assert!(close[0].is_nan());
+        assert_eq!(close[1], 2.0);
+        assert_eq!(close[2], 3.0);
+        assert!(close[3].is_nan());
+    }
+
+    #[test]
+    fn test_csv_reports_line_of_bad_data() {
+        let csv = "time,stock,close\n1,0,1.0\n1,5,2.0\n";
+        match ReplayData::from_csv(csv.as_bytes(), 2) {
+            Err(KunQuantError::InvalidReplayData { line, .. }) => assert_eq!(line, 3),
+            other => panic!("Expected InvalidReplayData, got {:?}", other),
+        }
+        assert!(ReplayData::from_csv("close,open\n".as_bytes(), 2).is_err());
+    }
+}
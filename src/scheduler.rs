@@ -0,0 +1,648 @@
+use crate::batch::{BatchParams, BatchRunner, CancellationToken, OutputLayout, check_chunk_len};
+use crate::error::{KunQuantError, Result};
+use crate::executor::Executor;
+use crate::library::Library;
+use std::any::Any;
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::JoinHandle;
+
+/// Scheduling priority of a [`BatchJob`]. Higher priorities are dispatched first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    /// Large backfills and other work that can wait
+    Background,
+    /// Regular jobs
+    #[default]
+    Normal,
+    /// Research queries and other jobs someone is waiting for
+    Interactive,
+}
+
+/// A batch computation submitted to a [`JobScheduler`].
+///
+/// The job owns its input data; outputs are allocated by the scheduler and
+/// returned by [`JobHandle::wait`]. Outputs hold `params.length` rows, like the
+/// output buffers of [`BatchRunner`].
+pub struct BatchJob {
+    library: Arc<Library>,
+    module_name: String,
+    params: BatchParams,
+    inputs: HashMap<String, Vec<f32>>,
+    outputs: Vec<String>,
+    priority: Priority,
+    tenant: String,
+    chunk_len: Option<usize>,
+    layout: Option<OutputLayout>,
+}
+
+impl BatchJob {
+    /// Creates a job running the named module of `library` over `params`.
+    pub fn new<N: AsRef<str>>(library: Arc<Library>, module_name: N, params: BatchParams) -> Self {
+        BatchJob {
+            library,
+            module_name: module_name.as_ref().to_string(),
+            params,
+            inputs: HashMap::new(),
+            outputs: Vec::new(),
+            priority: Priority::Normal,
+            tenant: DEFAULT_TENANT.to_string(),
+            chunk_len: None,
+            layout: None,
+        }
+    }
+
+    /// Adds an input buffer of `num_stocks * total_time` values.
+    pub fn with_input<N: AsRef<str>>(mut self, name: N, data: Vec<f32>) -> Self {
+        self.inputs.insert(name.as_ref().to_string(), data);
+        self
+    }
+
+    /// Requests an output buffer.
+    pub fn with_output<N: AsRef<str>>(mut self, name: N) -> Self {
+        self.outputs.push(name.as_ref().to_string());
+        self
+    }
+
+    /// Sets the priority. Defaults to [`Priority::Normal`].
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Sets the tenant whose quota the job counts against. Defaults to `"default"`.
+    pub fn with_tenant<N: AsRef<str>>(mut self, tenant: N) -> Self {
+        self.tenant = tenant.as_ref().to_string();
+        self
+    }
+
+    /// Computes the job in chunks of `chunk_len` time steps, see
+    /// [`BatchRunner::with_chunk_len`]. Chunks are the granularity of progress
+    /// reports and cancellation.
+    pub fn with_chunk_len(mut self, chunk_len: usize) -> Self {
+        self.chunk_len = Some(chunk_len);
+        self
+    }
+
+    /// Declares the output layout the module was compiled with, see
+    /// [`BatchRunner::with_output_layout`]. Jobs split into chunks or scheduler
+    /// slices need [`OutputLayout::TS`].
+    pub fn with_output_layout(mut self, layout: OutputLayout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+}
+
+/// Name of the tenant of jobs submitted without [`BatchJob::with_tenant`].
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Status of a submitted job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JobStatus {
+    /// Waiting for an executor or for its tenant's quota
+    Queued,
+    /// Being computed
+    Running,
+    /// Finished successfully; the outputs can be taken with `wait()`
+    Completed,
+    /// Finished with an error, returned by `wait()`
+    Failed,
+    /// Cancelled before finishing
+    Cancelled,
+}
+
+enum JobState {
+    Queued,
+    Running,
+    // The outcome is taken by `wait()`
+    Finished(JobStatus, Option<Result<HashMap<String, Vec<f32>>>>),
+}
+
+struct JobShared {
+    state: Mutex<JobState>,
+    finished: Condvar,
+    completed: Arc<AtomicUsize>,
+    total: usize,
+    cancellation: CancellationToken,
+}
+
+impl JobShared {
+    fn state(&self) -> MutexGuard<'_, JobState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn set_running(&self) {
+        *self.state() = JobState::Running;
+    }
+
+    fn set_queued(&self) {
+        *self.state() = JobState::Queued;
+    }
+
+    fn finish(&self, result: Result<HashMap<String, Vec<f32>>>) {
+        let status = match &result {
+            Ok(_) => JobStatus::Completed,
+            Err(KunQuantError::Cancelled { .. }) => JobStatus::Cancelled,
+            Err(_) => JobStatus::Failed,
+        };
+        *self.state() = JobState::Finished(status, Some(result));
+        self.finished.notify_all();
+    }
+
+    fn cancelled(&self) -> KunQuantError {
+        KunQuantError::Cancelled {
+            completed: self.completed.load(Ordering::Relaxed),
+            total: self.total,
+        }
+    }
+}
+
+/// Handle of a job submitted to a [`JobScheduler`].
+pub struct JobHandle {
+    id: u64,
+    shared: Arc<JobShared>,
+    inner: Arc<Inner>,
+}
+
+impl JobHandle {
+    /// Identifier of the job, unique within its scheduler.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the current status of the job.
+    pub fn status(&self) -> JobStatus {
+        match &*self.shared.state() {
+            JobState::Queued => JobStatus::Queued,
+            JobState::Running => JobStatus::Running,
+            JobState::Finished(status, _) => *status,
+        }
+    }
+
+    /// Number of time steps computed so far.
+    pub fn completed_steps(&self) -> usize {
+        self.shared.completed.load(Ordering::Relaxed)
+    }
+
+    /// Total number of time steps of the job.
+    pub fn total_steps(&self) -> usize {
+        self.shared.total
+    }
+
+    /// Fraction of the job computed so far, between 0 and 1.
+    pub fn progress(&self) -> f64 {
+        match self.shared.total {
+            0 => 1.0,
+            total => self.completed_steps() as f64 / total as f64,
+        }
+    }
+
+    /// Requests cancellation. A queued job is removed from the queue and finishes
+    /// as cancelled right away; a running job stops at its next chunk boundary.
+    pub fn cancel(&self) {
+        let mut queue = self.inner.queue();
+        self.shared.cancellation.cancel();
+        if let Some(index) = queue.jobs.iter().position(|job| job.id == self.id) {
+            let job = queue.jobs.remove(index);
+            job.shared.finish(Err(job.shared.cancelled()));
+        }
+        drop(queue);
+        // Jobs of the same tenant or of lower priority may be dispatchable now
+        self.inner.available.notify_all();
+    }
+
+    /// Blocks until the job has finished and returns its outputs keyed by name.
+    ///
+    /// # Returns
+    ///
+    /// Returns the outputs, or the job's error; `Cancelled` if it was cancelled or
+    /// the scheduler was dropped first.
+    pub fn wait(self) -> Result<HashMap<String, Vec<f32>>> {
+        let mut state = self.shared.state();
+        loop {
+            if let JobState::Finished(_, result) = &mut *state {
+                return result
+                    .take()
+                    .expect("job outcome is only taken by its handle");
+            }
+            state = self
+                .shared
+                .finished
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+}
+
+struct Job {
+    id: u64,
+    spec: BatchJob,
+    outputs: Vec<(String, Vec<f32>)>,
+    shared: Arc<JobShared>,
+}
+
+struct Queue {
+    jobs: Vec<Job>,
+    // Running jobs per tenant
+    running: HashMap<String, usize>,
+    next_id: u64,
+    shutdown: bool,
+}
+
+struct Inner {
+    queue: Mutex<Queue>,
+    available: Condvar,
+    quotas: HashMap<String, usize>,
+    default_quota: usize,
+    slice_len: Option<usize>,
+}
+
+impl Inner {
+    fn queue(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn quota(&self, tenant: &str) -> usize {
+        self.quotas
+            .get(tenant)
+            .copied()
+            .unwrap_or(self.default_quota)
+    }
+
+    /// Takes the next job to run, or `None` once the scheduler shuts down.
+    fn next_job(&self) -> Option<Job> {
+        let mut queue = self.queue();
+        loop {
+            if queue.shutdown {
+                return None;
+            }
+            // Highest priority first, then submission order; ids grow with submission
+            let next = queue
+                .jobs
+                .iter()
+                .enumerate()
+                .filter(|(_, job)| {
+                    let running = queue.running.get(&job.spec.tenant).copied().unwrap_or(0);
+                    running < self.quota(&job.spec.tenant)
+                })
+                .max_by(|(_, a), (_, b)| {
+                    a.spec.priority.cmp(&b.spec.priority).then(b.id.cmp(&a.id))
+                })
+                .map(|(index, _)| index);
+            if let Some(index) = next {
+                let job = queue.jobs.remove(index);
+                *queue.running.entry(job.spec.tenant.clone()).or_default() += 1;
+                job.shared.set_running();
+                return Some(job);
+            }
+            queue = self
+                .available
+                .wait(queue)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    fn worker(&self, executor: &Executor) {
+        while let Some(mut job) = self.next_job() {
+            // A panicking job fails instead of taking the dispatcher thread down
+            let result =
+                panic::catch_unwind(AssertUnwindSafe(|| self.run_slice(executor, &mut job)))
+                    .unwrap_or_else(|payload| {
+                        Err(KunQuantError::RuntimeError {
+                            operation: "batch_job".to_string(),
+                            message: format!("job panicked: {}", panic_message(payload.as_ref())),
+                            context: None,
+                        })
+                    });
+
+            let mut queue = self.queue();
+            if let Some(running) = queue.running.get_mut(&job.spec.tenant) {
+                *running -= 1;
+            }
+            match result {
+                Ok(true) => {
+                    job.shared
+                        .finish(Ok(std::mem::take(&mut job.outputs).into_iter().collect()));
+                }
+                // `cancel()` only finds queued jobs, so it is handled here for running ones
+                Ok(false) if queue.shutdown || job.shared.cancellation.is_cancelled() => {
+                    job.shared.finish(Err(job.shared.cancelled()))
+                }
+                Ok(false) => {
+                    job.shared.set_queued();
+                    queue.jobs.push(job);
+                }
+                Err(e) => job.shared.finish(Err(e)),
+            }
+            drop(queue);
+            // A slot of the tenant's quota or a requeued job may unblock any worker
+            self.available.notify_all();
+        }
+    }
+
+    /// Computes the next slice of `job` and returns whether the job is complete.
+    fn run_slice(&self, executor: &Executor, job: &mut Job) -> Result<bool> {
+        let params = &job.spec.params;
+        let completed = job.shared.completed.load(Ordering::Relaxed);
+        let remaining = params.length - completed;
+        let length = self
+            .slice_len
+            .map_or(remaining, |slice| slice.min(remaining));
+        let num_stocks = params.num_stocks;
+        if let Some(slice_len) = self.slice_len {
+            // Slices offset the output rows just like chunks
+            check_chunk_len(slice_len, params.length, job.spec.layout)?;
+        }
+
+        let module = job.spec.library.get_module(&job.spec.module_name)?;
+        let inputs: HashMap<&str, &[f32]> = job
+            .spec
+            .inputs
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.as_slice()))
+            .collect();
+        let mut outputs: HashMap<&str, &mut [f32]> = job
+            .outputs
+            .iter_mut()
+            .map(|(name, data)| {
+                let rows = &mut data[completed * num_stocks..(completed + length) * num_stocks];
+                (name.as_str(), rows)
+            })
+            .collect();
+        let slice_params = BatchParams::new(
+            num_stocks,
+            params.total_time,
+            params.cur_time + completed,
+            length,
+        )?;
+
+        let mut runner = BatchRunner::new(executor, &module)
+            .with_cancellation(job.shared.cancellation.clone())
+            .with_progress(job.shared.completed.clone());
+        if let Some(chunk_len) = job.spec.chunk_len {
+            runner = runner.with_chunk_len(chunk_len);
+        }
+        if let Some(layout) = job.spec.layout {
+            runner = runner.with_output_layout(layout);
+        }
+        match runner.run(&inputs, &mut outputs, &slice_params) {
+            Ok(()) => Ok(completed + length == params.length),
+            Err(KunQuantError::Cancelled { .. }) => Err(job.shared.cancelled()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Returns the message of a panic payload, if it is a string.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+/// Queues batch jobs by priority and dispatches them to a set of executors.
+///
+/// Every executor is driven by one dispatcher thread, so at most as many jobs run
+/// at once as there are executors. Whenever an executor becomes free, the queued
+/// job with the highest [`Priority`] whose tenant is below its quota of running
+/// jobs is dispatched; jobs of equal priority run in submission order.
+///
+/// A job normally occupies its executor until it finishes. With
+/// [`with_slice_len`](JobSchedulerBuilder::with_slice_len) jobs are instead
+/// computed in slices of time steps and requeued between slices, so an
+/// interactive query waits for at most one slice of a running backfill. Like
+/// chunks, slices offset the output rows, so jobs longer than a slice must declare
+/// [`OutputLayout::TS`] with [`BatchJob::with_output_layout`] and fail with
+/// `InvalidBatchConfig` otherwise. Slices also restart windows over intermediate
+/// values, see [`BatchRunner`'s data layout](crate::BatchRunner#data-layout).
+///
+/// Dropping the scheduler cancels the queued jobs and waits for the running
+/// slices to finish.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use kunquant_rs::scheduler::{BatchJob, JobScheduler, Priority};
+/// use kunquant_rs::{BatchParams, Executor, Library, OutputLayout};
+/// use std::sync::Arc;
+///
+/// # fn main() -> kunquant_rs::Result<()> {
+/// let library = Arc::new(Library::load("factors.so")?);
+/// let scheduler = JobScheduler::builder()
+///     .with_executor(Arc::new(Executor::multi_thread(8)?))
+///     .with_tenant_quota("backfill", 1)
+///     .with_slice_len(250)
+///     .build()?;
+///
+/// let backfill = scheduler.submit(
+///     BatchJob::new(library.clone(), "alpha001", BatchParams::full_range(4000, 5000)?)
+///         .with_input("close", vec![1.0; 4000 * 5000])
+///         .with_output("alpha001")
+///         .with_output_layout(OutputLayout::TS)
+///         .with_priority(Priority::Background)
+///         .with_tenant("backfill"),
+/// )?;
+/// let query = scheduler.submit(
+///     BatchJob::new(library, "alpha001", BatchParams::full_range(16, 250)?)
+///         .with_input("close", vec![1.0; 16 * 250])
+///         .with_output("alpha001")
+///         .with_priority(Priority::Interactive),
+/// )?;
+///
+/// let alpha = query.wait()?;
+/// println!("backfill at {:.0}%", backfill.progress() * 100.0);
+/// # Ok(())
+/// # }
+/// ```
+pub struct JobScheduler {
+    inner: Arc<Inner>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+/// Configures a [`JobScheduler`], see [`JobScheduler::builder`].
+#[derive(Default)]
+pub struct JobSchedulerBuilder {
+    executors: Vec<Arc<Executor>>,
+    quotas: HashMap<String, usize>,
+    default_quota: Option<usize>,
+    slice_len: Option<usize>,
+}
+
+impl JobSchedulerBuilder {
+    /// Adds an executor; one job runs on it at a time.
+    pub fn with_executor(mut self, executor: Arc<Executor>) -> Self {
+        self.executors.push(executor);
+        self
+    }
+
+    /// Limits how many jobs of `tenant` may run at the same time.
+    pub fn with_tenant_quota<N: AsRef<str>>(mut self, tenant: N, max_running: usize) -> Self {
+        self.quotas
+            .insert(tenant.as_ref().to_string(), max_running.max(1));
+        self
+    }
+
+    /// Limits how many jobs of tenants without their own quota may run at the same
+    /// time. Unlimited by default.
+    pub fn with_default_quota(mut self, max_running: usize) -> Self {
+        self.default_quota = Some(max_running.max(1));
+        self
+    }
+
+    /// Computes jobs in slices of at most `time_steps` and requeues them between
+    /// slices, so higher-priority jobs can overtake long-running ones.
+    pub fn with_slice_len(mut self, time_steps: usize) -> Self {
+        self.slice_len = Some(time_steps.max(1));
+        self
+    }
+
+    /// Starts the dispatcher threads.
+    ///
+    /// Without any executor, the process-wide [`Executor::shared`] one is used.
+    ///
+    /// # Returns
+    ///
+    /// Returns the scheduler, or an error if the shared executor cannot be created
+    /// or a thread cannot be spawned.
+    pub fn build(mut self) -> Result<JobScheduler> {
+        if self.executors.is_empty() {
+            self.executors.push(Executor::shared()?);
+        }
+        let inner = Arc::new(Inner {
+            queue: Mutex::new(Queue {
+                jobs: Vec::new(),
+                running: HashMap::new(),
+                next_id: 0,
+                shutdown: false,
+            }),
+            available: Condvar::new(),
+            quotas: self.quotas,
+            default_quota: self.default_quota.unwrap_or(usize::MAX),
+            slice_len: self.slice_len,
+        });
+
+        let mut scheduler = JobScheduler {
+            inner,
+            workers: Vec::with_capacity(self.executors.len()),
+        };
+        for (index, executor) in self.executors.into_iter().enumerate() {
+            let inner = scheduler.inner.clone();
+            let worker = std::thread::Builder::new()
+                .name(format!("kunquant-job-{}", index))
+                .spawn(move || inner.worker(&executor))?;
+            scheduler.workers.push(worker);
+        }
+        Ok(scheduler)
+    }
+}
+
+impl JobScheduler {
+    /// Starts configuring a scheduler.
+    pub fn builder() -> JobSchedulerBuilder {
+        JobSchedulerBuilder::default()
+    }
+
+    /// Queues a job.
+    ///
+    /// # Returns
+    ///
+    /// Returns the job's handle, or an error if the module is not found in the
+    /// job's library, the time window exceeds `total_time` (`InvalidBatchConfig`),
+    /// or an input doesn't hold `num_stocks * total_time` values.
+    ///
+    /// A job that panics while running fails with a `RuntimeError` returned by
+    /// [`JobHandle::wait`].
+    pub fn submit(&self, job: BatchJob) -> Result<JobHandle> {
+        job.library.get_module(&job.module_name)?;
+        let params = &job.params;
+        if params
+            .cur_time
+            .checked_add(params.length)
+            .is_none_or(|end| end > params.total_time)
+        {
+            return Err(KunQuantError::InvalidBatchConfig {
+                reason: format!(
+                    "time steps {}..{} exceed total_time {}",
+                    params.cur_time,
+                    params.cur_time.saturating_add(params.length),
+                    params.total_time
+                ),
+            });
+        }
+        for (name, data) in &job.inputs {
+            let expected = params.num_stocks * params.total_time;
+            if data.len() != expected {
+                return Err(KunQuantError::BufferSizeMismatch {
+                    name: name.clone(),
+                    expected,
+                    actual: data.len(),
+                    context: None,
+                });
+            }
+        }
+
+        let outputs = job
+            .outputs
+            .iter()
+            .map(|name| {
+                (
+                    name.clone(),
+                    vec![f32::NAN; params.num_stocks * params.length],
+                )
+            })
+            .collect();
+        let shared = Arc::new(JobShared {
+            state: Mutex::new(JobState::Queued),
+            finished: Condvar::new(),
+            completed: Arc::new(AtomicUsize::new(0)),
+            total: params.length,
+            cancellation: CancellationToken::new(),
+        });
+
+        let mut queue = self.inner.queue();
+        let id = queue.next_id;
+        queue.next_id += 1;
+        queue.jobs.push(Job {
+            id,
+            spec: job,
+            outputs,
+            shared: shared.clone(),
+        });
+        drop(queue);
+        self.inner.available.notify_all();
+        Ok(JobHandle {
+            id,
+            shared,
+            inner: self.inner.clone(),
+        })
+    }
+
+    /// Number of jobs waiting to be dispatched.
+    pub fn queued(&self) -> usize {
+        self.inner.queue().jobs.len()
+    }
+
+    /// Number of jobs currently running.
+    pub fn running(&self) -> usize {
+        self.inner.queue().running.values().sum()
+    }
+}
+
+impl Drop for JobScheduler {
+    fn drop(&mut self) {
+        let mut queue = self.inner.queue();
+        queue.shutdown = true;
+        for job in queue.jobs.drain(..) {
+            job.shared.finish(Err(job.shared.cancelled()));
+        }
+        drop(queue);
+        self.inner.available.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,399 @@
+//! Persistence format for streaming state.
+//!
+//! The KunQuant runtime does not expose the internal state of a stream context, so
+//! the state is captured as a journal of the most recent input ticks. Restoring a
+//! context replays the journal, which rebuilds every rolling window that is no
+//! longer than the journal capacity. States are only written while the journal
+//! covers the declared lookback, so a restored context never silently diverges.
+
+use crate::error::{KunQuantError, Result};
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 4] = b"KQSS";
+const FORMAT_VERSION: u32 = 2;
+
+/// Longest module or input name accepted when reading a state.
+const MAX_NAME_LEN: usize = 4096;
+
+/// Ring buffer of the inputs of the most recent ticks of a stream context.
+pub(crate) struct StateJournal {
+    capacity: usize,
+    // Recorded inputs with their buffer handles, fixed by the first recorded tick
+    inputs: Option<Vec<(String, usize)>>,
+    // The tick in progress when the journal was enabled is not recorded
+    skip_tick: bool,
+    // Each tick holds the data of every required input, `[input][stock]`
+    ticks: VecDeque<Vec<f32>>,
+    total_ticks: u64,
+}
+
+impl StateJournal {
+    pub(crate) fn new(capacity: usize) -> Self {
+        StateJournal {
+            capacity,
+            inputs: None,
+            skip_tick: false,
+            // Grown as ticks arrive; the capacity may come from an untrusted state file
+            ticks: VecDeque::new(),
+            total_ticks: 0,
+        }
+    }
+
+    /// Skips recording the next tick, which was already in progress.
+    pub(crate) fn skip_tick(&mut self) {
+        self.skip_tick = true;
+    }
+
+    /// Returns `true` once if the current tick must not be recorded.
+    pub(crate) fn take_skip_tick(&mut self) -> bool {
+        std::mem::take(&mut self.skip_tick)
+    }
+
+    /// Returns the recorded inputs, or `None` before the first recorded tick.
+    pub(crate) fn inputs(&self) -> Option<&[(String, usize)]> {
+        self.inputs.as_deref()
+    }
+
+    /// Returns the recorded inputs, fixing them with `inputs` if no tick has been
+    /// recorded yet.
+    pub(crate) fn inputs_or_insert_with(
+        &mut self,
+        inputs: impl FnOnce() -> Vec<(String, usize)>,
+    ) -> &[(String, usize)] {
+        self.inputs.get_or_insert_with(inputs)
+    }
+
+    /// Records the current tick's data of the journal's inputs from `pending`,
+    /// which is indexed by buffer handle.
+    pub(crate) fn record_inputs(&mut self, num_stocks: usize, pending: &[Vec<f32>]) {
+        let inputs = self.inputs.take().unwrap_or_default();
+        self.record(inputs.len() * num_stocks, |tick| {
+            for (_, handle) in &inputs {
+                tick.extend_from_slice(&pending[*handle]);
+            }
+        });
+        self.inputs = Some(inputs);
+    }
+
+    /// Number of ticks currently held.
+    pub(crate) fn len(&self) -> usize {
+        self.ticks.len()
+    }
+
+    /// Overrides the tick counter, used when continuing a restored journal.
+    pub(crate) fn set_total_ticks(&mut self, total_ticks: u64) {
+        self.total_ticks = total_ticks;
+    }
+
+    /// Records one tick, filling a buffer of `len` values with `fill`.
+    ///
+    /// Once the journal is full the oldest tick's allocation is reused.
+    pub(crate) fn record(&mut self, len: usize, fill: impl FnOnce(&mut Vec<f32>)) {
+        self.total_ticks += 1;
+        if self.capacity == 0 {
+            return;
+        }
+        let mut tick = if self.ticks.len() == self.capacity {
+            self.ticks.pop_front().unwrap_or_default()
+        } else {
+            Vec::with_capacity(len)
+        };
+        tick.clear();
+        fill(&mut tick);
+        self.ticks.push_back(tick);
+    }
+}
+
+/// A decoded stream state.
+pub(crate) struct StreamState {
+    pub(crate) module_name: String,
+    pub(crate) library_fingerprint: u64,
+    pub(crate) num_stocks: usize,
+    pub(crate) capacity: usize,
+    pub(crate) inputs: Vec<String>,
+    // Required inputs of the saved context, `None` if it had none declared yet
+    pub(crate) required: Option<Vec<String>>,
+    pub(crate) lookback: Option<usize>,
+    pub(crate) total_ticks: u64,
+    pub(crate) ticks: Vec<Vec<f32>>,
+}
+
+/// Context settings written ahead of the journaled ticks.
+pub(crate) struct StateHeader<'a> {
+    pub(crate) module_name: &'a str,
+    pub(crate) library_fingerprint: u64,
+    pub(crate) num_stocks: usize,
+    pub(crate) inputs: &'a [&'a str],
+    pub(crate) required: Option<&'a [&'a str]>,
+    pub(crate) lookback: Option<usize>,
+}
+
+/// Writes a stream state in the `KQSS` binary format (little-endian).
+pub(crate) fn write_state<W: Write>(
+    mut writer: W,
+    header: &StateHeader<'_>,
+    journal: &StateJournal,
+) -> Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    write_str(&mut writer, header.module_name)?;
+    writer.write_all(&header.library_fingerprint.to_le_bytes())?;
+    writer.write_all(&(header.num_stocks as u64).to_le_bytes())?;
+    writer.write_all(&(journal.capacity as u64).to_le_bytes())?;
+    write_names(&mut writer, header.inputs)?;
+    match header.required {
+        Some(required) => {
+            writer.write_all(&[1])?;
+            write_names(&mut writer, required)?;
+        }
+        None => writer.write_all(&[0])?,
+    }
+    // u64::MAX stands for no declared lookback
+    let lookback = header.lookback.map_or(u64::MAX, |lookback| lookback as u64);
+    writer.write_all(&lookback.to_le_bytes())?;
+    writer.write_all(&journal.total_ticks.to_le_bytes())?;
+    writer.write_all(&(journal.ticks.len() as u64).to_le_bytes())?;
+    for tick in &journal.ticks {
+        for value in tick {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a stream state written by [`write_state`].
+pub(crate) fn read_state<R: Read>(mut reader: R) -> Result<StreamState> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("not a KunQuant stream state"));
+    }
+    let version = read_u32(&mut reader)?;
+    if version != FORMAT_VERSION {
+        return Err(invalid(format!(
+            "unsupported state format version {} (expected {})",
+            version, FORMAT_VERSION
+        )));
+    }
+
+    let module_name = read_str(&mut reader)?;
+    let library_fingerprint = read_u64(&mut reader)?;
+    let num_stocks = read_u64(&mut reader)? as usize;
+    if num_stocks == 0 {
+        return Err(invalid("state has no stocks"));
+    }
+    let capacity = read_u64(&mut reader)? as usize;
+    let inputs = read_names(&mut reader)?;
+    let num_inputs = inputs.len();
+    let required = match read_bytes(&mut reader, 1)?[0] {
+        0 => None,
+        1 => Some(read_names(&mut reader)?),
+        _ => return Err(invalid("invalid required inputs flag")),
+    };
+    let lookback = match read_u64(&mut reader)? {
+        u64::MAX => None,
+        lookback => Some(lookback as usize),
+    };
+    let total_ticks = read_u64(&mut reader)?;
+    let num_ticks = read_u64(&mut reader)? as usize;
+    if num_ticks > capacity {
+        return Err(invalid("journal holds more ticks than its capacity"));
+    }
+
+    let tick_bytes = num_inputs
+        .checked_mul(num_stocks)
+        .and_then(|len| len.checked_mul(4))
+        .ok_or_else(|| invalid("tick size overflows"))?;
+    let mut ticks = Vec::new();
+    for _ in 0..num_ticks {
+        let bytes = read_bytes(&mut reader, tick_bytes)?;
+        let tick = bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        ticks.push(tick);
+    }
+
+    Ok(StreamState {
+        module_name,
+        library_fingerprint,
+        num_stocks,
+        capacity,
+        inputs,
+        required,
+        lookback,
+        total_ticks,
+        ticks,
+    })
+}
+
+fn invalid<S: Into<String>>(reason: S) -> KunQuantError {
+    KunQuantError::InvalidStreamState {
+        reason: reason.into(),
+    }
+}
+
+fn write_str<W: Write>(writer: &mut W, s: &str) -> Result<()> {
+    writer.write_all(&(s.len() as u32).to_le_bytes())?;
+    writer.write_all(s.as_bytes())?;
+    Ok(())
+}
+
+fn write_names<W: Write>(writer: &mut W, names: &[&str]) -> Result<()> {
+    writer.write_all(&(names.len() as u32).to_le_bytes())?;
+    for name in names {
+        write_str(writer, name)?;
+    }
+    Ok(())
+}
+
+fn read_names<R: Read>(reader: &mut R) -> Result<Vec<String>> {
+    let len = read_u32(reader)?;
+    // Grown as names arrive, the count may be corrupt
+    let mut names = Vec::new();
+    for _ in 0..len {
+        names.push(read_str(reader)?);
+    }
+    Ok(names)
+}
+
+fn read_str<R: Read>(reader: &mut R) -> Result<String> {
+    let len = read_u32(reader)? as usize;
+    if len > MAX_NAME_LEN {
+        return Err(invalid(format!(
+            "name is longer than {} bytes",
+            MAX_NAME_LEN
+        )));
+    }
+    let bytes = read_bytes(reader, len)?;
+    String::from_utf8(bytes).map_err(|_| invalid("name is not valid UTF-8"))
+}
+
+/// Reads exactly `len` bytes, allocating only as much as the reader supplies.
+fn read_bytes<R: Read>(reader: &mut R, len: usize) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(bytes)
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_keeps_most_recent_ticks() {
+        let mut journal = StateJournal::new(2);
+        for t in 0..5 {
+            journal.record(1, |tick| tick.push(t as f32));
+        }
+        assert_eq!(journal.total_ticks, 5);
+        assert_eq!(journal.ticks, VecDeque::from([vec![3.0], vec![4.0]]));
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let mut journal = StateJournal::new(4);
+        journal.record(4, |tick| tick.extend_from_slice(&[1.0, 2.0, 3.0, 4.0]));
+        journal.record(4, |tick| tick.extend_from_slice(&[5.0, f32::NAN, 7.0, 8.0]));
+
+        let mut bytes = Vec::new();
+        let header = StateHeader {
+            module_name: "alpha",
+            library_fingerprint: 42,
+            num_stocks: 2,
+            inputs: &["close", "open"],
+            required: Some(&["close"]),
+            lookback: Some(3),
+        };
+        write_state(&mut bytes, &header, &journal).unwrap();
+        let state = read_state(bytes.as_slice()).unwrap();
+
+        assert_eq!(state.module_name, "alpha");
+        assert_eq!(state.library_fingerprint, 42);
+        assert_eq!(state.num_stocks, 2);
+        assert_eq!(state.capacity, 4);
+        assert_eq!(state.inputs, vec!["close", "open"]);
+        assert_eq!(state.required, Some(vec!["close".to_string()]));
+        assert_eq!(state.lookback, Some(3));
+        assert_eq!(state.total_ticks, 2);
+        assert_eq!(state.ticks[0], vec![1.0, 2.0, 3.0, 4.0]);
+        assert!(state.ticks[1][1].is_nan());
+    }
+
+    #[test]
+    fn test_rejects_foreign_data() {
+        match read_state(&b"NOPE\x01\x00\x00\x00"[..]) {
+            Err(KunQuantError::InvalidStreamState { .. }) => {}
+            other => panic!("Expected InvalidStreamState, got {:?}", other.err()),
+        }
+    }
+
+    /// Encodes a state header followed by `num_ticks`, without any tick data.
+    fn header(num_stocks: u64, capacity: u64, num_inputs: u32, num_ticks: u64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        write_str(&mut bytes, "alpha").unwrap();
+        bytes.extend_from_slice(&42u64.to_le_bytes());
+        bytes.extend_from_slice(&num_stocks.to_le_bytes());
+        bytes.extend_from_slice(&capacity.to_le_bytes());
+        bytes.extend_from_slice(&num_inputs.to_le_bytes());
+        for i in 0..num_inputs {
+            write_str(&mut bytes, &format!("in{}", i)).unwrap();
+        }
+        bytes.push(0);
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        bytes.extend_from_slice(&num_ticks.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_rejects_corrupt_lengths() {
+        // Zero stocks
+        assert!(matches!(
+            read_state(header(0, 4, 1, 0).as_slice()),
+            Err(KunQuantError::InvalidStreamState { .. })
+        ));
+        // Tick size overflows usize
+        assert!(matches!(
+            read_state(header(u64::MAX / 2, u64::MAX, 3, 1).as_slice()),
+            Err(KunQuantError::InvalidStreamState { .. })
+        ));
+        // Huge name length
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            read_state(bytes.as_slice()),
+            Err(KunQuantError::InvalidStreamState { .. })
+        ));
+    }
+
+    #[test]
+    fn test_huge_counts_fail_without_allocating() {
+        // Claims a huge journal but supplies no tick data
+        match read_state(header(1 << 20, u64::MAX, 1 << 10, u64::MAX).as_slice()) {
+            Err(KunQuantError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof),
+            other => panic!("Expected an I/O error, got {:?}", other.err()),
+        }
+    }
+}
@@ -1,9 +1,127 @@
-use crate::error::{KunQuantError, Result};
+use crate::error::{ErrorContext, KunQuantError, Result, ResultExt};
 use crate::executor::Executor;
 use crate::ffi;
+use crate::history::OutputHistory;
+use crate::latency::{StreamLatency, StreamOperation};
 use crate::library::Module;
+use crate::missing::{MissingDataPolicy, MissingInput};
+use crate::snapshot::{self, StateJournal};
+use crate::subscription::{OutputReceiver, Subscriber, SubscriptionId};
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::ffi::CString;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::time::Instant;
+
+// Source of the ids binding `StreamHandle`s to the context that resolved them
+static NEXT_CONTEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A pre-resolved buffer handle of a [`StreamContext`].
+///
+/// `StreamHandle`s are obtained once with [`StreamContext::resolve`] and then used
+/// with [`StreamContext::push`] and [`StreamContext::output`], which skip the
+/// name lookup done by the string-based methods. This keeps per-tick work in hot
+/// loops down to the runtime call itself.
+///
+/// A handle is bound to the context that resolved it; using it with any other
+/// context fails with `KunQuantError::ForeignStreamHandle`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StreamHandle {
+    context_id: u64,
+    index: usize,
+}
+
+impl StreamHandle {
+    /// The runtime's numeric buffer handle, as returned by `get_buffer_handle()`.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+/// Outcome of [`StreamContext::warm_up`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WarmUpReport {
+    /// Number of historical ticks replayed
+    pub steps: usize,
+    /// Ticks still needed before outputs are fully valid, or `None` if no lookback
+    /// was declared with [`StreamContext::set_lookback`]
+    pub remaining: Option<usize>,
+}
+
+/// A complete set of input data for one streaming time step.
+///
+/// `StreamInputs` collects the per-stock data of every input buffer so that it can
+/// be pushed in one call with [`StreamContext::push_all`]. The data is borrowed,
+/// not copied.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use kunquant_rs::{StreamContext, StreamInputs, Result};
+/// # fn example(mut stream: StreamContext, open: &[f32], high: &[f32], low: &[f32], close: &[f32]) -> Result<()> {
+/// let inputs = StreamInputs::new()
+///     .with("open", open)
+///     .with("high", high)
+///     .with("low", low)
+///     .with("close", close);
+///
+/// stream.push_all(&inputs)?;
+/// stream.run()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StreamInputs<'d> {
+    data: Vec<(&'d str, &'d [f32])>,
+}
+
+impl<'d> StreamInputs<'d> {
+    /// Creates an empty set of inputs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the data for input `name`, replacing any previous data for it.
+    pub fn insert(&mut self, name: &'d str, data: &'d [f32]) {
+        match self.data.iter_mut().find(|(n, _)| *n == name) {
+            Some(entry) => entry.1 = data,
+            None => self.data.push((name, data)),
+        }
+    }
+
+    /// Builder-style variant of [`insert`](StreamInputs::insert).
+    pub fn with(mut self, name: &'d str, data: &'d [f32]) -> Self {
+        self.insert(name, data);
+        self
+    }
+
+    /// Iterates over `(name, data)` pairs in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&'d str, &'d [f32])> + '_ {
+        self.data.iter().copied()
+    }
+
+    /// Number of inputs in the set.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns `true` if the set contains no inputs.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+impl<'d> FromIterator<(&'d str, &'d [f32])> for StreamInputs<'d> {
+    fn from_iter<I: IntoIterator<Item = (&'d str, &'d [f32])>>(iter: I) -> Self {
+        let mut inputs = StreamInputs::new();
+        for (name, data) in iter {
+            inputs.insert(name, data);
+        }
+        inputs
+    }
+}
 
 /// A streaming computation context for real-time factor calculation.
 ///
@@ -17,19 +135,65 @@ use std::ffi::CString;
 ///
 /// # Thread Safety
 ///
-/// This struct is not thread-safe. Each thread should create its own `StreamContext` instance.
+/// This struct is not thread-safe. Each thread should create its own `StreamContext` instance,
+/// or use [`OwnedStreamContext`](crate::OwnedStreamContext) to move a stream between threads.
+/// It is neither `Send` nor `Sync`: the runtime's stream state must not be used from two
+/// threads at once, and latency tracking updates its histograms from `&self` methods.
 ///
 /// # Memory Management
 ///
 /// The streaming context automatically manages its resources using RAII. The underlying
 /// C handle is properly cleaned up when the context is dropped.
+///
+/// # Tick Completeness
+///
+/// The context tracks which inputs have been pushed since the last `run()`. The KunQuant
+/// runtime does not expose the inputs a module declares, so the required set is taken
+/// from [`set_required_inputs`](StreamContext::set_required_inputs) or the first
+/// [`push_all`](StreamContext::push_all), and otherwise from the inputs pushed before
+/// the first `run()`. From then on `run()` fails with `KunQuantError::MissingStreamInputs`
+/// if any required input has not been pushed since the last `run()`, instead of
+/// silently reusing the previous tick's data. Declaring an empty set with
+/// `set_required_inputs` turns the check off.
 pub struct StreamContext<'a> {
     handle: ffi::KunStreamContextHandle,
     num_stocks: usize,
     _executor: &'a Executor,
-    _module: &'a Module<'a>,
+    module: &'a Module<'a>,
+    // Unique id used to validate `StreamHandle`s
+    id: u64,
     // Cache buffer handles to avoid repeated lookups
     buffer_handles: HashMap<String, usize>,
+    // Inputs that must be pushed before every run, if declared
+    required_inputs: Option<Vec<(String, usize)>>,
+    // Indexed by buffer handle: pushed since the last run
+    pushed: Vec<bool>,
+    // Number of successful runs
+    ticks_run: u64,
+    // Ticks needed before outputs are fully valid, if declared
+    lookback: Option<usize>,
+    // Recent input ticks for `save_state`, if enabled
+    journal: Option<StateJournal>,
+    // Indexed by buffer handle: data of the current tick, kept while journaling
+    pending: Vec<Vec<f32>>,
+    // Output ring buffers updated after every run, keyed by output name
+    histories: Vec<(String, usize, OutputHistory)>,
+    // Output callbacks notified after every run
+    subscribers: Vec<Subscriber<'a>>,
+    next_subscription: u64,
+    // Missing-data handling of masked pushes, keyed by buffer handle
+    missing: HashMap<usize, MissingInput>,
+    // Scratch buffer for masked data after filling
+    filled: Vec<f32>,
+    // Stocks suspended by masked pushes of the current tick, and of the last run
+    suspending: Vec<bool>,
+    suspended: Vec<bool>,
+    // Published output values keyed by buffer handle, kept while an input has the
+    // `Suspend` policy; suspended stocks keep their previous values
+    held: HashMap<usize, Vec<f32>>,
+    // Latency histograms, if enabled; a `RefCell` so that `&self` reads are timed too.
+    // This doesn't cost `Sync`, which the raw runtime handle already rules out
+    latency: Option<RefCell<StreamLatency>>,
 }
 
 impl<'a> StreamContext<'a> {
@@ -67,8 +231,12 @@ impl<'a> StreamContext<'a> {
     /// # }
     /// ```
     pub fn new(executor: &'a Executor, module: &'a Module<'a>, num_stocks: usize) -> Result<Self> {
-        let handle =
-            unsafe { ffi::kunCreateStream(executor.handle(), module.handle(), num_stocks) };
+        let mut handle = std::ptr::null_mut();
+        let status = unsafe {
+            ffi::kunShimCreateStream(executor.handle(), module.handle(), num_stocks, &mut handle)
+        };
+        ffi::check(status, "create_stream")
+            .context(|| ErrorContext::new("create_stream").with_module(module))?;
 
         if handle.is_null() {
             return Err(KunQuantError::StreamCreationFailed);
@@ -78,8 +246,24 @@ impl<'a> StreamContext<'a> {
             handle,
             num_stocks,
             _executor: executor,
-            _module: module,
+            module,
+            id: NEXT_CONTEXT_ID.fetch_add(1, Ordering::Relaxed),
             buffer_handles: HashMap::new(),
+            required_inputs: None,
+            pushed: Vec::new(),
+            ticks_run: 0,
+            lookback: None,
+            journal: None,
+            pending: Vec::new(),
+            histories: Vec::new(),
+            subscribers: Vec::new(),
+            next_subscription: 0,
+            missing: HashMap::new(),
+            filled: Vec::new(),
+            suspending: vec![false; num_stocks],
+            suspended: vec![false; num_stocks],
+            held: HashMap::new(),
+            latency: None,
         })
     }
 
@@ -92,7 +276,7 @@ impl<'a> StreamContext<'a> {
     /// # Arguments
     ///
     /// * `name` - The name of the buffer as defined in the factor module. Can be any type
-    ///           that implements `AsRef<str>` (e.g., `&str`, `String`, etc.)
+    ///   that implements `AsRef<str>` (e.g., `&str`, `String`, etc.)
     ///
     /// # Returns
     ///
@@ -133,20 +317,87 @@ impl<'a> StreamContext<'a> {
             return Ok(handle);
         }
 
-        let c_name = CString::new(name_str)?;
-        let handle = unsafe { ffi::kunQueryBufferHandle(self.handle, c_name.as_ptr()) };
+        let handle = self
+            .query_buffer_handle(name_str)
+            .context(|| self.error_context("query_buffer_handle"))?;
+        self.buffer_handles.insert(name_str.to_string(), handle);
+        Ok(handle)
+    }
+
+    /// Resolves a named buffer into a typed [`StreamHandle`] bound to this context.
+    ///
+    /// Resolve the handles of every input and output once during setup, then use
+    /// [`push`](StreamContext::push) and [`output`](StreamContext::output) in the
+    /// per-tick loop.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the buffer as defined in the factor module
+    ///
+    /// # Returns
+    ///
+    /// Returns the handle, or `BufferHandleNotFound` if the name is not found.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kunquant_rs::{StreamContext, Result};
+    /// # fn example(mut stream: StreamContext, ticks: &[Vec<f32>]) -> Result<()> {
+    /// let close = stream.resolve("close")?;
+    /// let factor = stream.resolve("my_factor")?;
+    ///
+    /// for tick in ticks {
+    ///     stream.push(close, tick)?;
+    ///     stream.run()?;
+    ///     let values = stream.output(factor)?;
+    ///     println!("{:?}", values);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resolve<N: AsRef<str>>(&mut self, name: N) -> Result<StreamHandle> {
+        let index = self.get_buffer_handle(name)?;
+        Ok(StreamHandle {
+            context_id: self.id,
+            index,
+        })
+    }
+
+    fn query_buffer_handle(&self, name: &str) -> Result<usize> {
+        let c_name = CString::new(name)?;
+        let mut handle = usize::MAX;
+        let status =
+            unsafe { ffi::kunShimQueryBufferHandle(self.handle, c_name.as_ptr(), &mut handle) };
+        ffi::check(status, "query_buffer_handle")?;
 
         // Note: KunQuant returns SIZE_MAX for invalid buffer names
         if handle == usize::MAX {
             return Err(KunQuantError::BufferHandleNotFound {
-                name: name_str.to_string(),
+                name: name.to_string(),
+                context: None,
             });
         }
-
-        self.buffer_handles.insert(name_str.to_string(), handle);
         Ok(handle)
     }
 
+    /// Name of a cached buffer handle, for error messages.
+    fn buffer_name(&self, handle: usize) -> String {
+        self.buffer_handles
+            .iter()
+            .find(|(_, h)| **h == handle)
+            .map_or_else(|| format!("#{}", handle), |(name, _)| name.clone())
+    }
+
+    fn check_handle(&self, handle: StreamHandle) -> Result<usize> {
+        if handle.context_id != self.id {
+            return Err(KunQuantError::ForeignStreamHandle {
+                index: handle.index,
+                context: None,
+            });
+        }
+        Ok(handle.index)
+    }
+
     /// Retrieves the current computed data from a named output buffer.
     ///
     /// After calling `run()`, this method provides access to the computed factor values
@@ -160,6 +411,8 @@ impl<'a> StreamContext<'a> {
     ///
     /// Returns `Ok(&[f32])` containing the computed values for all stocks, or an error if:
     /// - The buffer name is not found
+    /// - An input has the [`MissingDataPolicy::Suspend`] policy and the output was not
+    ///   resolved before (`UnresolvedStreamOutput`)
     /// - The computation hasn't been run yet (call `run()` first)
     /// - The streaming context handle is invalid
     /// - The C library returns a null pointer
@@ -194,8 +447,64 @@ impl<'a> StreamContext<'a> {
     /// - The returned slice borrows from internal C buffers
     /// - The lifetime is tied to the `StreamContext` instance
     /// - Do not store references beyond the next streaming operation
-    pub fn get_current_buffer<N: AsRef<str>>(&mut self, name: N) -> Result<&[f32]> {
-        let handle = self.get_buffer_handle(name)?;
+    ///
+    /// # Performance Notes
+    ///
+    /// - Takes `&self`, so several outputs can be borrowed at the same time
+    /// - Names not yet cached by `get_buffer_handle()` are looked up on every call;
+    ///   use [`resolve`](StreamContext::resolve) and [`output`](StreamContext::output)
+    ///   in hot loops
+    pub fn get_current_buffer<N: AsRef<str>>(&self, name: N) -> Result<&[f32]> {
+        let start = self.start_timer();
+        let result = self
+            .lookup_output_handle(name.as_ref())
+            .and_then(|handle| self.published_buffer(handle));
+        self.stop_timer(StreamOperation::GetCurrentBuffer, start);
+        result.context(|| self.error_context("get_current_buffer"))
+    }
+
+    /// Retrieves the current computed data of an output through a resolved handle.
+    ///
+    /// Equivalent to [`get_current_buffer`](StreamContext::get_current_buffer)
+    /// without the name lookup.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(&[f32])` with one value per stock, or an error if:
+    /// - The handle was resolved by another context (`ForeignStreamHandle`)
+    /// - The C library returns a null pointer
+    pub fn output(&self, handle: StreamHandle) -> Result<&[f32]> {
+        let start = self.start_timer();
+        let result = self
+            .check_handle(handle)
+            .and_then(|index| self.published_buffer(index));
+        self.stop_timer(StreamOperation::GetCurrentBuffer, start);
+        result.context(|| self.error_context("get_current_buffer"))
+    }
+
+    /// Looks up an output in the cache, or asks the runtime without caching it.
+    /// With a `Suspend` input only resolved outputs have held values, so uncached
+    /// names are rejected.
+    fn lookup_output_handle(&self, name: &str) -> Result<usize> {
+        match self.buffer_handles.get(name) {
+            Some(&handle) => Ok(handle),
+            None if self.suspends() => Err(KunQuantError::UnresolvedStreamOutput {
+                name: name.to_string(),
+            }),
+            None => self.query_buffer_handle(name),
+        }
+    }
+
+    /// The values of an output as published by the context: held for suspended
+    /// stocks, otherwise the runtime's.
+    fn published_buffer(&self, handle: usize) -> Result<&[f32]> {
+        match self.held.get(&handle) {
+            Some(values) => Ok(values),
+            None => self.current_buffer(handle),
+        }
+    }
+
+    fn current_buffer(&self, handle: usize) -> Result<&[f32]> {
         let ptr = unsafe { ffi::kunStreamGetCurrentBuffer(self.handle, handle) };
 
         if ptr.is_null() {
@@ -253,18 +562,318 @@ impl<'a> StreamContext<'a> {
     /// - Buffer handles are cached for optimal performance
     /// - This method is designed for high-frequency updates
     pub fn push_data<N: AsRef<str>>(&mut self, name: N, data: &[f32]) -> Result<()> {
+        let start = self.start_timer();
+        let result = self.push_named(name.as_ref(), data);
+        self.stop_timer(StreamOperation::PushData, start);
+        result.context(|| self.error_context("push_data"))
+    }
+
+    fn push_named(&mut self, name: &str, data: &[f32]) -> Result<()> {
         if data.len() != self.num_stocks {
             return Err(KunQuantError::BufferSizeMismatch {
-                name: name.as_ref().to_string(),
+                name: name.to_string(),
                 expected: self.num_stocks,
                 actual: data.len(),
+                context: None,
             });
         }
 
         let handle = self.get_buffer_handle(name)?;
-        unsafe {
-            ffi::kunStreamPushData(self.handle, handle, data.as_ptr());
+        self.push_unchecked(handle, data)
+    }
+
+    /// Pushes data for the current time step through a resolved handle.
+    ///
+    /// Equivalent to [`push_data`](StreamContext::push_data) without the name lookup.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if:
+    /// - The handle was resolved by another context (`ForeignStreamHandle`)
+    /// - The data length doesn't match the number of stocks
+    pub fn push(&mut self, handle: StreamHandle, data: &[f32]) -> Result<()> {
+        let start = self.start_timer();
+        let result = self.push_handle(handle, data);
+        self.stop_timer(StreamOperation::PushData, start);
+        result.context(|| self.error_context("push_data"))
+    }
+
+    fn push_handle(&mut self, handle: StreamHandle, data: &[f32]) -> Result<()> {
+        let index = self.check_handle(handle)?;
+        if data.len() != self.num_stocks {
+            return Err(KunQuantError::BufferSizeMismatch {
+                name: self.buffer_name(index),
+                expected: self.num_stocks,
+                actual: data.len(),
+                context: None,
+            });
+        }
+
+        self.push_unchecked(index, data)
+    }
+
+    /// Pushes the data of every input for the current time step in one call.
+    ///
+    /// All data lengths and buffer names are validated before anything is pushed, so
+    /// on error no input of this tick has been modified. If no required inputs have
+    /// been declared yet, the inputs of this call become the required set, see
+    /// [Tick Completeness](StreamContext#tick-completeness).
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - Data for every input of the module, each `num_stocks` long
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if:
+    /// - Any data length doesn't match the number of stocks
+    /// - Any buffer name is not found
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kunquant_rs::{StreamContext, StreamInputs, Result};
+    /// # fn example(mut stream: StreamContext) -> Result<()> {
+    /// let close = vec![100.0; 8];
+    /// let open = vec![99.0; 8];
+    ///
+    /// stream.push_all(&StreamInputs::new().with("close", &close).with("open", &open))?;
+    /// stream.run()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn push_all(&mut self, inputs: &StreamInputs) -> Result<()> {
+        let start = self.start_timer();
+        let result = self.push_inputs(inputs);
+        self.stop_timer(StreamOperation::PushData, start);
+        result.context(|| self.error_context("push_data"))
+    }
+
+    fn push_inputs(&mut self, inputs: &StreamInputs) -> Result<()> {
+        let mut handles = Vec::with_capacity(inputs.len());
+        for (name, data) in inputs.iter() {
+            if data.len() != self.num_stocks {
+                return Err(KunQuantError::BufferSizeMismatch {
+                    name: name.to_string(),
+                    expected: self.num_stocks,
+                    actual: data.len(),
+                    context: None,
+                });
+            }
+            handles.push(self.get_buffer_handle(name)?);
+        }
+
+        if self.required_inputs.is_none() {
+            self.required_inputs = Some(
+                inputs
+                    .iter()
+                    .zip(&handles)
+                    .map(|((name, _), &handle)| (name.to_string(), handle))
+                    .collect(),
+            );
+        }
+        for ((_, data), handle) in inputs.iter().zip(handles) {
+            self.push_unchecked(handle, data)?;
+        }
+        Ok(())
+    }
+
+    /// Sets how stocks marked invalid are filled when pushing `name` with
+    /// [`push_masked`](StreamContext::push_masked).
+    ///
+    /// Forward-filling repeats the values pushed to this input since the first call
+    /// to this method or to `push_masked` for it, so set the policy before the first
+    /// tick. Inputs without a policy use [`MissingDataPolicy::ForwardFill`].
+    ///
+    /// While any input has the [`MissingDataPolicy::Suspend`] policy, outputs read
+    /// from the context hold the previous values of suspended stocks. Resolve them
+    /// with [`resolve`](StreamContext::resolve) before the first `run()`: outputs
+    /// read by name without being resolved fail with `UnresolvedStreamOutput`.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if the buffer name is not found.
+    pub fn set_missing_policy<N: AsRef<str>>(
+        &mut self,
+        name: N,
+        policy: MissingDataPolicy,
+    ) -> Result<()> {
+        let handle = self.get_buffer_handle(name)?;
+        let num_stocks = self.num_stocks;
+        self.missing
+            .entry(handle)
+            .or_insert_with(|| MissingInput::new(policy, num_stocks))
+            .policy = policy;
+        Ok(())
+    }
+
+    /// Pushes data for the current time step where only some stocks have new values.
+    ///
+    /// Stocks whose `valid` entry is `false` get a value chosen by the input's
+    /// [`MissingDataPolicy`] instead of their entry in `data`, which can hold
+    /// anything.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the input buffer
+    /// * `data` - Values for all stocks, `num_stocks` long
+    /// * `valid` - Validity mask, `num_stocks` long
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if:
+    /// - The length of `data` or `valid` doesn't match the number of stocks
+    /// - The buffer name is not found
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kunquant_rs::{MissingDataPolicy, StreamContext, Result};
+    /// # fn example(mut stream: StreamContext) -> Result<()> {
+    /// stream.set_missing_policy("close", MissingDataPolicy::ForwardFill)?;
+    /// stream.set_missing_policy("volume", MissingDataPolicy::Nan)?;
+    ///
+    /// // Stock 2 has no new bar in this tick
+    /// let valid = [true, true, false, true];
+    /// stream.push_masked("close", &[10.1, 20.3, 0.0, 15.2], &valid)?;
+    /// stream.push_masked("volume", &[500.0, 800.0, 0.0, 120.0], &valid)?;
+    /// stream.run()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn push_masked<N: AsRef<str>>(
+        &mut self,
+        name: N,
+        data: &[f32],
+        valid: &[bool],
+    ) -> Result<()> {
+        let start = self.start_timer();
+        let result = self.push_masked_named(name.as_ref(), data, valid);
+        self.stop_timer(StreamOperation::PushData, start);
+        result.context(|| self.error_context("push_data"))
+    }
+
+    fn push_masked_named(&mut self, name: &str, data: &[f32], valid: &[bool]) -> Result<()> {
+        for len in [data.len(), valid.len()] {
+            if len != self.num_stocks {
+                return Err(KunQuantError::BufferSizeMismatch {
+                    name: name.to_string(),
+                    expected: self.num_stocks,
+                    actual: len,
+                    context: None,
+                });
+            }
+        }
+
+        let handle = self.get_buffer_handle(name)?;
+        let num_stocks = self.num_stocks;
+        let input = self
+            .missing
+            .entry(handle)
+            .or_insert_with(|| MissingInput::new(MissingDataPolicy::default(), num_stocks));
+        let mut filled = std::mem::take(&mut self.filled);
+        input.fill(data, valid, &mut filled, &mut self.suspending);
+        let result = self.push_unchecked(handle, &filled);
+        self.filled = filled;
+        result
+    }
+
+    /// Declares the inputs that must be pushed before every `run()`.
+    ///
+    /// Replaces any earlier declaration, including one made by
+    /// [`push_all`](StreamContext::push_all) or inferred by the first `run()`. An
+    /// empty list turns the completeness check off. See
+    /// [Tick Completeness](StreamContext#tick-completeness).
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - Names of all input buffers of the module
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if any buffer name is not found.
+    pub fn set_required_inputs<N: AsRef<str>>(&mut self, names: &[N]) -> Result<()> {
+        let mut required = Vec::with_capacity(names.len());
+        for name in names {
+            let handle = self.get_buffer_handle(name)?;
+            required.push((name.as_ref().to_string(), handle));
         }
+        self.required_inputs = Some(required);
+        Ok(())
+    }
+
+    /// Returns the names of required inputs not pushed since the last `run()`.
+    ///
+    /// Before the required inputs are declared or inferred by the first `run()` this
+    /// is always empty.
+    pub fn missing_inputs(&self) -> Vec<&str> {
+        match &self.required_inputs {
+            Some(required) => required
+                .iter()
+                .filter(|(_, handle)| !self.is_pushed(*handle))
+                .map(|(name, _)| name.as_str())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Forgets the pushes of the current tick, e.g. after one of several pushes
+    /// failed.
+    ///
+    /// Every [required input](StreamContext#tick-completeness) must then be pushed
+    /// again before the next `run()`. The data already handed to the runtime is not
+    /// erased; it is overwritten by the next pushes.
+    pub fn discard_pushes(&mut self) {
+        self.pushed.fill(false);
+        self.suspending.fill(false);
+    }
+
+    /// Pushes data whose handle and length have already been validated.
+    fn push_unchecked(&mut self, handle: usize, data: &[f32]) -> Result<()> {
+        let status = unsafe { ffi::kunShimStreamPushData(self.handle, handle, data.as_ptr()) };
+        ffi::check(status, "push_data")?;
+        if handle >= self.pushed.len() {
+            self.pushed.resize(handle + 1, false);
+        }
+        self.pushed[handle] = true;
+
+        if let Some(input) = self.missing.get_mut(&handle) {
+            input.record(data);
+        }
+        if self.journal.is_some() {
+            if handle >= self.pending.len() {
+                self.pending.resize_with(handle + 1, Vec::new);
+            }
+            self.pending[handle].clear();
+            self.pending[handle].extend_from_slice(data);
+        }
+        Ok(())
+    }
+
+    fn is_pushed(&self, handle: usize) -> bool {
+        self.pushed.get(handle).copied().unwrap_or(false)
+    }
+
+    /// Checks that the current tick is complete and starts the next one.
+    fn finish_tick(&mut self) -> Result<()> {
+        if self.required_inputs.is_none() {
+            // Undeclared: the inputs of the first tick are required from now on
+            let mut pushed: Vec<(String, usize)> = self
+                .buffer_handles
+                .iter()
+                .filter(|(_, handle)| self.is_pushed(**handle))
+                .map(|(name, &handle)| (name.clone(), handle))
+                .collect();
+            pushed.sort_by_key(|(_, handle)| *handle);
+            self.required_inputs = Some(pushed);
+        }
+        let missing = self.missing_inputs();
+        if !missing.is_empty() {
+            return Err(KunQuantError::MissingStreamInputs {
+                missing: missing.into_iter().map(str::to_string).collect(),
+            });
+        }
+        self.pushed.iter_mut().for_each(|p| *p = false);
         Ok(())
     }
 
@@ -278,9 +887,13 @@ impl<'a> StreamContext<'a> {
     ///
     /// Returns `Ok(())` on successful computation, or an error if:
     /// - The streaming context handle is invalid
-    /// - Required input data hasn't been pushed
-    /// - The computation encounters runtime errors
-    /// - The C library execution fails
+    /// - Required input data hasn't been pushed since the last `run()`
+    ///   (`MissingStreamInputs`, nothing is computed in that case)
+    /// - The runtime raises an error during the computation (`RuntimeError`, carrying
+    ///   the runtime's message); the tick is not counted as run in that case
+    /// - Reading an output for an output history or subscriber fails; the tick has
+    ///   run and is journaled, and the remaining histories and subscribers are still
+    ///   updated
     ///
     /// # Examples
     ///
@@ -306,7 +919,8 @@ impl<'a> StreamContext<'a> {
     /// # Execution Model
     ///
     /// - Computation is performed synchronously
-    /// - All required inputs must be pushed before calling `run()`
+    /// - All required inputs must be pushed before calling `run()`, see
+    ///   [Tick Completeness](StreamContext#tick-completeness)
     /// - Results are immediately available after successful execution
     /// - The method can be called repeatedly for streaming scenarios
     ///
@@ -316,17 +930,621 @@ impl<'a> StreamContext<'a> {
     /// - Uses SIMD instructions when possible
     /// - Memory buffers are reused between calls
     /// - Execution time depends on factor complexity and number of stocks
-    pub fn run(&self) -> Result<()> {
+    pub fn run(&mut self) -> Result<()> {
+        self.run_tick().context(|| self.error_context("run"))
+    }
+
+    fn run_tick(&mut self) -> Result<()> {
         if self.handle.is_null() {
             return Err(KunQuantError::NullPointer);
         }
+        let start = self.start_timer();
+        let outputs = self.held_outputs();
+        self.finish_tick()?;
+        std::mem::swap(&mut self.suspended, &mut self.suspending);
+        self.suspending.fill(false);
+
+        let status = unsafe { ffi::kunShimStreamRun(self.handle) };
+        self.stop_timer(StreamOperation::Run, start);
+        ffi::check(status, "run")?;
+        self.ticks_run += 1;
+
+        // Record the tick before the fallible output consumers, so that a failing
+        // callback doesn't leave the journal behind the runtime's state
+        if let Some(journal) = &mut self.journal
+            && !journal.take_skip_tick()
+        {
+            // The inputs pushed since the journal was enabled, which covers the
+            // required ones and whatever an opted-out context feeds
+            let pending = &self.pending;
+            journal.inputs_or_insert_with(|| {
+                let mut pushed: Vec<(String, usize)> = self
+                    .buffer_handles
+                    .iter()
+                    .filter(|(_, handle)| pending.get(**handle).is_some_and(|d| !d.is_empty()))
+                    .map(|(name, &handle)| (name.clone(), handle))
+                    .collect();
+                pushed.sort_by_key(|(_, handle)| *handle);
+                pushed
+            });
+            journal.record_inputs(self.num_stocks, pending);
+        }
+
+        // Every output consumer is updated even if an earlier one fails; the first
+        // error is returned
+        let mut result = self.hold_outputs(&outputs);
+        if !self.histories.is_empty() {
+            let mut histories = std::mem::take(&mut self.histories);
+            let recorded = histories.iter_mut().try_for_each(|(_, handle, history)| {
+                history.record(self.published_buffer(*handle)?);
+                Ok(())
+            });
+            self.histories = histories;
+            result = result.and(recorded);
+        }
+
+        if !self.subscribers.is_empty() {
+            let tick = self.ticks_run - 1;
+            let mut subscribers = std::mem::take(&mut self.subscribers);
+            let notified: Result<()> = subscribers.iter_mut().try_for_each(|subscriber| {
+                subscriber.notify(tick, self.published_buffer(subscriber.handle)?);
+                Ok(())
+            });
+            self.subscribers = subscribers;
+            result = result.and(notified);
+        }
+        result
+    }
+
+    /// Whether any input has the `Suspend` missing-data policy.
+    fn suspends(&self) -> bool {
+        self.missing
+            .values()
+            .any(|input| input.policy == MissingDataPolicy::Suspend)
+    }
+
+    /// Handles whose values are held for suspended stocks: every resolved buffer
+    /// that is not an input of the current tick. Called before `finish_tick()`
+    /// forgets the pushes.
+    fn held_outputs(&mut self) -> Vec<usize> {
+        if !self.suspends() {
+            self.held.clear();
+            return Vec::new();
+        }
+        let is_input = |handle: usize| {
+            self.is_pushed(handle)
+                || self.missing.contains_key(&handle)
+                || self
+                    .required_inputs
+                    .iter()
+                    .flatten()
+                    .any(|(_, h)| *h == handle)
+        };
+        self.buffer_handles
+            .values()
+            .copied()
+            .filter(|&handle| !is_input(handle))
+            .collect()
+    }
+
+    /// Updates the held values of `outputs` from the runtime, except for the stocks
+    /// suspended in this run. An output held for the first time takes the runtime's
+    /// values of every stock.
+    fn hold_outputs(&mut self, outputs: &[usize]) -> Result<()> {
+        let mut held = std::mem::take(&mut self.held);
+        let result = outputs.iter().try_for_each(|&handle| {
+            let current = self.current_buffer(handle)?;
+            match held.get_mut(&handle) {
+                Some(values) => {
+                    for ((value, &new), &suspended) in
+                        values.iter_mut().zip(current).zip(&self.suspended)
+                    {
+                        if !suspended {
+                            *value = new;
+                        }
+                    }
+                }
+                None => {
+                    held.insert(handle, current.to_vec());
+                }
+            }
+            Ok(())
+        });
+        self.held = held;
+        result
+    }
+
+    /// Starts recording the inputs of the last `capacity` ticks for `save_state()`.
+    ///
+    /// The KunQuant runtime does not expose the internal state of a stream, so the
+    /// state is captured as a journal of recent inputs and rebuilt on
+    /// [`restore`](StreamContext::restore) by replaying them. The replay is exact as
+    /// long as the journal holds every tick the context has run; after that,
+    /// `save_state()` needs a [lookback](StreamContext::set_lookback) of at most
+    /// `capacity` ticks. Factors with unbounded memory (e.g. recursive averages)
+    /// should declare the number of ticks after which older inputs no longer matter
+    /// at the precision they need.
+    ///
+    /// A tick already in progress is not recorded. The journaled inputs are the
+    /// inputs pushed for the first recorded tick. While enabled, every push copies
+    /// its data into the journal.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Number of most recent ticks to keep
+    pub fn enable_state_journal(&mut self, capacity: usize) {
+        self.pending.clear();
+        let mut journal = StateJournal::new(capacity);
+        if self.pushed.contains(&true) {
+            journal.skip_tick();
+        }
+        self.journal = Some(journal);
+    }
 
-        unsafe {
-            ffi::kunStreamRun(self.handle);
+    /// Returns `true` if the state journal is enabled, i.e. `save_state()` can succeed.
+    pub fn has_state_journal(&self) -> bool {
+        self.journal.is_some()
+    }
+
+    /// Keeps the values of `name` from the last `capacity` ticks.
+    ///
+    /// After every `run()` the output is copied into a ring buffer that can be read
+    /// as a `[N][stock]` matrix through [`output_history`](StreamContext::output_history).
+    /// Enabling an output again resets its history with the new capacity.
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if the output name is not found.
+    pub fn enable_output_history<N: AsRef<str>>(&mut self, name: N, capacity: usize) -> Result<()> {
+        let name = name.as_ref();
+        let handle = self.get_buffer_handle(name)?;
+        let history = OutputHistory::new(self.num_stocks, capacity);
+        match self.histories.iter_mut().find(|(n, _, _)| n == name) {
+            Some(entry) => entry.2 = history,
+            None => self.histories.push((name.to_string(), handle, history)),
         }
         Ok(())
     }
 
+    /// Stops keeping the history of `name`, returning `true` if it was kept.
+    pub fn disable_output_history<N: AsRef<str>>(&mut self, name: N) -> bool {
+        let len = self.histories.len();
+        self.histories.retain(|(n, _, _)| n != name.as_ref());
+        self.histories.len() != len
+    }
+
+    /// Returns the recent history of `name`, if enabled.
+    pub fn output_history<N: AsRef<str>>(&self, name: N) -> Option<&OutputHistory> {
+        self.histories
+            .iter()
+            .find(|(n, _, _)| n == name.as_ref())
+            .map(|(_, _, history)| history)
+    }
+
+    /// Registers a callback called with `(tick_index, values)` of `name` after every `run()`.
+    ///
+    /// `tick_index` is the 0-based index of the tick that was just run (see
+    /// [`ticks_run`](StreamContext::ticks_run)). Callbacks run on the thread calling
+    /// `run()`, in registration order, and should return quickly.
+    ///
+    /// # Returns
+    ///
+    /// Returns the id of the subscription, or an error if the output name is not found.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kunquant_rs::{StreamContext, Result};
+    /// # fn example(mut stream: StreamContext) -> Result<()> {
+    /// let id = stream.subscribe("alpha", |tick, values| {
+    ///     println!("tick {}: {:?}", tick, values);
+    /// })?;
+    /// stream.subscribe_changes("alpha", |tick, values, changed| {
+    ///     for &stock in changed {
+    ///         println!("tick {}: stock {} -> {}", tick, stock, values[stock]);
+    ///     }
+    /// })?;
+    /// // ... push and run ticks ...
+    /// stream.unsubscribe(id);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn subscribe<N, F>(&mut self, name: N, callback: F) -> Result<SubscriptionId>
+    where
+        N: AsRef<str>,
+        F: FnMut(u64, &[f32]) + Send + 'a,
+    {
+        let handle = self.get_buffer_handle(name)?;
+        let id = self.next_subscription_id();
+        self.subscribers
+            .push(Subscriber::every(id, handle, callback));
+        Ok(id)
+    }
+
+    /// Registers a callback called only when values of `name` change.
+    ///
+    /// The callback receives `(tick_index, values, changed)`, where `changed` lists
+    /// the stocks whose value differs bitwise from the previous tick (a NaN that
+    /// stays NaN is unchanged). All stocks count as changed on the first tick after
+    /// subscribing; ticks without changes are not delivered.
+    pub fn subscribe_changes<N, F>(&mut self, name: N, callback: F) -> Result<SubscriptionId>
+    where
+        N: AsRef<str>,
+        F: FnMut(u64, &[f32], &[usize]) + Send + 'a,
+    {
+        let handle = self.get_buffer_handle(name)?;
+        let id = self.next_subscription_id();
+        self.subscribers
+            .push(Subscriber::changes(id, handle, callback));
+        Ok(id)
+    }
+
+    /// Subscribes to `name` through a channel receiving `(tick_index, values)`.
+    ///
+    /// Values are copied for every tick. Sending stops silently once the receiver
+    /// is dropped; call [`unsubscribe`](StreamContext::unsubscribe) to remove the
+    /// subscription as well.
+    pub fn subscribe_channel<N: AsRef<str>>(
+        &mut self,
+        name: N,
+    ) -> Result<(SubscriptionId, OutputReceiver)> {
+        let (sender, receiver) = mpsc::channel();
+        let id = self.subscribe(name, move |tick, values| {
+            let _ = sender.send((tick, values.to_vec()));
+        })?;
+        Ok((id, receiver))
+    }
+
+    /// Removes a subscription, returning `true` if it existed.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let len = self.subscribers.len();
+        self.subscribers.retain(|subscriber| subscriber.id != id);
+        self.subscribers.len() != len
+    }
+
+    fn next_subscription_id(&mut self) -> SubscriptionId {
+        self.next_subscription += 1;
+        SubscriptionId(self.next_subscription)
+    }
+
+    /// Writes a checkpoint of the stream state to `writer`.
+    ///
+    /// The checkpoint contains the module name, a fingerprint of the library file,
+    /// the number of stocks, the input names, the required inputs and the journaled
+    /// ticks. It can be
+    /// loaded with [`restore`](StreamContext::restore).
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if:
+    /// - The state journal is not enabled (`InvalidStreamState`)
+    /// - The journal no longer holds every tick run and no lookback was declared,
+    ///   or the lookback exceeds the journal capacity, so a restored context would
+    ///   diverge (`InvalidStreamState`)
+    /// - The library file cannot be read for its fingerprint, or writing fails (`Io`)
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kunquant_rs::{StreamContext, Result};
+    /// # fn example(mut stream: StreamContext) -> Result<()> {
+    /// stream.enable_state_journal(240);
+    /// // ... push and run ticks ...
+    /// let file = std::fs::File::create("alpha.state")?;
+    /// stream.save_state(std::io::BufWriter::new(file))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn save_state<W: Write>(&self, writer: W) -> Result<()> {
+        let journal = self
+            .journal
+            .as_ref()
+            .ok_or_else(|| KunQuantError::InvalidStreamState {
+                reason: "state journal is not enabled".to_string(),
+            })?;
+        let journaled = journal.len();
+        if self.ticks_run > journaled as u64 {
+            match self.lookback {
+                Some(lookback) if lookback <= journaled => {}
+                Some(lookback) => {
+                    return Err(KunQuantError::InvalidStreamState {
+                        reason: format!(
+                            "lookback of {} ticks exceeds the {} journaled ticks",
+                            lookback, journaled
+                        ),
+                    });
+                }
+                None => {
+                    return Err(KunQuantError::InvalidStreamState {
+                        reason: format!(
+                            "only the last {} of {} ticks are journaled and no lookback is \
+                             declared with set_lookback()",
+                            journaled, self.ticks_run
+                        ),
+                    });
+                }
+            }
+        }
+        let inputs: Vec<&str> = journal
+            .inputs()
+            .into_iter()
+            .flatten()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        let required: Option<Vec<&str>> = self
+            .required_inputs
+            .as_ref()
+            .map(|required| required.iter().map(|(name, _)| name.as_str()).collect());
+        let header = snapshot::StateHeader {
+            module_name: self.module.name(),
+            library_fingerprint: self.module.library().fingerprint()?,
+            num_stocks: self.num_stocks,
+            inputs: &inputs,
+            required: required.as_deref(),
+            lookback: self.lookback,
+        };
+        snapshot::write_state(writer, &header, journal)
+    }
+
+    /// Creates a streaming context from a checkpoint written by `save_state()`.
+    ///
+    /// The checkpoint's journaled ticks are replayed through the new context, so
+    /// rolling windows are filled and the outputs of the last checkpointed tick are
+    /// available immediately. The new context keeps journaling with the same
+    /// capacity, lookback and required inputs as the saved one, see
+    /// [Tick Completeness](StreamContext#tick-completeness). Its
+    /// [`ticks_run`](StreamContext::ticks_run) counts the replayed ticks, so
+    /// [`remaining_warm_up`](StreamContext::remaining_warm_up) reflects the windows
+    /// the replay could fill.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Executor for the new context
+    /// * `module` - The module the checkpoint was saved from
+    /// * `reader` - Source of the checkpoint data
+    ///
+    /// # Returns
+    ///
+    /// Returns the restored context, or an error if:
+    /// - The checkpoint is malformed, or belongs to another module or library build
+    ///   (`InvalidStreamState`)
+    /// - Reading fails (`Io`)
+    /// - The context cannot be created or an input is unknown to the module
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use kunquant_rs::{Executor, Library, StreamContext};
+    ///
+    /// # fn main() -> kunquant_rs::Result<()> {
+    /// let executor = Executor::single_thread()?;
+    /// let library = Library::load("factors.so")?;
+    /// let module = library.get_module("alpha_stream")?;
+    ///
+    /// let file = std::fs::File::open("alpha.state")?;
+    /// let stream = StreamContext::restore(&executor, &module, std::io::BufReader::new(file))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn restore<R: Read>(
+        executor: &'a Executor,
+        module: &'a Module<'a>,
+        reader: R,
+    ) -> Result<Self> {
+        let state = snapshot::read_state(reader)?;
+        if state.module_name != module.name() {
+            return Err(KunQuantError::InvalidStreamState {
+                reason: format!(
+                    "state was saved for module '{}', not '{}'",
+                    state.module_name,
+                    module.name()
+                ),
+            });
+        }
+        if state.library_fingerprint != module.library().fingerprint()? {
+            return Err(KunQuantError::InvalidStreamState {
+                reason: format!(
+                    "state was saved with a different build of '{}'",
+                    module.library().path().display()
+                ),
+            });
+        }
+
+        let mut stream = StreamContext::new(executor, module, state.num_stocks)?;
+        let handles = state
+            .inputs
+            .iter()
+            .map(|name| stream.get_buffer_handle(name))
+            .collect::<Result<Vec<_>>>()?;
+        // Replay unchecked; the saved requirement is put in place afterwards
+        stream.required_inputs = Some(Vec::new());
+        stream.enable_state_journal(state.capacity);
+        for tick in &state.ticks {
+            for (data, &handle) in tick.chunks_exact(state.num_stocks).zip(&handles) {
+                stream.push_unchecked(handle, data)?;
+            }
+            stream.run()?;
+        }
+        stream.required_inputs = None;
+        if let Some(required) = &state.required {
+            stream.set_required_inputs(required)?;
+        }
+        stream.lookback = state.lookback;
+        if let Some(journal) = &mut stream.journal {
+            journal.set_total_ticks(state.total_ticks);
+        }
+        Ok(stream)
+    }
+
+    /// Replays a TS history through the context to fill its rolling windows.
+    ///
+    /// Each of the `num_times` rows of the history is pushed as one tick and run,
+    /// exactly as if it had arrived live. Intermediate outputs are discarded; use
+    /// [`warm_up_with`](StreamContext::warm_up_with) to observe them.
+    ///
+    /// # Arguments
+    ///
+    /// * `history` - Data for every input in TS layout (`[time, stock]`), each
+    ///   `num_times * num_stocks` long, oldest row first
+    /// * `num_times` - Number of historical time steps to replay
+    ///
+    /// # Returns
+    ///
+    /// Returns a [`WarmUpReport`], or an error if:
+    /// - Any history length doesn't match `num_times * num_stocks`
+    /// - Any buffer name is not found
+    /// - A tick fails to run (e.g. an input of the module is missing from `history`)
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kunquant_rs::{StreamContext, Result};
+    /// # use std::collections::HashMap;
+    /// # fn example(mut stream: StreamContext, close: &[f32], open: &[f32]) -> Result<()> {
+    /// stream.set_lookback(60);
+    ///
+    /// let history = HashMap::from([("close", close), ("open", open)]);
+    /// let report = stream.warm_up(&history, close.len() / stream.num_stocks())?;
+    /// if let Some(remaining) = report.remaining {
+    ///     println!("{} more ticks until outputs are fully valid", remaining);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn warm_up(
+        &mut self,
+        history: &HashMap<&str, &[f32]>,
+        num_times: usize,
+    ) -> Result<WarmUpReport> {
+        self.warm_up_with(history, num_times, |_, _| Ok(()))
+    }
+
+    /// Like [`warm_up`](StreamContext::warm_up), calling `on_step` after every tick.
+    ///
+    /// `on_step` receives the time index within the history and the context, so it
+    /// can read outputs with `get_current_buffer()` or `output()`. Returning an error
+    /// stops the replay.
+    pub fn warm_up_with<F>(
+        &mut self,
+        history: &HashMap<&str, &[f32]>,
+        num_times: usize,
+        mut on_step: F,
+    ) -> Result<WarmUpReport>
+    where
+        F: FnMut(usize, &Self) -> Result<()>,
+    {
+        let mut series = Vec::with_capacity(history.len());
+        for (&name, &data) in history {
+            if data.len() != num_times * self.num_stocks {
+                return Err(KunQuantError::BufferSizeMismatch {
+                    name: name.to_string(),
+                    expected: num_times * self.num_stocks,
+                    actual: data.len(),
+                    context: None,
+                });
+            }
+            series.push((self.get_buffer_handle(name)?, data));
+        }
+
+        for t in 0..num_times {
+            let row = t * self.num_stocks..(t + 1) * self.num_stocks;
+            for (handle, data) in &series {
+                let start = self.start_timer();
+                let result = self.push_unchecked(*handle, &data[row.clone()]);
+                self.stop_timer(StreamOperation::PushData, start);
+                result?;
+            }
+            self.run()?;
+            on_step(t, self)?;
+        }
+
+        Ok(WarmUpReport {
+            steps: num_times,
+            remaining: self.remaining_warm_up(),
+        })
+    }
+
+    /// Declares how many ticks the module needs before its outputs are fully valid.
+    ///
+    /// This is usually the longest rolling window of the factor. The runtime does
+    /// not expose it, so it has to be provided by the caller.
+    pub fn set_lookback(&mut self, lookback: usize) {
+        self.lookback = Some(lookback);
+    }
+
+    /// Iterates over the stocks suspended in the last run by a
+    /// [`MissingDataPolicy::Suspend`] input.
+    ///
+    /// Their outputs read through the context keep the values of the previous run.
+    pub fn suspended_stocks(&self) -> impl Iterator<Item = usize> + '_ {
+        self.suspended
+            .iter()
+            .enumerate()
+            .filter(|(_, suspended)| **suspended)
+            .map(|(stock, _)| stock)
+    }
+
+    /// Returns `true` if `stock` was suspended in the last run, see
+    /// [`suspended_stocks`](StreamContext::suspended_stocks).
+    pub fn is_suspended(&self, stock: usize) -> bool {
+        self.suspended.get(stock).copied().unwrap_or(false)
+    }
+
+    /// Starts recording latency histograms of `push_data()`, `run()` and
+    /// `get_current_buffer()`, see [`StreamOperation`] for what is timed.
+    ///
+    /// While disabled (the default) the operations are not timed at all. Enabling
+    /// again clears the recorded latencies.
+    pub fn enable_latency_tracking(&mut self) {
+        self.latency = Some(RefCell::new(StreamLatency::default()));
+    }
+
+    /// Stops recording latencies and discards the histograms.
+    pub fn disable_latency_tracking(&mut self) {
+        self.latency = None;
+    }
+
+    /// Returns a copy of the latency histograms, or `None` if tracking is not
+    /// enabled.
+    pub fn latency(&self) -> Option<StreamLatency> {
+        self.latency
+            .as_ref()
+            .map(|latency| latency.borrow().clone())
+    }
+
+    /// Clears the recorded latencies, e.g. after warming up.
+    pub fn reset_latency(&self) {
+        if let Some(latency) = &self.latency {
+            latency.borrow_mut().reset();
+        }
+    }
+
+    /// Context for errors of `operation` on this context.
+    fn error_context(&self, operation: &str) -> ErrorContext {
+        ErrorContext::new(operation).with_module(self.module)
+    }
+
+    fn start_timer(&self) -> Option<Instant> {
+        self.latency.as_ref().map(|_| Instant::now())
+    }
+
+    fn stop_timer(&self, operation: StreamOperation, start: Option<Instant>) {
+        if let (Some(latency), Some(start)) = (&self.latency, start) {
+            latency.borrow_mut().record(operation, start.elapsed());
+        }
+    }
+
+    /// Number of ticks successfully run on this context.
+    pub fn ticks_run(&self) -> u64 {
+        self.ticks_run
+    }
+
+    /// Ticks still needed before outputs are fully valid, if a lookback was declared.
+    pub fn remaining_warm_up(&self) -> Option<usize> {
+        self.lookback
+            .map(|lookback| (lookback as u64).saturating_sub(self.ticks_run) as usize)
+    }
+
     /// Returns the number of stocks this streaming context is configured to process.
     ///
     /// This value is set during context creation and determines the expected length
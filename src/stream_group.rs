@@ -0,0 +1,374 @@
+use crate::error::{KunQuantError, Result};
+use crate::executor::Executor;
+use crate::library::Library;
+use crate::owned_stream::OwnedStreamContext;
+use crate::stream::{StreamHandle, StreamInputs};
+use std::collections::{HashMap, HashSet};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::JoinHandle;
+
+/// Fans one market feed out to many streaming contexts.
+///
+/// A `StreamGroup` owns a set of named [`OwnedStreamContext`]s. The names of the
+/// feed's inputs are declared with [`with_inputs`](StreamGroup::with_inputs), and
+/// each input pushed to the group is forwarded to every member whose module
+/// declares a buffer with that name; members without it are skipped. The runtime
+/// resolves output buffers by name as well, so only declared inputs are accepted:
+/// a push can't overwrite a member's outputs. Which members accept which input is
+/// resolved once and cached, so steady-state pushes do no name lookups.
+///
+/// [`run`](StreamGroup::run) advances every member by one tick, optionally on a
+/// pool of worker threads, and the outputs are then available keyed by
+/// `(member, output)`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use kunquant_rs::{Executor, Library, StreamGroup};
+/// use std::sync::Arc;
+///
+/// # fn main() -> kunquant_rs::Result<()> {
+/// let executor = Arc::new(Executor::single_thread()?);
+/// let library = Arc::new(Library::load("factors.so")?);
+///
+/// let mut group = StreamGroup::new()
+///     .with_inputs(&["close", "open"])
+///     .with_parallelism(4);
+/// for module in ["alpha001_stream", "alpha002_stream"] {
+///     group.add_module(executor.clone(), library.clone(), module, 16)?;
+/// }
+///
+/// group.push_data("close", &[10.0; 16])?;
+/// group.push_data("open", &[9.5; 16])?;
+/// group.run()?;
+///
+/// let alpha = group.output("alpha001_stream", "alpha001")?;
+/// println!("{:?}", alpha);
+/// # Ok(())
+/// # }
+/// ```
+pub struct StreamGroup {
+    members: Vec<GroupMember>,
+    inputs: HashSet<String>,
+    parallelism: usize,
+    // Started by the first parallel run
+    pool: Option<GroupPool>,
+}
+
+struct GroupMember {
+    name: String,
+    context: OwnedStreamContext,
+    // `None` caches that the member's module has no buffer with this name
+    inputs: HashMap<String, Option<StreamHandle>>,
+}
+
+impl GroupMember {
+    fn input_handle(&mut self, name: &str) -> Result<Option<StreamHandle>> {
+        if let Some(handle) = self.inputs.get(name) {
+            return Ok(*handle);
+        }
+        let handle = match self.context.resolve(name) {
+            Ok(handle) => Some(handle),
+            Err(KunQuantError::BufferHandleNotFound { .. }) => None,
+            Err(e) => return Err(e),
+        };
+        self.inputs.insert(name.to_string(), handle);
+        Ok(handle)
+    }
+
+    fn push_data(&mut self, name: &str, data: &[f32]) -> Result<bool> {
+        match self.input_handle(name)? {
+            Some(handle) => self.context.push(handle, data).map(|_| true),
+            None => Ok(false),
+        }
+    }
+}
+
+/// Result of running one member, with a panic caught so that the member can be
+/// handed back before the panic is resumed.
+type MemberResult = std::thread::Result<Result<()>>;
+
+/// Persistent threads running batches of members for parallel groups.
+///
+/// Members are moved to a worker with each batch and moved back with the results,
+/// so no context is shared between threads.
+struct GroupPool {
+    workers: Vec<PoolWorker>,
+}
+
+struct PoolWorker {
+    batches: Sender<Vec<GroupMember>>,
+    results: Receiver<(Vec<GroupMember>, Vec<MemberResult>)>,
+    thread: JoinHandle<()>,
+}
+
+impl GroupPool {
+    fn new(threads: usize) -> Result<Self> {
+        let mut workers = Vec::with_capacity(threads);
+        for index in 0..threads {
+            let (batches, batch_receiver) = mpsc::channel::<Vec<GroupMember>>();
+            let (result_sender, results) = mpsc::channel();
+            let thread = std::thread::Builder::new()
+                .name(format!("kunquant-group-{}", index))
+                .spawn(move || {
+                    for mut batch in batch_receiver {
+                        let results = batch
+                            .iter_mut()
+                            .map(|m| panic::catch_unwind(AssertUnwindSafe(|| m.context.run())))
+                            .collect();
+                        if result_sender.send((batch, results)).is_err() {
+                            break;
+                        }
+                    }
+                })?;
+            workers.push(PoolWorker {
+                batches,
+                results,
+                thread,
+            });
+        }
+        Ok(GroupPool { workers })
+    }
+
+    /// Runs `members` split into contiguous batches, one per worker, and returns
+    /// them in their original order with one result each.
+    fn run(&self, members: Vec<GroupMember>) -> (Vec<GroupMember>, Vec<MemberResult>) {
+        let batch_size = members.len().div_ceil(self.workers.len());
+        let mut members = members.into_iter();
+        let mut busy = 0;
+        for worker in &self.workers {
+            let batch: Vec<_> = members.by_ref().take(batch_size).collect();
+            if batch.is_empty() {
+                break;
+            }
+            worker
+                .batches
+                .send(batch)
+                .expect("stream group worker stopped");
+            busy += 1;
+        }
+
+        let mut members = Vec::new();
+        let mut results = Vec::new();
+        for worker in &self.workers[..busy] {
+            let (batch, batch_results) =
+                worker.results.recv().expect("stream group worker stopped");
+            members.extend(batch);
+            results.extend(batch_results);
+        }
+        (members, results)
+    }
+}
+
+impl Drop for GroupPool {
+    fn drop(&mut self) {
+        for worker in self.workers.drain(..) {
+            // Closing the batch channel ends the worker's loop
+            drop(worker.batches);
+            let _ = worker.thread.join();
+        }
+    }
+}
+
+impl Default for StreamGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamGroup {
+    /// Creates an empty group that runs its members sequentially.
+    pub fn new() -> Self {
+        StreamGroup {
+            members: Vec::new(),
+            inputs: HashSet::new(),
+            parallelism: 1,
+            pool: None,
+        }
+    }
+
+    /// Declares the names of the inputs pushed to the group.
+    ///
+    /// [`push_data`](StreamGroup::push_data) rejects every other name, which keeps
+    /// pushes away from the members' output buffers.
+    pub fn with_inputs<N: AsRef<str>>(mut self, names: &[N]) -> Self {
+        self.inputs
+            .extend(names.iter().map(|name| name.as_ref().to_string()));
+        self
+    }
+
+    /// Runs members on up to `threads` threads in [`run`](StreamGroup::run).
+    ///
+    /// Members are split into contiguous batches, one per thread. The threads are
+    /// started by the first run and kept until the group is dropped. A value of 0
+    /// or 1 runs everything on the calling thread.
+    pub fn with_parallelism(mut self, threads: usize) -> Self {
+        self.parallelism = threads.max(1);
+        self.pool = None;
+        self
+    }
+
+    /// Adds a context under `name`.
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if a member with the same name already exists.
+    pub fn add<N: Into<String>>(&mut self, name: N, context: OwnedStreamContext) -> Result<()> {
+        let name = name.into();
+        if self.members.iter().any(|m| m.name == name) {
+            return Err(KunQuantError::DuplicateStreamMember { name });
+        }
+        self.members.push(GroupMember {
+            name,
+            context,
+            inputs: HashMap::new(),
+        });
+        Ok(())
+    }
+
+    /// Creates a context for `module_name` and adds it under the module name.
+    pub fn add_module<N: AsRef<str>>(
+        &mut self,
+        executor: Arc<Executor>,
+        library: Arc<Library>,
+        module_name: N,
+        num_stocks: usize,
+    ) -> Result<()> {
+        let module_name = module_name.as_ref();
+        let context = OwnedStreamContext::new(executor, library, module_name, num_stocks)?;
+        self.add(module_name, context)
+    }
+
+    /// Pushes one input of the current tick to every member that declares it.
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if:
+    /// - The name was not declared with [`with_inputs`](StreamGroup::with_inputs)
+    ///   (`InvalidBufferName`)
+    /// - No member has a buffer with this name (`BufferHandleNotFound`)
+    /// - The data length doesn't match a member's number of stocks
+    pub fn push_data<N: AsRef<str>>(&mut self, name: N, data: &[f32]) -> Result<()> {
+        let name = name.as_ref();
+        if !self.inputs.contains(name) {
+            return Err(KunQuantError::InvalidBufferName {
+                name: name.to_string(),
+            });
+        }
+        let mut accepted = false;
+        for member in &mut self.members {
+            accepted |= member.push_data(name, data)?;
+        }
+        if !accepted {
+            return Err(KunQuantError::BufferHandleNotFound {
+                name: name.to_string(),
+                context: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// Pushes every input of the current tick, see [`push_data`](StreamGroup::push_data).
+    pub fn push_all(&mut self, inputs: &StreamInputs) -> Result<()> {
+        for (name, data) in inputs.iter() {
+            self.push_data(name, data)?;
+        }
+        Ok(())
+    }
+
+    /// Runs one tick on every member.
+    ///
+    /// Every member is run even if another one fails.
+    ///
+    /// # Returns
+    ///
+    /// Returns `StreamGroupFailed` with the error of every failed member, in member
+    /// order. A member that panics resumes the panic on the calling thread once all
+    /// members have run.
+    pub fn run(&mut self) -> Result<()> {
+        let results: Vec<MemberResult> = if self.parallelism <= 1 || self.members.len() <= 1 {
+            self.members
+                .iter_mut()
+                .map(|m| Ok(m.context.run()))
+                .collect()
+        } else {
+            if self.pool.is_none() {
+                self.pool = Some(GroupPool::new(self.parallelism)?);
+            }
+            let pool = self.pool.as_ref().expect("pool was just started");
+            let (members, results) = pool.run(std::mem::take(&mut self.members));
+            self.members = members;
+            results
+        };
+
+        let mut failures = Vec::new();
+        for (member, result) in self.members.iter().zip(results) {
+            match result {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => failures.push((member.name.clone(), e)),
+                Err(payload) => panic::resume_unwind(payload),
+            }
+        }
+        if !failures.is_empty() {
+            return Err(KunQuantError::StreamGroupFailed { failures });
+        }
+        Ok(())
+    }
+
+    /// Returns the current values of `output` of member `member`.
+    ///
+    /// # Returns
+    ///
+    /// Returns `UnknownStreamMember` if the group has no member of that name, or an
+    /// error if the member has no buffer named `output`.
+    pub fn output(&self, member: &str, output: &str) -> Result<&[f32]> {
+        self.context(member)
+            .ok_or_else(|| KunQuantError::UnknownStreamMember {
+                name: member.to_string(),
+            })?
+            .get_current_buffer(output)
+    }
+
+    /// Returns the current values of several outputs keyed by `(member, output)`.
+    pub fn outputs<'k>(
+        &self,
+        keys: &[(&'k str, &'k str)],
+    ) -> Result<HashMap<(&'k str, &'k str), &[f32]>> {
+        keys.iter()
+            .map(|&(member, output)| Ok(((member, output), self.output(member, output)?)))
+            .collect()
+    }
+
+    /// Returns the context of a member.
+    pub fn context(&self, member: &str) -> Option<&OwnedStreamContext> {
+        self.members
+            .iter()
+            .find(|m| m.name == member)
+            .map(|m| &m.context)
+    }
+
+    /// Returns the context of a member for member-specific pushes or configuration.
+    pub fn context_mut(&mut self, member: &str) -> Option<&mut OwnedStreamContext> {
+        self.members
+            .iter_mut()
+            .find(|m| m.name == member)
+            .map(|m| &mut m.context)
+    }
+
+    /// Names of the members, in insertion order.
+    pub fn members(&self) -> impl Iterator<Item = &str> {
+        self.members.iter().map(|m| m.name.as_str())
+    }
+
+    /// Number of members.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns `true` if the group has no members.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+}
@@ -0,0 +1,121 @@
+use std::sync::mpsc;
+
+/// Identifies a subscription registered on a stream context.
+///
+/// Returned by [`StreamContext::subscribe`](crate::StreamContext::subscribe) and its
+/// variants, and used to [`unsubscribe`](crate::StreamContext::unsubscribe).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(pub(crate) u64);
+
+/// Receiving end of [`StreamContext::subscribe_channel`](crate::StreamContext::subscribe_channel),
+/// yielding `(tick_index, values)` pairs.
+pub type OutputReceiver = mpsc::Receiver<(u64, Vec<f32>)>;
+
+type UpdateCallback<'a> = Box<dyn FnMut(u64, &[f32]) + Send + 'a>;
+type ChangeCallback<'a> = Box<dyn FnMut(u64, &[f32], &[usize]) + Send + 'a>;
+
+enum Delivery<'a> {
+    Every(UpdateCallback<'a>),
+    Changes {
+        callback: ChangeCallback<'a>,
+        // Values of the previous tick, empty before the first one
+        previous: Vec<f32>,
+        changed: Vec<usize>,
+    },
+}
+
+/// A callback registered for one output of a stream context.
+pub(crate) struct Subscriber<'a> {
+    pub(crate) id: SubscriptionId,
+    pub(crate) handle: usize,
+    delivery: Delivery<'a>,
+}
+
+impl<'a> Subscriber<'a> {
+    pub(crate) fn every<F>(id: SubscriptionId, handle: usize, callback: F) -> Self
+    where
+        F: FnMut(u64, &[f32]) + Send + 'a,
+    {
+        Subscriber {
+            id,
+            handle,
+            delivery: Delivery::Every(Box::new(callback)),
+        }
+    }
+
+    pub(crate) fn changes<F>(id: SubscriptionId, handle: usize, callback: F) -> Self
+    where
+        F: FnMut(u64, &[f32], &[usize]) + Send + 'a,
+    {
+        Subscriber {
+            id,
+            handle,
+            delivery: Delivery::Changes {
+                callback: Box::new(callback),
+                previous: Vec::new(),
+                changed: Vec::new(),
+            },
+        }
+    }
+
+    /// Delivers the values of tick `tick` to the callback.
+    pub(crate) fn notify(&mut self, tick: u64, values: &[f32]) {
+        match &mut self.delivery {
+            Delivery::Every(callback) => callback(tick, values),
+            Delivery::Changes {
+                callback,
+                previous,
+                changed,
+            } => {
+                changed.clear();
+                if previous.len() == values.len() {
+                    // Bitwise comparison, so a NaN that stays NaN is not a change
+                    changed.extend(
+                        values
+                            .iter()
+                            .zip(previous.iter())
+                            .enumerate()
+                            .filter(|(_, (v, p))| v.to_bits() != p.to_bits())
+                            .map(|(stock, _)| stock),
+                    );
+                    previous.copy_from_slice(values);
+                } else {
+                    changed.extend(0..values.len());
+                    previous.clear();
+                    previous.extend_from_slice(values);
+                }
+                if !changed.is_empty() {
+                    callback(tick, values, changed);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_changes_only_reports_changed_stocks() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let mut subscriber = Subscriber::changes(
+            SubscriptionId(0),
+            0,
+            move |tick, _: &[f32], changed: &[usize]| {
+                sink.lock().unwrap().push((tick, changed.to_vec()));
+            },
+        );
+
+        subscriber.notify(0, &[1.0, f32::NAN, 3.0]);
+        subscriber.notify(1, &[1.0, f32::NAN, 3.0]);
+        subscriber.notify(2, &[1.0, 2.0, 4.0]);
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![(0, vec![0, 1, 2]), (2, vec![1, 2])]
+        );
+    }
+}
@@ -0,0 +1,349 @@
+use crate::error::{KunQuantError, Result};
+use crate::executor::Executor;
+use crate::library::Library;
+use crate::owned_stream::OwnedStreamContext;
+use crate::snapshot;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A streaming context addressed by symbol, for universes that change over time.
+///
+/// A stream context processes a fixed number of stocks. `SymbolStream` maps
+/// symbols to stock slots of an [`OwnedStreamContext`] and reserves spare slots so
+/// symbols can be added without touching the running context:
+///
+/// - [`add_symbol`](SymbolStream::add_symbol) takes a spare slot. When none is left
+///   the context is rebuilt with more slots.
+/// - [`remove_symbol`](SymbolStream::remove_symbol) releases the symbol. Its slot is
+///   fed NaN from then on and is only reused after the next rebuild, so a new
+///   symbol never inherits another symbol's rolling windows.
+/// - A rebuild creates a new context and replays the state journal (the last
+///   `history` ticks) into it with the retained symbols' columns moved to their new
+///   slots. Symbols added by the rebuild see NaN for the replayed ticks, and
+///   removed symbols are dropped.
+///
+/// The replay is exact only if `history` covers every tick the module's outputs
+/// depend on. Recursive factors (e.g. exponential averages) depend on every tick
+/// since the start, so after a rebuild their outputs restart from the last
+/// `history` ticks and differ from those of the old context until the difference
+/// decays; choose `history` so that this is below the precision you need.
+///
+/// The rebuilt context keeps the required inputs and the lookback of the old one.
+/// It is otherwise new, which is why the stream only lends its context out
+/// immutably: output histories, subscriptions and latency tracking could not be
+/// carried over to new slots and are not available through `SymbolStream`.
+///
+/// Add and remove symbols between ticks, not between pushes and `run()`: a rebuild
+/// discards pushes of the current tick.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use kunquant_rs::{Executor, Library, SymbolStream};
+/// use std::sync::Arc;
+///
+/// # fn main() -> kunquant_rs::Result<()> {
+/// let executor = Arc::new(Executor::single_thread()?);
+/// let library = Arc::new(Library::load("factors.so")?);
+///
+/// // Keep 8 spare slots and the last 240 ticks for migrating state on rebuilds
+/// let mut stream = SymbolStream::new(
+///     executor, library, "alpha_stream", &["600000.SH", "000001.SZ"], 8, 240,
+/// )?;
+///
+/// stream.push_symbols("close", [("600000.SH", 10.2), ("000001.SZ", 12.5)])?;
+/// stream.run()?;
+///
+/// stream.add_symbol("688981.SH")?;
+/// stream.remove_symbol("000001.SZ");
+/// # Ok(())
+/// # }
+/// ```
+pub struct SymbolStream {
+    context: OwnedStreamContext,
+    slots: HashMap<String, usize>,
+    // Free slots never used since the last rebuild, highest first
+    spare: Vec<usize>,
+    // Whether each slot holds a live symbol
+    active: Vec<bool>,
+    spare_slots: usize,
+    history: usize,
+    scratch: Vec<f32>,
+}
+
+impl SymbolStream {
+    /// Creates a stream for `symbols` of the named module.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - Executor for the stream context
+    /// * `library` - Library containing the streaming module
+    /// * `module_name` - Name of the streaming module
+    /// * `symbols` - Initial universe; symbol `i` gets slot `i`
+    /// * `spare_slots` - Number of free slots to reserve for additions, also
+    ///   used as the growth step of rebuilds
+    /// * `history` - Number of recent ticks journaled to migrate state on rebuilds,
+    ///   also declared as the module's lookback; it must cover the longest window
+    ///   of the module for rebuilds to keep the outputs intact, see the
+    ///   [type documentation](SymbolStream) for recursive factors
+    ///
+    /// # Returns
+    ///
+    /// Returns the stream, or an error if the module is not found or the context
+    /// cannot be created.
+    pub fn new<N: AsRef<str>, S: AsRef<str>>(
+        executor: Arc<Executor>,
+        library: Arc<Library>,
+        module_name: N,
+        symbols: &[S],
+        spare_slots: usize,
+        history: usize,
+    ) -> Result<Self> {
+        let capacity = symbols.len() + spare_slots;
+        let mut context = OwnedStreamContext::new(executor, library, module_name, capacity)?;
+        context.enable_state_journal(history);
+        context.set_lookback(history);
+
+        let mut slots = HashMap::with_capacity(capacity);
+        for symbol in symbols {
+            let slot = slots.len();
+            slots.entry(symbol.as_ref().to_string()).or_insert(slot);
+        }
+        let mut active = vec![false; capacity];
+        slots.values().for_each(|&slot| active[slot] = true);
+
+        Ok(SymbolStream {
+            context,
+            spare: (slots.len()..capacity).rev().collect(),
+            slots,
+            active,
+            spare_slots,
+            history,
+            scratch: vec![f32::NAN; capacity],
+        })
+    }
+
+    /// Adds a symbol and returns its slot.
+    ///
+    /// Adding a symbol that is already present returns its current slot. If no
+    /// spare slot is left, the context is rebuilt first, which can move the slots
+    /// of other symbols and, for recursive factors, change their outputs (see
+    /// [`rebuild`](SymbolStream::rebuild)).
+    pub fn add_symbol<S: AsRef<str>>(&mut self, symbol: S) -> Result<usize> {
+        let symbol = symbol.as_ref();
+        if let Some(&slot) = self.slots.get(symbol) {
+            return Ok(slot);
+        }
+        if self.spare.is_empty() {
+            self.rebuild(self.slots.len() + 1 + self.spare_slots)?;
+        }
+        let slot = self
+            .spare
+            .pop()
+            .expect("rebuild always leaves a spare slot");
+        self.active[slot] = true;
+        self.slots.insert(symbol.to_string(), slot);
+        Ok(slot)
+    }
+
+    /// Removes a symbol from the universe.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the symbol was present.
+    pub fn remove_symbol<S: AsRef<str>>(&mut self, symbol: S) -> bool {
+        match self.slots.remove(symbol.as_ref()) {
+            Some(slot) => {
+                self.active[slot] = false;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Rebuilds the context with `capacity` slots and migrates retained symbols.
+    ///
+    /// Retained symbols are compacted into the lowest slots in their current order
+    /// and their journaled ticks are replayed into the new context. Slots of
+    /// removed symbols become spare again.
+    ///
+    /// The new context requires the same inputs as the old one and counts only the
+    /// replayed ticks as run. Outputs that depend on more than the last `history`
+    /// ticks, such as those of recursive factors, are recomputed from the replayed
+    /// ticks alone and differ from the old context's.
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if the checkpoint cannot be taken or the new context fails
+    /// to replay it. The current context is kept in that case.
+    pub fn rebuild(&mut self, capacity: usize) -> Result<()> {
+        let capacity = capacity.max(self.slots.len());
+        let mut checkpoint = Vec::new();
+        self.context.save_state(&mut checkpoint)?;
+        let state = snapshot::read_state(checkpoint.as_slice())?;
+
+        // New slot of every retained symbol, in the order of their old slots
+        let mut retained: Vec<(String, usize)> = self
+            .slots
+            .iter()
+            .map(|(symbol, &slot)| (symbol.clone(), slot))
+            .collect();
+        retained.sort_by_key(|(_, slot)| *slot);
+
+        let num_times = state.ticks.len();
+        let mut history = vec![vec![f32::NAN; num_times * capacity]; state.inputs.len()];
+        for (t, tick) in state.ticks.iter().enumerate() {
+            for (input, data) in history.iter_mut().zip(tick.chunks_exact(state.num_stocks)) {
+                for (new_slot, (_, old_slot)) in retained.iter().enumerate() {
+                    input[t * capacity + new_slot] = data[*old_slot];
+                }
+            }
+        }
+
+        let mut context = OwnedStreamContext::new(
+            self.context.executor().clone(),
+            self.context.library().clone(),
+            self.context.module().name(),
+            capacity,
+        )?;
+        context.enable_state_journal(self.history);
+        context.set_lookback(self.history);
+        // Carry the completeness check over, including an opt-out
+        if let Some(required) = &state.required {
+            context.set_required_inputs(required)?;
+        }
+        let inputs: HashMap<&str, &[f32]> = state
+            .inputs
+            .iter()
+            .map(String::as_str)
+            .zip(history.iter().map(Vec::as_slice))
+            .collect();
+        context.warm_up(&inputs, num_times)?;
+
+        self.slots = retained
+            .into_iter()
+            .enumerate()
+            .map(|(new_slot, (symbol, _))| (symbol, new_slot))
+            .collect();
+        self.active = (0..capacity).map(|slot| slot < self.slots.len()).collect();
+        self.spare = (self.slots.len()..capacity).rev().collect();
+        self.scratch = vec![f32::NAN; capacity];
+        self.context = context;
+        Ok(())
+    }
+
+    /// Pushes one input for the current tick, keyed by symbol.
+    ///
+    /// Symbols not listed, spare slots and removed symbols get NaN.
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if a symbol is not in the universe (`UnknownSymbol`) or the
+    /// input name is not found.
+    pub fn push_symbols<'s, N, I>(&mut self, name: N, values: I) -> Result<()>
+    where
+        N: AsRef<str>,
+        I: IntoIterator<Item = (&'s str, f32)>,
+    {
+        self.scratch.fill(f32::NAN);
+        for (symbol, value) in values {
+            let slot = self
+                .slots
+                .get(symbol)
+                .ok_or_else(|| KunQuantError::UnknownSymbol {
+                    symbol: symbol.to_string(),
+                })?;
+            self.scratch[*slot] = value;
+        }
+        self.context.push_data(name, &self.scratch)
+    }
+
+    /// Pushes one input for the current tick in slot order.
+    ///
+    /// `data` must hold [`capacity`](SymbolStream::capacity) values; values of
+    /// spare slots and removed symbols are replaced by NaN.
+    pub fn push_slots<N: AsRef<str>>(&mut self, name: N, data: &[f32]) -> Result<()> {
+        if data.len() != self.scratch.len() {
+            return Err(KunQuantError::BufferSizeMismatch {
+                name: name.as_ref().to_string(),
+                expected: self.scratch.len(),
+                actual: data.len(),
+                context: None,
+            });
+        }
+        for ((dst, &src), &active) in self.scratch.iter_mut().zip(data).zip(&self.active) {
+            *dst = if active { src } else { f32::NAN };
+        }
+        self.context.push_data(name, &self.scratch)
+    }
+
+    /// Runs the computation for the current tick.
+    pub fn run(&mut self) -> Result<()> {
+        self.context.run()
+    }
+
+    /// Returns the current values of an output in slot order.
+    pub fn output<N: AsRef<str>>(&self, name: N) -> Result<&[f32]> {
+        self.context.get_current_buffer(name)
+    }
+
+    /// Returns the current value of an output for one symbol.
+    pub fn output_for<N: AsRef<str>, S: AsRef<str>>(&self, name: N, symbol: S) -> Result<f32> {
+        let symbol = symbol.as_ref();
+        let slot = self
+            .slot(symbol)
+            .ok_or_else(|| KunQuantError::UnknownSymbol {
+                symbol: symbol.to_string(),
+            })?;
+        Ok(self.output(name)?[slot])
+    }
+
+    /// Returns the current values of an output for every symbol in the universe.
+    pub fn outputs_by_symbol<N: AsRef<str>>(&self, name: N) -> Result<HashMap<&str, f32>> {
+        let values = self.output(name)?;
+        Ok(self
+            .slots
+            .iter()
+            .map(|(symbol, &slot)| (symbol.as_str(), values[slot]))
+            .collect())
+    }
+
+    /// Returns the slot of a symbol.
+    pub fn slot(&self, symbol: &str) -> Option<usize> {
+        self.slots.get(symbol).copied()
+    }
+
+    /// Iterates over the symbols in the universe and their slots.
+    pub fn symbols(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.slots
+            .iter()
+            .map(|(symbol, &slot)| (symbol.as_str(), slot))
+    }
+
+    /// Number of symbols in the universe.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns `true` if the universe is empty.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Number of slots of the current context.
+    pub fn capacity(&self) -> usize {
+        self.scratch.len()
+    }
+
+    /// Number of spare slots left before the next rebuild.
+    pub fn spare_slots(&self) -> usize {
+        self.spare.len()
+    }
+
+    /// Returns the underlying stream context.
+    ///
+    /// The context is replaced by every rebuild.
+    pub fn context(&self) -> &OwnedStreamContext {
+        &self.context
+    }
+}
@@ -0,0 +1,784 @@
+use crate::batch::{BatchParams, BatchRunner, OutputLayout};
+use crate::error::{KunQuantError, Result};
+use crate::executor::Executor;
+use crate::library::Module;
+use crate::replay::StreamReplay;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Tolerance used when comparing two computed factor values.
+///
+/// Two values match if any of the enabled criteria holds:
+/// - they are bitwise equal (including `+0.0 == -0.0`),
+/// - their absolute difference is at most `abs`,
+/// - their relative difference is at most `rel` of the larger magnitude,
+/// - they are at most `max_ulps` representable `f32` values apart.
+///
+/// NaN only matches NaN, and only when `nan_equal` is set.
+///
+/// The default tolerance requires exact equality and treats NaN as equal to NaN,
+/// which is what a deterministic runtime should produce.
+///
+/// # Examples
+///
+/// ```rust
+/// use kunquant_rs::verify::Tolerance;
+///
+/// let tolerance = Tolerance::default().with_max_ulps(4).with_rel(1e-6);
+/// assert!(tolerance.matches(1.0, 1.0 + f32::EPSILON));
+/// assert!(tolerance.matches(f32::NAN, f32::NAN));
+/// assert!(!tolerance.matches(1.0, f32::NAN));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    /// Maximum distance in units in the last place
+    pub max_ulps: u32,
+    /// Maximum relative difference
+    pub rel: f32,
+    /// Maximum absolute difference
+    pub abs: f32,
+    /// Whether NaN is considered equal to NaN
+    pub nan_equal: bool,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance {
+            max_ulps: 0,
+            rel: 0.0,
+            abs: 0.0,
+            nan_equal: true,
+        }
+    }
+}
+
+impl Tolerance {
+    /// Sets the maximum distance in units in the last place.
+    pub fn with_max_ulps(mut self, max_ulps: u32) -> Self {
+        self.max_ulps = max_ulps;
+        self
+    }
+
+    /// Sets the maximum relative difference.
+    pub fn with_rel(mut self, rel: f32) -> Self {
+        self.rel = rel;
+        self
+    }
+
+    /// Sets the maximum absolute difference.
+    pub fn with_abs(mut self, abs: f32) -> Self {
+        self.abs = abs;
+        self
+    }
+
+    /// Sets whether NaN is considered equal to NaN.
+    pub fn with_nan_equal(mut self, nan_equal: bool) -> Self {
+        self.nan_equal = nan_equal;
+        self
+    }
+
+    /// Returns `true` if `a` and `b` are equal within this tolerance.
+    pub fn matches(&self, a: f32, b: f32) -> bool {
+        if a.is_nan() || b.is_nan() {
+            return self.nan_equal && a.is_nan() && b.is_nan();
+        }
+        if a == b {
+            return true;
+        }
+        let diff = (a - b).abs();
+        if diff <= self.abs {
+            return true;
+        }
+        if diff <= self.rel * a.abs().max(b.abs()) {
+            return true;
+        }
+        ulps_between(a, b) <= u64::from(self.max_ulps)
+    }
+}
+
+/// Number of representable `f32` values between `a` and `b`.
+fn ulps_between(a: f32, b: f32) -> u64 {
+    // Map the sign-magnitude bit pattern onto a monotonic integer line
+    fn ordered(x: f32) -> i64 {
+        let bits = x.to_bits() as i32 as i64;
+        if bits < 0 {
+            i64::from(i32::MIN) - bits
+        } else {
+            bits
+        }
+    }
+    ordered(a).abs_diff(ordered(b))
+}
+
+/// Executor used by one run of a [`DeterminismChecker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutorKind {
+    /// `Executor::single_thread()`
+    SingleThread,
+    /// `Executor::multi_thread(n)`
+    MultiThread(i32),
+}
+
+/// One execution configuration compared by a [`DeterminismChecker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunConfig {
+    /// Executor to run the module on
+    pub executor: ExecutorKind,
+    /// Chunk length for a chunked [`BatchRunner`] run, or `None` for the full range
+    pub chunk_len: Option<usize>,
+}
+
+impl RunConfig {
+    /// Full-range run on a single-threaded executor.
+    pub fn single_thread() -> Self {
+        RunConfig {
+            executor: ExecutorKind::SingleThread,
+            chunk_len: None,
+        }
+    }
+
+    /// Full-range run on a multi-threaded executor with `num_threads` threads.
+    pub fn multi_thread(num_threads: i32) -> Self {
+        RunConfig {
+            executor: ExecutorKind::MultiThread(num_threads),
+            chunk_len: None,
+        }
+    }
+
+    /// Runs this configuration in chunks of `chunk_len` time steps.
+    pub fn chunked(mut self, chunk_len: usize) -> Self {
+        self.chunk_len = Some(chunk_len);
+        self
+    }
+
+    fn create_executor(&self) -> Result<Executor> {
+        match self.executor {
+            ExecutorKind::SingleThread => Executor::single_thread(),
+            ExecutorKind::MultiThread(n) => Executor::multi_thread(n),
+        }
+    }
+}
+
+impl fmt::Display for RunConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.executor {
+            ExecutorKind::SingleThread => write!(f, "single_thread")?,
+            ExecutorKind::MultiThread(n) => write!(f, "multi_thread({})", n)?,
+        }
+        if let Some(chunk_len) = self.chunk_len {
+            write!(f, " chunk_len={}", chunk_len)?;
+        }
+        Ok(())
+    }
+}
+
+/// A single output value that differs between the reference and another run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    /// Configuration that produced `actual`
+    pub config: RunConfig,
+    /// Name of the output buffer
+    pub output: String,
+    /// Absolute time index (offset by the requested `cur_time`)
+    pub time: usize,
+    /// Stock index
+    pub stock: usize,
+    /// Value produced by the reference configuration
+    pub expected: f32,
+    /// Value produced by `config`
+    pub actual: f32,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {} at time {} stock {}: expected {}, got {}",
+            self.config, self.output, self.time, self.stock, self.expected, self.actual
+        )
+    }
+}
+
+/// Result of a [`DeterminismChecker::check`] run.
+#[derive(Debug, Clone)]
+pub struct DeterminismReport {
+    /// Configuration whose outputs the others were compared against
+    pub reference: RunConfig,
+    /// Recorded mismatches, at most `max_mismatches` of them
+    pub mismatches: Vec<Mismatch>,
+    /// Number of mismatching values per `(config, output)`, including unrecorded ones
+    pub mismatch_counts: Vec<(RunConfig, String, usize)>,
+}
+
+impl DeterminismReport {
+    /// Returns `true` if every configuration matched the reference.
+    pub fn is_consistent(&self) -> bool {
+        self.total_mismatches() == 0
+    }
+
+    /// Total number of mismatching values across all configurations and outputs.
+    pub fn total_mismatches(&self) -> usize {
+        self.mismatch_counts.iter().map(|(_, _, count)| count).sum()
+    }
+}
+
+// Outputs are pre-filled with a different value per run, so values the module
+// never writes mismatch under any tolerance, NaN handling included
+const REFERENCE_SENTINEL: f32 = f32::MAX;
+const RUN_SENTINEL: f32 = f32::MIN;
+
+/// Verifies that a module produces the same results on different executors.
+///
+/// The checker runs one module with the same inputs on several [`RunConfig`]s
+/// (single vs multi-threaded executors, full range vs chunked runs), compares all
+/// outputs against the first configuration and reports every value that differs
+/// beyond the configured [`Tolerance`], with its output name, stock and time index.
+///
+/// # Data Layout
+///
+/// Outputs are read in the layout the module was compiled with, declared with
+/// [`with_output_layout`](DeterminismChecker::with_output_layout) and KunQuant's
+/// default [`OutputLayout::STs`] otherwise. Chunked configurations are driven by
+/// [`BatchRunner`] and therefore need modules compiled with the TS layout.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use kunquant_rs::{BatchParams, Library, OutputLayout};
+/// use kunquant_rs::verify::{DeterminismChecker, RunConfig, Tolerance};
+/// use std::collections::HashMap;
+///
+/// # fn main() -> kunquant_rs::Result<()> {
+/// let library = Library::load("factors.so")?;
+/// let module = library.get_module("alpha001")?;
+///
+/// let close = vec![1.0f32; 16 * 100];
+/// let inputs = HashMap::from([("close", close.as_slice())]);
+///
+/// let report = DeterminismChecker::new(&module)
+///     .with_output_layout(OutputLayout::TS)
+///     .with_config(RunConfig::single_thread())
+///     .with_config(RunConfig::multi_thread(4))
+///     .with_config(RunConfig::multi_thread(4).chunked(10))
+///     .with_tolerance(Tolerance::default().with_max_ulps(2))
+///     .check(&inputs, &["alpha001"], &BatchParams::full_range(16, 100)?)?;
+///
+/// for mismatch in &report.mismatches {
+///     println!("{}", mismatch);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct DeterminismChecker<'a> {
+    module: &'a Module<'a>,
+    configs: Vec<RunConfig>,
+    layout: OutputLayout,
+    tolerance: Tolerance,
+    max_mismatches: usize,
+}
+
+impl<'a> DeterminismChecker<'a> {
+    /// Default number of mismatches recorded in a report.
+    pub const DEFAULT_MAX_MISMATCHES: usize = 100;
+
+    /// Creates a checker for `module`.
+    ///
+    /// If no configuration is added, [`check`](DeterminismChecker::check) compares
+    /// a single-threaded run against a multi-threaded run using all available cores.
+    pub fn new(module: &'a Module<'a>) -> Self {
+        DeterminismChecker {
+            module,
+            configs: Vec::new(),
+            layout: OutputLayout::STs,
+            tolerance: Tolerance::default(),
+            max_mismatches: Self::DEFAULT_MAX_MISMATCHES,
+        }
+    }
+
+    /// Adds a configuration. The first configuration added is the reference.
+    pub fn with_config(mut self, config: RunConfig) -> Self {
+        self.configs.push(config);
+        self
+    }
+
+    /// Declares the output layout the module was compiled with, see
+    /// [Data Layout](DeterminismChecker#data-layout).
+    pub fn with_output_layout(mut self, layout: OutputLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Sets the tolerance used to compare outputs.
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Limits how many individual mismatches are recorded in the report.
+    ///
+    /// Mismatches beyond the limit are still counted in `mismatch_counts`.
+    pub fn with_max_mismatches(mut self, max_mismatches: usize) -> Self {
+        self.max_mismatches = max_mismatches;
+        self
+    }
+
+    /// Runs every configuration and compares their outputs.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - Input buffers keyed by name, each `num_stocks * total_time` long
+    /// * `output_names` - Names of the module outputs to compare
+    /// * `params` - Batch parameters shared by all runs
+    ///
+    /// # Returns
+    ///
+    /// Returns a [`DeterminismReport`], or an error if:
+    /// - Fewer than two configurations were added (`InvalidBatchConfig`)
+    /// - A configuration is chunked but the module's layout is not TS
+    ///   (`InvalidBatchConfig`)
+    /// - Any run fails
+    pub fn check(
+        &self,
+        inputs: &HashMap<&str, &[f32]>,
+        output_names: &[&str],
+        params: &BatchParams,
+    ) -> Result<DeterminismReport> {
+        let configs = if self.configs.is_empty() {
+            let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+            vec![
+                RunConfig::single_thread(),
+                RunConfig::multi_thread(cores as i32),
+            ]
+        } else {
+            self.configs.clone()
+        };
+        if configs.len() < 2 {
+            return Err(KunQuantError::InvalidBatchConfig {
+                reason: format!(
+                    "a determinism check compares at least 2 configurations, got {}",
+                    configs.len()
+                ),
+            });
+        }
+
+        let reference = self.run_config(
+            &configs[0],
+            inputs,
+            output_names,
+            params,
+            REFERENCE_SENTINEL,
+        )?;
+        let mut report = DeterminismReport {
+            reference: configs[0],
+            mismatches: Vec::new(),
+            mismatch_counts: Vec::new(),
+        };
+
+        for config in &configs[1..] {
+            let outputs = self.run_config(config, inputs, output_names, params, RUN_SENTINEL)?;
+            for (name, (expected, actual)) in
+                output_names.iter().zip(reference.iter().zip(&outputs))
+            {
+                let mut count = 0;
+                for (idx, (&e, &a)) in expected.iter().zip(actual).enumerate() {
+                    if self.tolerance.matches(e, a) {
+                        continue;
+                    }
+                    count += 1;
+                    if report.mismatches.len() < self.max_mismatches {
+                        let (time, stock) = position(self.layout, idx, params);
+                        report.mismatches.push(Mismatch {
+                            config: *config,
+                            output: name.to_string(),
+                            time: params.cur_time + time,
+                            stock,
+                            expected: e,
+                            actual: a,
+                        });
+                    }
+                }
+                report
+                    .mismatch_counts
+                    .push((*config, name.to_string(), count));
+            }
+        }
+        Ok(report)
+    }
+
+    fn run_config(
+        &self,
+        config: &RunConfig,
+        inputs: &HashMap<&str, &[f32]>,
+        output_names: &[&str],
+        params: &BatchParams,
+        sentinel: f32,
+    ) -> Result<Vec<Vec<f32>>> {
+        let executor = config.create_executor()?;
+        // The runner rejects chunks unless the layout is TS
+        let mut runner = BatchRunner::new(&executor, self.module).with_output_layout(self.layout);
+        if let Some(chunk_len) = config.chunk_len {
+            runner = runner.with_chunk_len(chunk_len);
+        }
+
+        let mut storage =
+            vec![vec![sentinel; params.num_stocks * params.length]; output_names.len()];
+        let mut outputs: HashMap<&str, &mut [f32]> = output_names
+            .iter()
+            .copied()
+            .zip(storage.iter_mut().map(|v| v.as_mut_slice()))
+            .collect();
+        runner.run(inputs, &mut outputs, params)?;
+        drop(outputs);
+        Ok(storage)
+    }
+}
+
+/// Time step within the run and stock of the output value at `idx`.
+fn position(layout: OutputLayout, idx: usize, params: &BatchParams) -> (usize, usize) {
+    match layout {
+        OutputLayout::TS => (idx / params.num_stocks, idx % params.num_stocks),
+        // [stock / 8, time, 8]
+        OutputLayout::STs => {
+            let block = idx / (params.length * 8);
+            let within = idx % (params.length * 8);
+            (within / 8, block * 8 + within % 8)
+        }
+    }
+}
+
+/// The first output value where a stream module and a batch module disagree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    /// Name of the output buffer
+    pub output: String,
+    /// Time index (tick number of the replay)
+    pub time: usize,
+    /// Stock index
+    pub stock: usize,
+    /// Value produced by the batch module
+    pub batch: f32,
+    /// Value produced by the stream module
+    pub stream: f32,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} diverges at time {} stock {}: batch {}, stream {}",
+            self.output, self.time, self.stock, self.batch, self.stream
+        )
+    }
+}
+
+/// Result of a [`StreamConsistencyChecker::check`] run.
+#[derive(Debug, Clone)]
+pub struct StreamConsistencyReport {
+    /// Earliest divergence by time step, then stock, across all outputs
+    pub first_divergence: Option<Divergence>,
+    /// Number of diverging values per output
+    pub divergence_counts: Vec<(String, usize)>,
+}
+
+impl StreamConsistencyReport {
+    /// Returns `true` if the stream and batch outputs agree everywhere.
+    pub fn is_consistent(&self) -> bool {
+        self.first_divergence.is_none()
+    }
+
+    /// Total number of diverging values across all outputs.
+    pub fn total_divergences(&self) -> usize {
+        self.divergence_counts.iter().map(|(_, count)| count).sum()
+    }
+
+    /// Compares one output, keeping the earliest divergence seen so far.
+    fn compare(
+        &mut self,
+        name: &str,
+        batch: &[f32],
+        stream: &[f32],
+        num_stocks: usize,
+        skip_time: usize,
+        tolerance: &Tolerance,
+    ) {
+        let mut count = 0;
+        for (idx, (&b, &s)) in batch
+            .iter()
+            .zip(stream)
+            .enumerate()
+            .skip(skip_time * num_stocks)
+        {
+            if tolerance.matches(b, s) {
+                continue;
+            }
+            count += 1;
+            let earlier = self
+                .first_divergence
+                .as_ref()
+                .is_none_or(|first| idx < first.time * num_stocks + first.stock);
+            if earlier {
+                self.first_divergence = Some(Divergence {
+                    output: name.to_string(),
+                    time: idx / num_stocks,
+                    stock: idx % num_stocks,
+                    batch: b,
+                    stream: s,
+                });
+            }
+        }
+        self.divergence_counts.push((name.to_string(), count));
+    }
+}
+
+/// Verifies that a stream module and a batch module of the same factor agree.
+///
+/// The checker runs the batch module (`output_layout="TS"`) over the full input
+/// range and replays the same inputs tick by tick through the stream module
+/// (`output_layout="STREAM"`) with a [`StreamReplay`]. Both modules must use the
+/// same input and output names. Outputs are compared with a [`Tolerance`] and the
+/// first diverging time step and stock is reported.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use kunquant_rs::{Executor, Library};
+/// use kunquant_rs::verify::{StreamConsistencyChecker, Tolerance};
+/// use std::collections::HashMap;
+///
+/// # fn main() -> kunquant_rs::Result<()> {
+/// let executor = Executor::single_thread()?;
+/// let library = Library::load("factors.so")?;
+/// let batch = library.get_module("alpha001")?;
+/// let stream = library.get_module("alpha001_stream")?;
+///
+/// let close = vec![1.0f32; 16 * 100];
+/// let inputs = HashMap::from([("close", close.as_slice())]);
+///
+/// let report = StreamConsistencyChecker::new(&executor, &batch, &stream)
+///     .with_tolerance(Tolerance::default().with_rel(1e-5))
+///     .check(&inputs, &["alpha001"], 16, 100)?;
+///
+/// if let Some(divergence) = &report.first_divergence {
+///     println!("{}", divergence);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct StreamConsistencyChecker<'a> {
+    executor: &'a Executor,
+    batch: &'a Module<'a>,
+    stream: &'a Module<'a>,
+    tolerance: Tolerance,
+    skip_time: usize,
+}
+
+impl<'a> StreamConsistencyChecker<'a> {
+    /// Creates a checker comparing `stream` against `batch`, both run on `executor`.
+    pub fn new(executor: &'a Executor, batch: &'a Module<'a>, stream: &'a Module<'a>) -> Self {
+        StreamConsistencyChecker {
+            executor,
+            batch,
+            stream,
+            tolerance: Tolerance::default(),
+            skip_time: 0,
+        }
+    }
+
+    /// Sets the tolerance used to compare outputs.
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Ignores the first `skip_time` time steps, e.g. a warm-up period where the
+    /// two implementations are allowed to differ.
+    pub fn with_skip_time(mut self, skip_time: usize) -> Self {
+        self.skip_time = skip_time;
+        self
+    }
+
+    /// Runs both modules and compares their outputs.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - Input buffers in TS layout, each `num_stocks * num_time` long
+    /// * `output_names` - Names of the outputs to compare
+    /// * `num_stocks` - Number of stocks
+    /// * `num_time` - Number of time steps
+    ///
+    /// # Returns
+    ///
+    /// Returns a [`StreamConsistencyReport`], or an error if either run fails.
+    pub fn check(
+        &self,
+        inputs: &HashMap<&str, &[f32]>,
+        output_names: &[&str],
+        num_stocks: usize,
+        num_time: usize,
+    ) -> Result<StreamConsistencyReport> {
+        let params = BatchParams::full_range(num_stocks, num_time)?;
+        let mut batch_storage = vec![vec![f32::NAN; num_stocks * num_time]; output_names.len()];
+        let mut batch_outputs: HashMap<&str, &mut [f32]> = output_names
+            .iter()
+            .copied()
+            .zip(batch_storage.iter_mut().map(|v| v.as_mut_slice()))
+            .collect();
+        BatchRunner::new(self.executor, self.batch).run(inputs, &mut batch_outputs, &params)?;
+        drop(batch_outputs);
+
+        let replay = StreamReplay::new(self.executor, self.stream, num_stocks).run(
+            inputs,
+            output_names,
+            num_time,
+        )?;
+
+        let mut report = StreamConsistencyReport {
+            first_divergence: None,
+            divergence_counts: Vec::new(),
+        };
+        for (name, batch) in output_names.iter().zip(&batch_storage) {
+            let stream = replay.output(name).unwrap_or_default();
+            report.compare(
+                name,
+                batch,
+                stream,
+                num_stocks,
+                self.skip_time,
+                &self.tolerance,
+            );
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_tolerance_is_exact() {
+        let tolerance = Tolerance::default();
+        assert!(tolerance.matches(1.5, 1.5));
+        assert!(tolerance.matches(0.0, -0.0));
+        assert!(tolerance.matches(f32::NAN, f32::NAN));
+        assert!(!tolerance.matches(1.0, 1.0 + f32::EPSILON));
+        assert!(!tolerance.matches(f32::NAN, 0.0));
+    }
+
+    #[test]
+    fn test_ulp_tolerance() {
+        let tolerance = Tolerance::default().with_max_ulps(2);
+        let one_ulp = f32::from_bits(1.0f32.to_bits() + 1);
+        let three_ulps = f32::from_bits(1.0f32.to_bits() + 3);
+        assert!(tolerance.matches(1.0, one_ulp));
+        assert!(!tolerance.matches(1.0, three_ulps));
+        // Distance across zero counts the values on both sides
+        let tiny = f32::from_bits(1);
+        assert!(tolerance.matches(tiny, -tiny));
+    }
+
+    #[test]
+    fn test_rel_abs_and_nan_tolerance() {
+        assert!(Tolerance::default().with_rel(1e-3).matches(1000.0, 1000.5));
+        assert!(!Tolerance::default().with_rel(1e-3).matches(1.0, 1.01));
+        assert!(Tolerance::default().with_abs(0.1).matches(1.0, 1.05));
+        assert!(
+            !Tolerance::default()
+                .with_nan_equal(false)
+                .matches(f32::NAN, f32::NAN)
+        );
+    }
+
+    #[test]
+    fn test_unwritten_outputs_never_match() {
+        for tolerance in [
+            Tolerance::default(),
+            Tolerance::default()
+                .with_rel(1.0)
+                .with_abs(1e30)
+                .with_max_ulps(1_000_000),
+        ] {
+            assert!(!tolerance.matches(REFERENCE_SENTINEL, RUN_SENTINEL));
+        }
+    }
+
+    #[test]
+    fn test_mismatch_positions_follow_layout() {
+        let params = BatchParams::full_range(16, 3).unwrap();
+        assert_eq!(position(OutputLayout::TS, 17, &params), (1, 1));
+        // Second block of 8 stocks starts after 3 * 8 values
+        assert_eq!(position(OutputLayout::STs, 9, &params), (1, 1));
+        assert_eq!(position(OutputLayout::STs, 24 + 10, &params), (1, 10));
+    }
+
+    #[test]
+    fn test_run_config_display() {
+        assert_eq!(RunConfig::single_thread().to_string(), "single_thread");
+        assert_eq!(
+            RunConfig::multi_thread(4).chunked(10).to_string(),
+            "multi_thread(4) chunk_len=10"
+        );
+    }
+
+    #[test]
+    fn test_reports_earliest_divergence_across_outputs() {
+        let mut report = StreamConsistencyReport {
+            first_divergence: None,
+            divergence_counts: Vec::new(),
+        };
+        let tolerance = Tolerance::default();
+        // [time][stock] with 2 stocks
+        report.compare(
+            "a",
+            &[0.0, 1.0, 2.0, 3.0],
+            &[0.0, 1.0, 2.0, 9.0],
+            2,
+            0,
+            &tolerance,
+        );
+        report.compare(
+            "b",
+            &[0.0, 1.0, 2.0, 3.0],
+            &[0.0, 5.0, 6.0, 3.0],
+            2,
+            0,
+            &tolerance,
+        );
+        let first = report.first_divergence.as_ref().unwrap();
+        assert_eq!(
+            (first.output.as_str(), first.time, first.stock),
+            ("b", 0, 1)
+        );
+        assert_eq!(report.total_divergences(), 3);
+
+        // Skipped warm-up rows are not compared
+        let mut skipped = StreamConsistencyReport {
+            first_divergence: None,
+            divergence_counts: Vec::new(),
+        };
+        skipped.compare(
+            "b",
+            &[0.0, 1.0, 2.0, 3.0],
+            &[0.0, 5.0, 2.0, 3.0],
+            2,
+            1,
+            &tolerance,
+        );
+        assert!(skipped.is_consistent());
+    }
+
+    #[test]
+    fn test_divergence_display() {
+        let divergence = Divergence {
+            output: "alpha".to_string(),
+            time: 3,
+            stock: 7,
+            batch: 1.0,
+            stream: 2.0,
+        };
+        assert_eq!(
+            divergence.to_string(),
+            "alpha diverges at time 3 stock 7: batch 1, stream 2"
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! ABI compatibility check between factor libraries and the KunQuant runtime.
+//!
+//! A factor library and `libKunRuntime` may carry their ABI tag as an exported,
+//! NUL-terminated `char kunquant_abi_version[]` array. KunQuant releases don't emit
+//! the tag themselves; builds that want the check define it in both the runtime and
+//! the generated libraries, e.g. with
+//! `extern "C" const char kunquant_abi_version[] = "1.2";`.
+//!
+//! The tags are read from the ELF dynamic symbol table on disk. Only the ELF
+//! header, the section headers, `.dynsym`, `.dynstr` and the tag itself are read,
+//! and nothing is mapped by the dynamic loader, so no static initializer of an
+//! incompatible library runs before it is rejected.
+//!
+//! A library is rejected when its tag differs from the runtime's, including when
+//! only one of them carries a tag. When neither does, as with stock KunQuant
+//! builds, nothing can be checked: the library is accepted and a warning is
+//! printed to stderr once per process. Files that aren't 64-bit little-endian ELF
+//! objects can't be checked and are accepted.
+
+use crate::error::{KunQuantError, Result};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::{Once, OnceLock};
+
+const ABI_VERSION_SYMBOL: &[u8] = b"kunquant_abi_version";
+
+/// Version reported in `IncompatibleLibrary` for a side without a tag.
+const UNTAGGED: &str = "untagged";
+
+/// Returns the ABI version tag of the loaded `libKunRuntime`.
+///
+/// Returns `None` if the runtime doesn't export a version tag or the platform has
+/// no way to look it up. [`Library::load`](crate::Library::load) then rejects
+/// tagged libraries and loads untagged ones without a check, printing a warning
+/// to stderr once per process.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// match kunquant_rs::runtime_version() {
+///     Some(version) => println!("KunQuant runtime ABI {}", version),
+///     None => println!("KunQuant runtime without ABI tag"),
+/// }
+/// ```
+pub fn runtime_version() -> Option<&'static str> {
+    static VERSION: OnceLock<Option<String>> = OnceLock::new();
+    VERSION.get_or_init(read_runtime_version).as_deref()
+}
+
+/// Fails with `IncompatibleLibrary` if the library at `path` was built for a
+/// different runtime ABI than the one loaded.
+///
+/// A library that can't be read passes; loading it then reports the reason.
+pub(crate) fn check_library(path: &Path) -> Result<()> {
+    let checked = check_against(path, runtime_version())?;
+    if !checked {
+        static WARNING: Once = Once::new();
+        WARNING.call_once(|| {
+            eprintln!(
+                "kunquant_rs: warning: neither libKunRuntime nor {} exports a \
+                 kunquant_abi_version tag, so factor libraries are loaded without an \
+                 ABI compatibility check",
+                path.display()
+            )
+        });
+    }
+    Ok(())
+}
+
+/// Checks the tag of the library at `path` against `runtime_version`.
+///
+/// Returns `false` if neither side has a tag, so the ABI could not be checked.
+fn check_against(path: &Path, runtime_version: Option<&str>) -> Result<bool> {
+    let Some(library_tag) = read_tag(path) else {
+        return Ok(true);
+    };
+    let incompatible = |library_version: Option<String>| KunQuantError::IncompatibleLibrary {
+        library_version: library_version.unwrap_or_else(|| UNTAGGED.to_string()),
+        runtime_version: runtime_version.unwrap_or(UNTAGGED).to_string(),
+        context: None,
+    };
+    match (library_tag, runtime_version) {
+        (None, None) => Ok(false),
+        (Some(library_version), Some(runtime_version)) if library_version == runtime_version => {
+            Ok(true)
+        }
+        (library_version, _) => Err(incompatible(library_version)),
+    }
+}
+
+#[cfg(unix)]
+fn read_runtime_version() -> Option<String> {
+    // Find the file the runtime was loaded from through one of its functions, so
+    // the tag isn't picked up from a factor library loaded with global symbols
+    let path = unsafe {
+        let mut info: libc::Dl_info = std::mem::zeroed();
+        let function = crate::ffi::kunCreateBufferNameMap as *const libc::c_void;
+        if libc::dladdr(function, &mut info) == 0 || info.dli_fname.is_null() {
+            return None;
+        }
+        std::ffi::CStr::from_ptr(info.dli_fname).to_owned()
+    };
+    use std::os::unix::ffi::OsStrExt;
+    read_tag(Path::new(std::ffi::OsStr::from_bytes(path.to_bytes()))).flatten()
+}
+
+#[cfg(not(unix))]
+fn read_runtime_version() -> Option<String> {
+    None
+}
+
+/// Reads the `kunquant_abi_version` tag of the shared object at `path`.
+///
+/// Returns `None` if the file can't be read or isn't a supported ELF object, and
+/// `Some(None)` if it has no tag.
+fn read_tag(path: &Path) -> Option<Option<String>> {
+    let file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    elf_tag(&mut ElfReader { file, len })
+}
+
+/// Bounds-checked reads of parts of an ELF file.
+struct ElfReader<R> {
+    file: R,
+    len: u64,
+}
+
+impl<R: Read + Seek> ElfReader<R> {
+    /// Reads `len` bytes at `offset`, or `None` if they lie outside the file.
+    fn read(&mut self, offset: u64, len: u64) -> Option<Vec<u8>> {
+        if offset.checked_add(len)? > self.len {
+            return None;
+        }
+        let mut data = vec![0; usize::try_from(len).ok()?];
+        self.file.seek(SeekFrom::Start(offset)).ok()?;
+        self.file.read_exact(&mut data).ok()?;
+        Some(data)
+    }
+}
+
+// Offsets into 64-bit ELF headers (`Elf64_Ehdr`, `Elf64_Shdr`, `Elf64_Sym`)
+const EHDR_SIZE: u64 = 64;
+const SHT_DYNSYM: u32 = 11;
+const SHT_NOBITS: u32 = 8;
+const SHN_UNDEF: u16 = 0;
+const SYM_SIZE: usize = 24;
+
+/// Finds the tag in the dynamic symbol table of an ELF file.
+fn elf_tag<R: Read + Seek>(reader: &mut ElfReader<R>) -> Option<Option<String>> {
+    let header = reader.read(0, EHDR_SIZE)?;
+    // Only 64-bit (class 2) little-endian (data 1) objects are supported
+    if header.get(..6)? != b"\x7fELF\x02\x01" {
+        return None;
+    }
+    let shoff = u64_at(&header, 0x28)?;
+    let shentsize = u16_at(&header, 0x3a)? as usize;
+    let shnum = u16_at(&header, 0x3c)? as usize;
+    let headers = reader.read(shoff, (shnum * shentsize) as u64)?;
+    let section = |index: usize| slice(&headers, index.checked_mul(shentsize)?, shentsize);
+
+    let Some(dynsym) = (0..shnum)
+        .filter_map(section)
+        .find(|header| u32_at(header, 4) == Some(SHT_DYNSYM))
+    else {
+        return Some(None);
+    };
+    let strtab = section(u32_at(dynsym, 0x28)? as usize)?;
+    let names = reader.read(u64_at(strtab, 0x18)?, u64_at(strtab, 0x20)?)?;
+    let symbols = reader.read(u64_at(dynsym, 0x18)?, u64_at(dynsym, 0x20)?)?;
+    for symbol in symbols.chunks_exact(SYM_SIZE) {
+        let name = names.get(u32_at(symbol, 0)? as usize..)?;
+        let shndx = u16_at(symbol, 6)?;
+        if !name.starts_with(ABI_VERSION_SYMBOL)
+            || name.get(ABI_VERSION_SYMBOL.len()) != Some(&0)
+            || shndx == SHN_UNDEF
+        {
+            continue;
+        }
+        // Translate the symbol's address into a file offset through its section
+        let target = section(shndx as usize)?;
+        if u32_at(target, 4)? == SHT_NOBITS {
+            return None;
+        }
+        let addr = u64_at(target, 0x10)?;
+        let offset = u64_at(target, 0x18)?;
+        let start = u64_at(symbol, 8)?.checked_sub(addr)?.checked_add(offset)?;
+        let tag = reader.read(start, u64_at(symbol, 16)?)?;
+        let tag = tag.split(|&b| b == 0).next()?;
+        return Some(Some(String::from_utf8_lossy(tag).into_owned()));
+    }
+    Some(None)
+}
+
+fn slice(data: &[u8], start: usize, len: usize) -> Option<&[u8]> {
+    data.get(start..start.checked_add(len)?)
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(slice(data, offset, 2)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(slice(data, offset, 4)?.try_into().ok()?))
+}
+
+fn u64_at(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(slice(data, offset, 8)?.try_into().ok()?))
+}
+
+#[cfg(all(test, target_os = "linux", target_arch = "x86_64"))]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    /// Compiles `source` into a shared library in the temporary directory.
+    fn build_library(name: &str, source: &str) -> PathBuf {
+        let dir = std::env::temp_dir();
+        let source_path = dir.join(format!("{}-{}.c", name, std::process::id()));
+        let library_path = dir.join(format!("{}-{}.so", name, std::process::id()));
+        std::fs::write(&source_path, source).unwrap();
+        let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+        let status = Command::new(compiler)
+            .args(["-shared", "-fPIC", "-o"])
+            .arg(&library_path)
+            .arg(&source_path)
+            .status()
+            .expect("a C compiler is required to build the test libraries");
+        assert!(status.success());
+        std::fs::remove_file(&source_path).unwrap();
+        library_path
+    }
+
+    #[test]
+    fn test_reads_tag_without_loading() {
+        // The constructor would abort the test process if the library were loaded
+        let tagged = build_library(
+            "kunquant-tagged",
+            "#include <stdlib.h>\n\
+             const char kunquant_abi_version[] = \"test-abi-1\";\n\
+             __attribute__((constructor)) static void init(void) { abort(); }\n",
+        );
+        let untagged = build_library("kunquant-untagged", "int unrelated = 1;\n");
+
+        assert_eq!(read_tag(&tagged), Some(Some("test-abi-1".to_string())));
+        assert_eq!(read_tag(&untagged), Some(None));
+        assert_eq!(read_tag(Path::new("Cargo.toml")), None);
+
+        std::fs::remove_file(tagged).unwrap();
+        std::fs::remove_file(untagged).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_mismatched_tag() {
+        let library = build_library(
+            "kunquant-mismatched",
+            "const char kunquant_abi_version[] = \"test-abi-1\";\n",
+        );
+
+        match check_against(&library, Some("test-abi-2")) {
+            Err(KunQuantError::IncompatibleLibrary {
+                library_version,
+                runtime_version,
+                ..
+            }) => {
+                assert_eq!(library_version, "test-abi-1");
+                assert_eq!(runtime_version, "test-abi-2");
+            }
+            other => panic!("Expected IncompatibleLibrary, got {:?}", other),
+        }
+        assert!(check_against(&library, Some("test-abi-1")).unwrap());
+
+        // A tag on only one side is a mismatch, none at all can't be checked
+        let untagged = build_library("kunquant-unmarked", "int unrelated = 1;\n");
+        for (library, runtime_version) in [(&library, None), (&untagged, Some("test-abi-1"))] {
+            match check_against(library, runtime_version) {
+                Err(KunQuantError::IncompatibleLibrary { .. }) => {}
+                other => panic!("Expected IncompatibleLibrary, got {:?}", other),
+            }
+        }
+        assert!(!check_against(&untagged, None).unwrap());
+
+        std::fs::remove_file(library).unwrap();
+        std::fs::remove_file(untagged).unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+#![cfg(feature = "async")]
+
+use kunquant_rs::engine::{Backpressure, StreamEngine, Tick};
+use kunquant_rs::{Executor, KunQuantError, Library, OwnedStreamContext, Result};
+use std::path::Path;
+use std::sync::Arc;
+
+const NUM_STOCKS: usize = 64;
+
+fn stream_context() -> Result<OwnedStreamContext> {
+    let lib_path = "test_libs/simple_stream_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!(
+            "Streaming test library not found. Please run 'python generate_test_factor.py' first"
+        );
+    }
+
+    let executor = Arc::new(Executor::single_thread()?);
+    let library = Arc::new(Library::load(lib_path)?);
+    OwnedStreamContext::new(executor, library, "simple_stream_test", NUM_STOCKS)
+}
+
+fn tick(close: f32) -> Tick {
+    Tick::new()
+        .with("close", vec![close; NUM_STOCKS])
+        .with("open", vec![0.0; NUM_STOCKS])
+        .with("high", vec![3.0; NUM_STOCKS])
+        .with("low", vec![0.0; NUM_STOCKS])
+}
+
+#[tokio::test]
+async fn test_engine_publishes_outputs_and_shuts_down() -> Result<()> {
+    let mut context = stream_context()?;
+    context.enable_state_journal(16);
+    let engine = StreamEngine::builder(context)
+        .with_outputs(&["simple_stream"])
+        .with_queue_capacity(4)
+        .spawn()?;
+    let mut outputs = engine.subscribe();
+
+    let sender = engine.sender();
+    for step in 0..10 {
+        sender.send(tick(step as f32 * 0.1)).await?;
+    }
+    for step in 0..10u64 {
+        let output = outputs.recv().await.expect("engine stopped early");
+        assert_eq!(output.tick(), step);
+        let expected = (step as f32 * 0.1) / 3.001;
+        assert!((output.output("simple_stream").unwrap()[0] - expected).abs() < 1e-5);
+    }
+
+    let shutdown = engine.shutdown().await?;
+    assert_eq!(shutdown.ticks_processed, 10);
+    assert_eq!(shutdown.ticks_dropped, 0);
+    assert_eq!(shutdown.errors, 0);
+    assert!(!shutdown.state.as_ref().unwrap().is_empty());
+    assert_eq!(shutdown.context.ticks_run(), 10);
+
+    // The engine is gone, so remaining senders are rejected
+    assert!(matches!(
+        sender.send(tick(0.0)).await,
+        Err(KunQuantError::EngineStopped)
+    ));
+
+    println!("✓ Stream engine test completed!");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_engine_drop_policies_never_block() -> Result<()> {
+    for backpressure in [
+        Backpressure::DropOldest { max_backlog: 2 },
+        Backpressure::Conflate,
+    ] {
+        let engine = StreamEngine::builder(stream_context()?)
+            .with_backpressure(backpressure)
+            .spawn()?;
+        let sender = engine.sender();
+        for step in 0..200 {
+            sender.try_send(tick(step as f32 * 0.01))?;
+        }
+        let shutdown = engine.shutdown().await?;
+        assert_eq!(shutdown.ticks_processed + shutdown.ticks_dropped, 200);
+        assert_eq!(shutdown.errors, 0);
+        // Without a state journal there is no checkpoint
+        assert!(shutdown.state.is_none());
+        // The last tick is never dropped
+        let expected = 1.99 / 3.001;
+        assert!((shutdown.context.get_current_buffer("simple_stream")?[0] - expected).abs() < 1e-5);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_engine_counts_failed_ticks() -> Result<()> {
+    let engine = StreamEngine::builder(stream_context()?).spawn()?;
+    engine
+        .send(Tick::new().with("close", vec![1.0; NUM_STOCKS - 1]))
+        .await?;
+    engine.send(tick(1.0)).await?;
+
+    let shutdown = engine.shutdown().await?;
+    assert_eq!(shutdown.errors, 1);
+    assert_eq!(shutdown.ticks_processed, 1);
+    assert!(matches!(
+        shutdown.last_error,
+        Some(KunQuantError::BufferSizeMismatch { .. })
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_engine_discards_partially_pushed_ticks() -> Result<()> {
+    let mut context = stream_context()?;
+    context.set_required_inputs(&["close", "open", "high", "low"])?;
+    let engine = StreamEngine::builder(context).spawn()?;
+
+    // "low" has the wrong length after the other inputs were pushed
+    engine
+        .send(
+            Tick::new()
+                .with("close", vec![1.0; NUM_STOCKS])
+                .with("open", vec![0.0; NUM_STOCKS])
+                .with("high", vec![3.0; NUM_STOCKS])
+                .with("low", vec![0.0; NUM_STOCKS - 1]),
+        )
+        .await?;
+    // The failed tick's pushes must not complete this one
+    engine
+        .send(Tick::new().with("low", vec![0.0; NUM_STOCKS]))
+        .await?;
+    engine.send(tick(1.0)).await?;
+
+    let shutdown = engine.shutdown().await?;
+    assert_eq!(shutdown.errors, 2);
+    assert_eq!(shutdown.ticks_processed, 1);
+    match shutdown.last_error {
+        Some(KunQuantError::MissingStreamInputs { missing }) => {
+            assert_eq!(missing, vec!["close", "open", "high"])
+        }
+        other => panic!("Expected MissingStreamInputs, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_engine_discards_pushes_of_failed_runs() -> Result<()> {
+    let mut context = stream_context()?;
+    context.set_required_inputs(&["close", "open", "high", "low"])?;
+    let engine = StreamEngine::builder(context).spawn()?;
+
+    // "low" is missing, so the run fails
+    engine
+        .send(
+            Tick::new()
+                .with("close", vec![1.0; NUM_STOCKS])
+                .with("open", vec![0.0; NUM_STOCKS])
+                .with("high", vec![3.0; NUM_STOCKS]),
+        )
+        .await?;
+    // Only "low" is new; the inputs of the failed tick must not complete it
+    engine
+        .send(Tick::new().with("low", vec![0.0; NUM_STOCKS]))
+        .await?;
+    engine.send(tick(1.0)).await?;
+
+    let shutdown = engine.shutdown().await?;
+    assert_eq!(shutdown.errors, 2);
+    assert_eq!(shutdown.ticks_processed, 1);
+    match shutdown.last_error {
+        Some(KunQuantError::MissingStreamInputs { missing }) => {
+            assert_eq!(missing, vec!["close", "open", "high"])
+        }
+        other => panic!("Expected MissingStreamInputs, got {:?}", other),
+    }
+    Ok(())
+}
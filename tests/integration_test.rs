@@ -1,8 +1,14 @@
+use kunquant_rs::registry;
+use kunquant_rs::scheduler::{BatchJob, JobHandle, JobScheduler, JobStatus, Priority};
+use kunquant_rs::verify::{DeterminismChecker, RunConfig};
 use kunquant_rs::{
-    BatchParams, BufferNameMap, Executor, Library, Result, StreamContext, run_graph,
+    BatchParams, BatchRunner, BufferNameMap, CancellationToken, Executor, ExecutorBuilder,
+    KunQuantError, Library, OutputLayout, Result, StreamContext, run_graph,
 };
 use rand::Rng;
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
 
 const NUM_STOCKS: usize = 8;
 const NUM_TIME: usize = 100;
@@ -171,3 +177,416 @@ fn test_multi_thread_executor() -> Result<()> {
     println!("✓ Multi-thread executor test passed!");
     Ok(())
 }
+
+#[test]
+fn test_batch_runner_chunked() -> Result<()> {
+    let lib_path = "test_libs/simple_test_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!("Test library not found. Please run 'python generate_test_factor.py' first");
+    }
+
+    let executor = Executor::single_thread()?;
+    let library = Library::load(lib_path)?;
+    let module = library.get_module("simple_test")?;
+
+    let input_data = generate_random_data(NUM_STOCKS * NUM_TIME);
+    let mut output_data = vec![0.0f32; NUM_STOCKS * NUM_TIME];
+
+    let inputs = HashMap::from([("input", input_data.as_slice())]);
+    let mut outputs = HashMap::from([("output", output_data.as_mut_slice())]);
+
+    // 100 time points in chunks of 30 leaves a shorter final chunk
+    let runner = BatchRunner::new(&executor, &module)
+        .with_output_layout(OutputLayout::TS)
+        .with_chunk_len(30);
+    let params = BatchParams::full_range(NUM_STOCKS, NUM_TIME)?;
+    runner.run(&inputs, &mut outputs, &params)?;
+
+    let tolerance = 1e-5;
+    for i in 0..input_data.len() {
+        let expected = input_data[i] * 3.0;
+        let actual = output_data[i];
+        let diff = (expected - actual).abs();
+
+        if diff > tolerance {
+            panic!(
+                "Chunked mismatch at index {}: expected {}, got {}, diff {}",
+                i, expected, actual, diff
+            );
+        }
+    }
+
+    println!("✓ Chunked batch runner test passed!");
+    Ok(())
+}
+
+#[test]
+fn test_batch_runner_cancelled() -> Result<()> {
+    let lib_path = "test_libs/simple_test_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!("Test library not found. Please run 'python generate_test_factor.py' first");
+    }
+
+    let executor = Executor::single_thread()?;
+    let library = Library::load(lib_path)?;
+    let module = library.get_module("simple_test")?;
+
+    let input_data = generate_random_data(NUM_STOCKS * NUM_TIME);
+    let mut output_data = vec![0.0f32; NUM_STOCKS * NUM_TIME];
+
+    let inputs = HashMap::from([("input", input_data.as_slice())]);
+    let mut outputs = HashMap::from([("output", output_data.as_mut_slice())]);
+
+    let token = CancellationToken::new();
+    token.cancel();
+    let runner = BatchRunner::new(&executor, &module)
+        .with_output_layout(OutputLayout::TS)
+        .with_chunk_len(10)
+        .with_cancellation(token);
+    let params = BatchParams::full_range(NUM_STOCKS, NUM_TIME)?;
+
+    match runner.run(&inputs, &mut outputs, &params) {
+        Err(KunQuantError::Cancelled { completed, total }) => {
+            assert_eq!(completed, 0);
+            assert_eq!(total, NUM_TIME);
+        }
+        other => panic!("Expected Cancelled, got {:?}", other),
+    }
+    assert!(output_data.iter().all(|&v| v == 0.0));
+
+    println!("✓ Cancelled batch runner test passed!");
+    Ok(())
+}
+
+#[test]
+fn test_batch_runner_rejects_invalid_chunking() -> Result<()> {
+    let lib_path = "test_libs/simple_test_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!("Test library not found. Please run 'python generate_test_factor.py' first");
+    }
+
+    let executor = Executor::single_thread()?;
+    let library = Library::load(lib_path)?;
+    let module = library.get_module("simple_test")?;
+
+    let input_data = generate_random_data(NUM_STOCKS * NUM_TIME);
+    let mut output_data = vec![0.0f32; NUM_STOCKS * NUM_TIME];
+    let inputs = HashMap::from([("input", input_data.as_slice())]);
+    let mut outputs = HashMap::from([("output", output_data.as_mut_slice())]);
+    let params = BatchParams::full_range(NUM_STOCKS, NUM_TIME)?;
+
+    let runners = [
+        // Zero-length chunks
+        BatchRunner::new(&executor, &module)
+            .with_output_layout(OutputLayout::TS)
+            .with_chunk_len(0),
+        // Chunks of a module whose layout is not declared as TS
+        BatchRunner::new(&executor, &module).with_chunk_len(10),
+        BatchRunner::new(&executor, &module)
+            .with_output_layout(OutputLayout::STs)
+            .with_chunk_len(10),
+    ];
+    for runner in runners {
+        match runner.run(&inputs, &mut outputs, &params) {
+            Err(KunQuantError::InvalidBatchConfig { .. }) => {}
+            other => panic!("Expected InvalidBatchConfig, got {:?}", other),
+        }
+    }
+    assert!(output_data.iter().all(|&v| v == 0.0));
+
+    // A single chunk covering the whole window needs no layout
+    let runner = BatchRunner::new(&executor, &module).with_chunk_len(NUM_TIME);
+    let mut outputs = HashMap::from([("output", output_data.as_mut_slice())]);
+    runner.run(&inputs, &mut outputs, &params)?;
+    assert_eq!(output_data[0], input_data[0] * 3.0);
+
+    println!("✓ Invalid chunking test passed!");
+    Ok(())
+}
+
+#[test]
+fn test_run_graph_reports_runtime_errors() -> Result<()> {
+    let lib_path = "test_libs/simple_test_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!("Test library not found. Please run 'python generate_test_factor.py' first");
+    }
+
+    let executor = Executor::single_thread()?;
+    let library = Library::load(lib_path)?;
+    let module = library.get_module("simple_test")?;
+
+    // The output buffer is never mapped, so the runtime cannot run the graph
+    let mut input_data = generate_random_data(NUM_STOCKS * NUM_TIME);
+    let mut buffers = BufferNameMap::new()?;
+    buffers.set_buffer_slice("input", &mut input_data)?;
+    let params = BatchParams::full_range(NUM_STOCKS, NUM_TIME)?;
+
+    let error = run_graph(&executor, &module, &buffers, &params).unwrap_err();
+    match &error {
+        KunQuantError::RuntimeError {
+            operation, message, ..
+        } => {
+            assert_eq!(operation, "run_graph");
+            assert!(!message.is_empty());
+        }
+        other => panic!("Expected RuntimeError, got {:?}", other),
+    }
+    assert_eq!(error.code(), "RUNTIME_ERROR");
+    assert!(!error.is_retryable());
+
+    // The error says which module and time steps failed
+    let context = error.context().expect("run_graph errors carry context");
+    assert_eq!(context.operation, "run_graph");
+    assert_eq!(context.module.as_deref(), Some("simple_test"));
+    assert_eq!(context.library.as_deref(), Some(Path::new(lib_path)));
+    assert_eq!(context.time_range, Some(0..NUM_TIME));
+
+    println!("✓ Runtime error capture test passed!");
+    Ok(())
+}
+
+#[test]
+fn test_library_load_diagnostics() {
+    match Library::load("test_libs/does_not_exist.so") {
+        Err(KunQuantError::LibraryNotFound { path }) => {
+            assert_eq!(path, "test_libs/does_not_exist.so");
+        }
+        other => panic!("Expected LibraryNotFound, got {:?}", other.err()),
+    }
+
+    // An existing file that isn't a shared library reports the loader's reason
+    match Library::load(Path::new("./Cargo.toml")) {
+        Err(KunQuantError::LibraryLoadFailed { path, reason }) => {
+            assert_eq!(path, "./Cargo.toml");
+            assert!(!reason.is_empty());
+        }
+        other => panic!("Expected LibraryLoadFailed, got {:?}", other.err()),
+    }
+
+    println!("✓ Library load diagnostics test passed!");
+}
+
+#[test]
+fn test_runtime_version_accepts_test_libraries() -> Result<()> {
+    let lib_path = "test_libs/simple_test_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!("Test library not found. Please run 'python generate_test_factor.py' first");
+    }
+
+    // The test libraries are built with the runtime they are loaded by, so the
+    // ABI check must accept them whether or not the runtime carries a tag
+    let version = kunquant_rs::runtime_version();
+    let library = Library::load(lib_path)?;
+    library.get_module("simple_test")?;
+
+    // A missing module names the library it was looked up in
+    let Err(error) = library.get_module("no_such_module") else {
+        panic!("Expected ModuleNotFound");
+    };
+    assert_eq!(error.code(), "MODULE_NOT_FOUND");
+    let context = error.context().expect("get_module errors carry context");
+    assert_eq!(context.library.as_deref(), Some(Path::new(lib_path)));
+
+    println!("✓ Runtime version test passed (runtime ABI {:?})", version);
+    Ok(())
+}
+
+#[test]
+fn test_determinism_across_executors() -> Result<()> {
+    let lib_path = "test_libs/simple_test_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!("Test library not found. Please run 'python generate_test_factor.py' first");
+    }
+
+    let library = Library::load(lib_path)?;
+    let module = library.get_module("simple_test")?;
+
+    let input_data = generate_random_data(NUM_STOCKS * NUM_TIME);
+    let inputs = HashMap::from([("input", input_data.as_slice())]);
+
+    let report = DeterminismChecker::new(&module)
+        .with_output_layout(OutputLayout::TS)
+        .with_config(RunConfig::single_thread())
+        .with_config(RunConfig::multi_thread(4))
+        .with_config(RunConfig::single_thread().chunked(7))
+        .check(
+            &inputs,
+            &["output"],
+            &BatchParams::full_range(NUM_STOCKS, NUM_TIME)?,
+        )?;
+
+    for mismatch in &report.mismatches {
+        println!("  {}", mismatch);
+    }
+    assert!(report.is_consistent());
+
+    // A single configuration has nothing to compare against
+    let params = BatchParams::full_range(NUM_STOCKS, NUM_TIME)?;
+    let single = DeterminismChecker::new(&module)
+        .with_config(RunConfig::single_thread())
+        .check(&inputs, &["output"], &params);
+    assert!(matches!(
+        single,
+        Err(KunQuantError::InvalidBatchConfig { .. })
+    ));
+    // Chunks of an STs module can't be compared
+    let chunked = DeterminismChecker::new(&module)
+        .with_output_layout(OutputLayout::STs)
+        .with_config(RunConfig::single_thread())
+        .with_config(RunConfig::single_thread().chunked(7))
+        .check(&inputs, &["output"], &params);
+    assert!(matches!(
+        chunked,
+        Err(KunQuantError::InvalidBatchConfig { .. })
+    ));
+
+    println!("✓ Determinism test passed!");
+    Ok(())
+}
+
+#[test]
+fn test_executor_builder() -> Result<()> {
+    let executor = Executor::builder().with_threads(2).build()?;
+    drop(executor);
+
+    #[cfg(target_os = "linux")]
+    {
+        let executor = Executor::builder()
+            .with_cpus([0])
+            .with_thread_name("kq-test")
+            .build()?;
+        drop(executor);
+    }
+
+    assert!(matches!(
+        Executor::builder().with_threads(0).build(),
+        Err(KunQuantError::InvalidExecutorConfig { .. })
+    ));
+    assert!(matches!(
+        Executor::multi_thread(0),
+        Err(KunQuantError::InvalidExecutorConfig { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_shared_executor_pools() -> Result<()> {
+    let first = Executor::shared()?;
+    let second = Executor::shared()?;
+    assert!(Arc::ptr_eq(&first, &second));
+
+    assert!(matches!(
+        Executor::shared_named("unconfigured"),
+        Err(KunQuantError::InvalidExecutorConfig { .. })
+    ));
+
+    Executor::configure_shared("integration_test", ExecutorBuilder::new().with_threads(2))?;
+    let pool = Executor::shared_named("integration_test")?;
+    let info = registry::pools()
+        .into_iter()
+        .find(|p| p.name == "integration_test")
+        .unwrap();
+    assert_eq!(info.threads, Some(2));
+    assert_eq!(info.references, 1);
+
+    // The executor is released with its last reference and recreated on demand
+    drop(pool);
+    let info = registry::pools()
+        .into_iter()
+        .find(|p| p.name == "integration_test")
+        .unwrap();
+    assert_eq!(info.references, 0);
+    Executor::shared_named("integration_test")?;
+    Ok(())
+}
+
+#[test]
+fn test_job_scheduler_priorities_and_quotas() -> Result<()> {
+    let lib_path = "test_libs/simple_test_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!("Test library not found. Please run 'python generate_test_factor.py' first");
+    }
+
+    let library = Arc::new(Library::load(lib_path)?);
+    let scheduler = JobScheduler::builder()
+        .with_executor(Arc::new(Executor::single_thread()?))
+        .with_executor(Arc::new(Executor::single_thread()?))
+        .with_tenant_quota("backfill", 1)
+        .with_slice_len(25)
+        .build()?;
+
+    let job = |input: &[f32], priority: Priority, tenant: &str| -> Result<BatchJob> {
+        Ok(BatchJob::new(
+            library.clone(),
+            "simple_test",
+            BatchParams::full_range(NUM_STOCKS, NUM_TIME)?,
+        )
+        .with_input("input", input.to_vec())
+        .with_output("output")
+        .with_priority(priority)
+        .with_tenant(tenant)
+        .with_output_layout(OutputLayout::TS)
+        .with_chunk_len(10))
+    };
+
+    let input = generate_random_data(NUM_STOCKS * NUM_TIME);
+    let backfills: Vec<JobHandle> = (0..3)
+        .map(|_| scheduler.submit(job(&input, Priority::Background, "backfill")?))
+        .collect::<Result<_>>()?;
+    let query = scheduler.submit(job(&input, Priority::Interactive, "research")?)?;
+
+    // A queued job is dropped from the queue as soon as it is cancelled
+    let cancelled = scheduler.submit(job(&input, Priority::Background, "backfill")?)?;
+    let queued = scheduler.queued();
+    cancelled.cancel();
+    assert_eq!(cancelled.status(), JobStatus::Cancelled);
+    assert_eq!(scheduler.queued(), queued - 1);
+    assert!(matches!(
+        cancelled.wait(),
+        Err(KunQuantError::Cancelled { .. })
+    ));
+
+    // The backfills run one at a time, so the last one can't finish before the
+    // query that overtook it
+    let outputs = query.wait()?;
+    for (expected, actual) in input.iter().zip(&outputs["output"]) {
+        assert!((expected * 3.0 - actual).abs() < 1e-5);
+    }
+    assert_ne!(backfills[2].status(), JobStatus::Completed);
+
+    // Only backfills are left, so the idle executor must not exceed their quota
+    let mut max_running = 0;
+    while backfills
+        .iter()
+        .any(|backfill| matches!(backfill.status(), JobStatus::Queued | JobStatus::Running))
+    {
+        max_running = max_running.max(scheduler.running());
+        std::thread::yield_now();
+    }
+    assert!(max_running <= 1);
+    for backfill in backfills {
+        assert_eq!(backfill.total_steps(), NUM_TIME);
+        backfill.wait()?;
+    }
+    assert_eq!(scheduler.queued(), 0);
+
+    let mismatched = job(&input[1..], Priority::Normal, "research")?;
+    assert!(matches!(
+        scheduler.submit(mismatched),
+        Err(KunQuantError::BufferSizeMismatch { .. })
+    ));
+
+    // Windows past the end of the data are rejected before they are queued
+    let too_long = BatchJob::new(
+        library.clone(),
+        "simple_test",
+        BatchParams::new(NUM_STOCKS, NUM_TIME, 10, NUM_TIME)?,
+    )
+    .with_input("input", input.clone())
+    .with_output("output");
+    assert!(matches!(
+        scheduler.submit(too_long),
+        Err(KunQuantError::InvalidBatchConfig { .. })
+    ));
+    Ok(())
+}
@@ -0,0 +1,198 @@
+#![cfg(unix)]
+
+use kunquant_rs::{BatchParams, IsolatedRunner, KunQuantError, Result};
+use rand::Rng;
+use std::collections::HashMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const NUM_STOCKS: usize = 8;
+const NUM_TIME: usize = 100;
+
+const WORKER: &str = env!("CARGO_BIN_EXE_kunquant-worker");
+
+fn generate_random_data(size: usize) -> Vec<f32> {
+    let mut rng = rand::thread_rng();
+    (0..size).map(|_| rng.gen_range(1.0..100.0)).collect()
+}
+
+/// Writes an executable shell script standing in for the worker.
+fn fake_worker(name: &str, body: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+    std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+/// Runs `simple_test` on valid buffers and returns the result.
+fn run_simple(runner: &IsolatedRunner) -> Result<()> {
+    let input_data = generate_random_data(NUM_STOCKS * NUM_TIME);
+    let mut output_data = vec![0.0f32; NUM_STOCKS * NUM_TIME];
+    let inputs = HashMap::from([("input", input_data.as_slice())]);
+    let mut outputs = HashMap::from([("output", output_data.as_mut_slice())]);
+    let params = BatchParams::full_range(NUM_STOCKS, NUM_TIME)?;
+    runner.run(&inputs, &mut outputs, &params)
+}
+
+#[test]
+fn test_isolated_simple_factor() -> Result<()> {
+    let lib_path = "test_libs/simple_test_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!("Test library not found. Please run 'python generate_test_factor.py' first");
+    }
+
+    let runner = IsolatedRunner::new(lib_path, "simple_test")
+        .with_worker(WORKER)
+        .with_timeout(Duration::from_secs(30));
+
+    let input_data = generate_random_data(NUM_STOCKS * NUM_TIME);
+    let mut output_data = vec![0.0f32; NUM_STOCKS * NUM_TIME];
+
+    let inputs = HashMap::from([("input", input_data.as_slice())]);
+    let mut outputs = HashMap::from([("output", output_data.as_mut_slice())]);
+
+    let params = BatchParams::full_range(NUM_STOCKS, NUM_TIME)?;
+    runner.run(&inputs, &mut outputs, &params)?;
+
+    // Verify results: output should be input * 3
+    let tolerance = 1e-5;
+    for i in 0..input_data.len() {
+        let expected = input_data[i] * 3.0;
+        let actual = output_data[i];
+        assert!(
+            (expected - actual).abs() <= tolerance,
+            "Mismatch at index {}: expected {}, got {}",
+            i,
+            expected,
+            actual
+        );
+    }
+
+    println!("✓ Isolated simple factor test passed!");
+    Ok(())
+}
+
+#[test]
+fn test_isolated_module_not_found() -> Result<()> {
+    let lib_path = "test_libs/simple_test_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!("Test library not found. Please run 'python generate_test_factor.py' first");
+    }
+
+    let runner = IsolatedRunner::new(lib_path, "no_such_module").with_worker(WORKER);
+    let inputs = HashMap::new();
+    let mut outputs = HashMap::new();
+    let params = BatchParams::full_range(NUM_STOCKS, NUM_TIME)?;
+
+    match runner.run(&inputs, &mut outputs, &params) {
+        Err(KunQuantError::ModuleNotFound { name, context }) => {
+            assert_eq!(name, "no_such_module");
+            assert_eq!(
+                context.unwrap().library.as_deref(),
+                Some(Path::new(lib_path))
+            );
+        }
+        other => panic!("Expected ModuleNotFound, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_isolated_library_not_found() -> Result<()> {
+    let runner = IsolatedRunner::new("test_libs/no_such_lib.so", "simple_test").with_worker(WORKER);
+    match run_simple(&runner) {
+        Err(KunQuantError::LibraryNotFound { path }) => {
+            assert_eq!(path, "test_libs/no_such_lib.so")
+        }
+        other => panic!("Expected LibraryNotFound, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_isolated_runtime_error() -> Result<()> {
+    let lib_path = "test_libs/simple_test_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!("Test library not found. Please run 'python generate_test_factor.py' first");
+    }
+
+    // The output buffer is missing, which the runtime rejects in the worker
+    let runner = IsolatedRunner::new(lib_path, "simple_test").with_worker(WORKER);
+    let input_data = generate_random_data(NUM_STOCKS * NUM_TIME);
+    let inputs = HashMap::from([("input", input_data.as_slice())]);
+    let mut outputs = HashMap::new();
+    let params = BatchParams::full_range(NUM_STOCKS, NUM_TIME)?;
+
+    match runner.run(&inputs, &mut outputs, &params) {
+        Err(e @ KunQuantError::RuntimeError { .. }) => {
+            assert_eq!(e.context().unwrap().operation, "isolated_run");
+            assert_eq!(e.context().unwrap().module.as_deref(), Some("simple_test"));
+        }
+        other => panic!("Expected RuntimeError, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_isolated_buffer_size_mismatch() -> Result<()> {
+    let runner =
+        IsolatedRunner::new("test_libs/simple_test_lib.so", "simple_test").with_worker(WORKER);
+    let input_data = vec![1.0f32; NUM_STOCKS * NUM_TIME - 1];
+    let mut output_data = vec![0.0f32; NUM_STOCKS * NUM_TIME];
+    let inputs = HashMap::from([("input", input_data.as_slice())]);
+    let mut outputs = HashMap::from([("output", output_data.as_mut_slice())]);
+    let params = BatchParams::full_range(NUM_STOCKS, NUM_TIME)?;
+
+    match runner.run(&inputs, &mut outputs, &params) {
+        Err(KunQuantError::BufferSizeMismatch {
+            name,
+            expected,
+            actual,
+            ..
+        }) => {
+            assert_eq!(name, "input");
+            assert_eq!(expected, NUM_STOCKS * NUM_TIME);
+            assert_eq!(actual, NUM_STOCKS * NUM_TIME - 1);
+        }
+        other => panic!("Expected BufferSizeMismatch, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_isolated_worker_crash() {
+    let worker = fake_worker("kunquant-crashing-worker", "kill -SEGV $$");
+    let runner =
+        IsolatedRunner::new("test_libs/simple_test_lib.so", "simple_test").with_worker(&worker);
+
+    let result = run_simple(&runner);
+    std::fs::remove_file(&worker).unwrap();
+    match result {
+        Err(KunQuantError::WorkerCrashed { reason }) => {
+            assert_eq!(reason, format!("terminated by signal {}", libc::SIGSEGV))
+        }
+        other => panic!("Expected WorkerCrashed, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_isolated_worker_timeout() {
+    let worker = fake_worker("kunquant-hanging-worker", "exec sleep 30");
+    let runner = IsolatedRunner::new("test_libs/simple_test_lib.so", "simple_test")
+        .with_worker(&worker)
+        .with_timeout(Duration::from_millis(100));
+
+    let result = run_simple(&runner);
+    std::fs::remove_file(&worker).unwrap();
+    match result {
+        Err(KunQuantError::WorkerCrashed { reason }) => {
+            assert!(
+                reason.starts_with("timed out"),
+                "unexpected reason: {}",
+                reason
+            )
+        }
+        other => panic!("Expected WorkerCrashed, got {:?}", other),
+    }
+}
@@ -1,5 +1,13 @@
-use kunquant_rs::{Executor, Library, Result, StreamContext};
+use kunquant_rs::bars::{BarBuilder, BarField, EmptyBarPolicy};
+use kunquant_rs::latency::StreamOperation;
+use kunquant_rs::verify::{StreamConsistencyChecker, Tolerance};
+use kunquant_rs::{
+    Executor, KunQuantError, Library, MissingDataPolicy, OwnedStreamContext, Result, StreamContext,
+    StreamGroup, StreamInputs, StreamReplay, SymbolStream,
+};
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 
 const NUM_STOCKS_ALIGNED: usize = 64;
 const NUM_STOCKS_UNALIGNED: usize = 63;
@@ -202,3 +210,881 @@ fn test_stream_single_step_unaligned() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_stream_push_all_and_missing_inputs() -> Result<()> {
+    let lib_path = "test_libs/simple_stream_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!(
+            "Streaming test library not found. Please run 'python generate_test_factor.py' first"
+        );
+    }
+
+    let executor = Executor::single_thread()?;
+    let library = Library::load(lib_path)?;
+    let module = library.get_module("simple_stream_test")?;
+
+    let mut stream = StreamContext::new(&executor, &module, NUM_STOCKS_ALIGNED)?;
+
+    let close_data = vec![101.0f32; NUM_STOCKS_ALIGNED];
+    let open_data = vec![100.0f32; NUM_STOCKS_ALIGNED];
+    let high_data = vec![102.0f32; NUM_STOCKS_ALIGNED];
+    let low_data = vec![99.0f32; NUM_STOCKS_ALIGNED];
+
+    // First tick: every input pushed at once, which also defines the required set
+    let inputs = StreamInputs::new()
+        .with("close", &close_data)
+        .with("open", &open_data)
+        .with("high", &high_data)
+        .with("low", &low_data);
+    stream.push_all(&inputs)?;
+    stream.run()?;
+
+    // Second tick: "low" is forgotten, so run must refuse to reuse stale data
+    stream.push_data("close", &close_data)?;
+    stream.push_data("open", &open_data)?;
+    stream.push_data("high", &high_data)?;
+    assert_eq!(stream.missing_inputs(), vec!["low"]);
+    match stream.run() {
+        Err(KunQuantError::MissingStreamInputs { missing }) => {
+            assert_eq!(missing, vec!["low".to_string()])
+        }
+        other => panic!("Expected MissingStreamInputs, got {:?}", other),
+    }
+
+    // Completing the tick makes it runnable again
+    stream.push_data("low", &low_data)?;
+    stream.run()?;
+
+    println!("✓ Stream push_all test completed!");
+    Ok(())
+}
+
+#[test]
+fn test_stream_declared_inputs_checked_from_first_tick() -> Result<()> {
+    let lib_path = "test_libs/simple_stream_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!(
+            "Streaming test library not found. Please run 'python generate_test_factor.py' first"
+        );
+    }
+
+    let executor = Executor::single_thread()?;
+    let library = Library::load(lib_path)?;
+    let module = library.get_module("simple_stream_test")?;
+
+    let mut stream = StreamContext::new(&executor, &module, NUM_STOCKS_ALIGNED)?;
+    stream.set_required_inputs(&["close", "open", "high", "low"])?;
+
+    // "low" is forgotten on the very first tick
+    let data = vec![100.0f32; NUM_STOCKS_ALIGNED];
+    stream.push_data("close", &data)?;
+    stream.push_data("open", &data)?;
+    stream.push_data("high", &data)?;
+    match stream.run() {
+        Err(KunQuantError::MissingStreamInputs { missing }) => {
+            assert_eq!(missing, vec!["low".to_string()])
+        }
+        other => panic!("Expected MissingStreamInputs, got {:?}", other),
+    }
+    assert_eq!(stream.ticks_run(), 0);
+
+    stream.push_data("low", &data)?;
+    stream.run()?;
+    assert_eq!(stream.ticks_run(), 1);
+
+    // Without a declaration the inputs of the first tick become required
+    let mut inferred = StreamContext::new(&executor, &module, NUM_STOCKS_ALIGNED)?;
+    for name in ["close", "open", "high", "low"] {
+        inferred.push_data(name, &data)?;
+    }
+    inferred.run()?;
+    for name in ["close", "open", "high"] {
+        inferred.push_data(name, &data)?;
+    }
+    match inferred.run() {
+        Err(KunQuantError::MissingStreamInputs { missing }) => {
+            assert_eq!(missing, vec!["low".to_string()])
+        }
+        other => panic!("Expected MissingStreamInputs, got {:?}", other),
+    }
+
+    // An empty declaration opts out of the check
+    inferred.set_required_inputs::<&str>(&[])?;
+    inferred.run()?;
+    assert_eq!(inferred.ticks_run(), 2);
+
+    println!("✓ Stream declared inputs test completed!");
+    Ok(())
+}
+
+#[test]
+fn test_stream_typed_handles() -> Result<()> {
+    let lib_path = "test_libs/simple_stream_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!(
+            "Streaming test library not found. Please run 'python generate_test_factor.py' first"
+        );
+    }
+
+    let executor = Executor::single_thread()?;
+    let library = Library::load(lib_path)?;
+    let module = library.get_module("simple_stream_test")?;
+
+    let mut stream = StreamContext::new(&executor, &module, NUM_STOCKS_ALIGNED)?;
+    let mut second = StreamContext::new(&executor, &module, NUM_STOCKS_ALIGNED)?;
+
+    let close = stream.resolve("close")?;
+    let open = stream.resolve("open")?;
+    let high = stream.resolve("high")?;
+    let low = stream.resolve("low")?;
+    let output = stream.resolve("simple_stream")?;
+
+    let close_data = vec![101.0f32; NUM_STOCKS_ALIGNED];
+    let open_data = vec![100.0f32; NUM_STOCKS_ALIGNED];
+    let high_data = vec![102.0f32; NUM_STOCKS_ALIGNED];
+    let low_data = vec![99.0f32; NUM_STOCKS_ALIGNED];
+
+    stream.push(close, &close_data)?;
+    stream.push(open, &open_data)?;
+    stream.push(high, &high_data)?;
+    stream.push(low, &low_data)?;
+    stream.run()?;
+
+    // Handle-based and name-based reads can be borrowed at the same time
+    let by_handle = stream.output(output)?;
+    let by_name = stream.get_current_buffer("simple_stream")?;
+    assert_eq!(by_handle, by_name);
+
+    let expected = (101.0f32 - 100.0) / (102.0 - 99.0 + 0.001);
+    for &actual in by_handle {
+        assert!((expected - actual).abs() < 1e-5);
+    }
+
+    // Handles cannot be used with another context
+    match second.push(close, &close_data) {
+        Err(KunQuantError::ForeignStreamHandle { .. }) => {}
+        other => panic!("Expected ForeignStreamHandle, got {:?}", other),
+    }
+
+    println!("✓ Typed stream handle test completed!");
+    Ok(())
+}
+
+#[test]
+fn test_stream_save_and_restore_state() -> Result<()> {
+    let lib_path = "test_libs/simple_stream_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!(
+            "Streaming test library not found. Please run 'python generate_test_factor.py' first"
+        );
+    }
+
+    let executor = Executor::single_thread()?;
+    let library = Library::load(lib_path)?;
+    let module = library.get_module("simple_stream_test")?;
+
+    let mut stream = StreamContext::new(&executor, &module, NUM_STOCKS_ALIGNED)?;
+    stream.enable_state_journal(16);
+
+    // More ticks than the journal holds, so only the last 16 are replayed
+    for step in 0..20 {
+        let base = 100.0 + step as f32;
+        let close_data = vec![base + 1.0; NUM_STOCKS_ALIGNED];
+        let open_data = vec![base; NUM_STOCKS_ALIGNED];
+        let high_data = vec![base + 2.0; NUM_STOCKS_ALIGNED];
+        let low_data = vec![base - 1.0; NUM_STOCKS_ALIGNED];
+        stream.push_all(
+            &StreamInputs::new()
+                .with("close", &close_data)
+                .with("open", &open_data)
+                .with("high", &high_data)
+                .with("low", &low_data),
+        )?;
+        stream.run()?;
+    }
+
+    // Older ticks were dropped, so saving needs a lookback the journal covers
+    let mut checkpoint = Vec::new();
+    for lookback in [None, Some(20)] {
+        if let Some(lookback) = lookback {
+            stream.set_lookback(lookback);
+        }
+        match stream.save_state(&mut checkpoint) {
+            Err(KunQuantError::InvalidStreamState { .. }) => {}
+            other => panic!("Expected InvalidStreamState, got {:?}", other),
+        }
+    }
+    checkpoint.clear();
+    stream.set_lookback(16);
+    stream.save_state(&mut checkpoint)?;
+
+    let mut restored = StreamContext::restore(&executor, &module, checkpoint.as_slice())?;
+    assert_eq!(restored.num_stocks(), NUM_STOCKS_ALIGNED);
+    // Only the replayed ticks count towards warming up
+    assert_eq!(restored.ticks_run(), 16);
+    assert_eq!(restored.remaining_warm_up(), Some(0));
+    assert_eq!(
+        restored.get_current_buffer("simple_stream")?,
+        stream.get_current_buffer("simple_stream")?
+    );
+
+    // The inputs declared by push_all stay required
+    restored.push_data("close", &vec![1.0; NUM_STOCKS_ALIGNED])?;
+    assert!(matches!(
+        restored.run(),
+        Err(KunQuantError::MissingStreamInputs { .. })
+    ));
+
+    // Garbage is rejected instead of producing a half-initialized context
+    match StreamContext::restore(&executor, &module, &b"garbage!"[..]) {
+        Err(KunQuantError::InvalidStreamState { .. }) => {}
+        other => panic!("Expected InvalidStreamState, got {:?}", other.err()),
+    }
+
+    println!("✓ Stream state save/restore test completed!");
+    Ok(())
+}
+
+#[test]
+fn test_stream_warm_up_from_history() -> Result<()> {
+    let lib_path = "test_libs/simple_stream_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!(
+            "Streaming test library not found. Please run 'python generate_test_factor.py' first"
+        );
+    }
+
+    let executor = Executor::single_thread()?;
+    let library = Library::load(lib_path)?;
+    let module = library.get_module("simple_stream_test")?;
+
+    let mut stream = StreamContext::new(&executor, &module, NUM_STOCKS_ALIGNED)?;
+    stream.set_lookback(20);
+
+    const NUM_TIME: usize = 12;
+    let size = NUM_TIME * NUM_STOCKS_ALIGNED;
+    let open: Vec<f32> = (0..size).map(|i| 100.0 + (i % 7) as f32).collect();
+    let close: Vec<f32> = open.iter().map(|o| o + 1.5).collect();
+    let high: Vec<f32> = open.iter().map(|o| o + 2.0).collect();
+    let low: Vec<f32> = open.iter().map(|o| o - 1.0).collect();
+
+    let history = HashMap::from([
+        ("open", open.as_slice()),
+        ("close", close.as_slice()),
+        ("high", high.as_slice()),
+        ("low", low.as_slice()),
+    ]);
+
+    let mut observed = 0;
+    let report = stream.warm_up_with(&history, NUM_TIME, |_, _| {
+        observed += 1;
+        Ok(())
+    })?;
+    assert_eq!(observed, NUM_TIME);
+    assert_eq!(report.steps, NUM_TIME);
+    assert_eq!(report.remaining, Some(20 - NUM_TIME));
+    assert_eq!(stream.ticks_run(), NUM_TIME as u64);
+
+    // Outputs reflect the last historical row
+    let output = stream.get_current_buffer("simple_stream")?;
+    let last = (NUM_TIME - 1) * NUM_STOCKS_ALIGNED;
+    for (i, value) in output.iter().enumerate() {
+        let j = last + i;
+        let expected = (close[j] - open[j]) / (high[j] - low[j] + 0.001);
+        assert!((expected - value).abs() < 1e-5);
+    }
+
+    println!("✓ Stream warm-up test completed!");
+    Ok(())
+}
+
+#[test]
+fn test_owned_stream_moves_across_threads() -> Result<()> {
+    fn assert_send<T: Send>() {}
+    assert_send::<OwnedStreamContext>();
+
+    let lib_path = "test_libs/simple_stream_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!(
+            "Streaming test library not found. Please run 'python generate_test_factor.py' first"
+        );
+    }
+
+    let executor = Arc::new(Executor::single_thread()?);
+    let library = Arc::new(Library::load(lib_path)?);
+
+    // Streams are created here and driven by worker threads sharing one executor
+    let workers: Vec<_> = (0..4)
+        .map(|w| {
+            let mut stream = OwnedStreamContext::new(
+                executor.clone(),
+                library.clone(),
+                "simple_stream_test",
+                NUM_STOCKS_ALIGNED,
+            )?;
+            Ok(std::thread::spawn(move || -> Result<Vec<f32>> {
+                let base = 100.0 + w as f32;
+                stream.push_data("open", &[base; NUM_STOCKS_ALIGNED])?;
+                stream.push_data("close", &[base + 1.0; NUM_STOCKS_ALIGNED])?;
+                stream.push_data("high", &[base + 2.0; NUM_STOCKS_ALIGNED])?;
+                stream.push_data("low", &[base - 2.0; NUM_STOCKS_ALIGNED])?;
+                stream.run()?;
+                Ok(stream.get_current_buffer("simple_stream")?.to_vec())
+            }))
+        })
+        .collect::<Result<_>>()?;
+
+    let expected = 1.0 / (4.0 + 0.001);
+    for worker in workers {
+        let output = worker.join().expect("stream worker panicked")?;
+        assert!(output.iter().all(|v| (v - expected).abs() < 1e-5));
+    }
+
+    println!("✓ Owned stream threading test completed!");
+    Ok(())
+}
+
+#[test]
+fn test_stream_replay_collects_every_tick() -> Result<()> {
+    let lib_path = "test_libs/simple_stream_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!(
+            "Streaming test library not found. Please run 'python generate_test_factor.py' first"
+        );
+    }
+
+    let executor = Executor::single_thread()?;
+    let library = Library::load(lib_path)?;
+    let module = library.get_module("simple_stream_test")?;
+
+    const NUM_TIME: usize = 10;
+    let size = NUM_TIME * NUM_STOCKS_ALIGNED;
+    let open: Vec<f32> = (0..size).map(|i| 50.0 + (i % 13) as f32).collect();
+    let close: Vec<f32> = open
+        .iter()
+        .enumerate()
+        .map(|(i, o)| o + (i % 3) as f32)
+        .collect();
+    let high: Vec<f32> = open.iter().map(|o| o + 4.0).collect();
+    let low: Vec<f32> = open.iter().map(|o| o - 1.0).collect();
+    let inputs = HashMap::from([
+        ("open", open.as_slice()),
+        ("close", close.as_slice()),
+        ("high", high.as_slice()),
+        ("low", low.as_slice()),
+    ]);
+
+    let replay = StreamReplay::new(&executor, &module, NUM_STOCKS_ALIGNED);
+    let result = replay.run(&inputs, &["simple_stream"], NUM_TIME)?;
+    assert_eq!(result.num_time(), NUM_TIME);
+
+    let output = result.output("simple_stream").unwrap();
+    assert_eq!(output.len(), size);
+    for j in 0..size {
+        let expected = (close[j] - open[j]) / (high[j] - low[j] + 0.001);
+        assert!((expected - output[j]).abs() < 1e-5);
+    }
+
+    // Wrongly sized inputs are rejected before anything runs
+    let short = HashMap::from([("open", &open[..NUM_STOCKS_ALIGNED])]);
+    match replay.run(&short, &["simple_stream"], NUM_TIME) {
+        Err(KunQuantError::BufferSizeMismatch { .. }) => {}
+        other => panic!("Expected BufferSizeMismatch, got {:?}", other.err()),
+    }
+
+    println!("✓ Stream replay test completed!");
+    Ok(())
+}
+
+#[test]
+fn test_stream_matches_batch_module() -> Result<()> {
+    let lib_path = "test_libs/simple_stream_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!(
+            "Streaming test library not found. Please run 'python generate_test_factor.py' first"
+        );
+    }
+
+    let executor = Executor::single_thread()?;
+    let library = Library::load(lib_path)?;
+    let batch = library.get_module("simple_stream_batch")?;
+    let stream = library.get_module("simple_stream_test")?;
+
+    const NUM_TIME: usize = 20;
+    let size = NUM_TIME * NUM_STOCKS_ALIGNED;
+    let open: Vec<f32> = (0..size).map(|i| 10.0 + (i % 11) as f32 * 0.5).collect();
+    let close: Vec<f32> = open.iter().map(|o| o * 1.01).collect();
+    let high: Vec<f32> = open.iter().map(|o| o * 1.02).collect();
+    let low: Vec<f32> = open.iter().map(|o| o * 0.98).collect();
+    let inputs = HashMap::from([
+        ("open", open.as_slice()),
+        ("close", close.as_slice()),
+        ("high", high.as_slice()),
+        ("low", low.as_slice()),
+    ]);
+
+    let checker = StreamConsistencyChecker::new(&executor, &batch, &stream)
+        .with_tolerance(Tolerance::default().with_max_ulps(4));
+    let report = checker.check(&inputs, &["simple_stream"], NUM_STOCKS_ALIGNED, NUM_TIME)?;
+    assert!(report.is_consistent(), "{:?}", report.first_divergence);
+
+    println!("✓ Stream vs batch consistency test completed!");
+    Ok(())
+}
+
+#[test]
+fn test_stream_group_fans_out_inputs() -> Result<()> {
+    let lib_path = "test_libs/simple_stream_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!(
+            "Streaming test library not found. Please run 'python generate_test_factor.py' first"
+        );
+    }
+
+    let executor = Arc::new(Executor::single_thread()?);
+    let library = Arc::new(Library::load(lib_path)?);
+
+    let mut group = StreamGroup::new()
+        .with_inputs(&["open", "close", "high", "low", "volume"])
+        .with_parallelism(2);
+    group.add_module(
+        executor.clone(),
+        library.clone(),
+        "simple_stream_test",
+        NUM_STOCKS_ALIGNED,
+    )?;
+    for name in ["copy_1", "copy_2"] {
+        let context = OwnedStreamContext::new(
+            executor.clone(),
+            library.clone(),
+            "simple_stream_test",
+            NUM_STOCKS_ALIGNED,
+        )?;
+        group.add(name, context)?;
+    }
+    assert_eq!(group.len(), 3);
+
+    let duplicate = OwnedStreamContext::new(
+        executor.clone(),
+        library.clone(),
+        "simple_stream_test",
+        NUM_STOCKS_ALIGNED,
+    )?;
+    match group.add("copy_1", duplicate) {
+        Err(KunQuantError::DuplicateStreamMember { name }) => assert_eq!(name, "copy_1"),
+        other => panic!("Expected DuplicateStreamMember, got {:?}", other.err()),
+    }
+
+    for step in 0..3 {
+        let base = 20.0 + step as f32;
+        group.push_data("open", &[base; NUM_STOCKS_ALIGNED])?;
+        group.push_data("close", &[base + 0.5; NUM_STOCKS_ALIGNED])?;
+        group.push_data("high", &[base + 1.0; NUM_STOCKS_ALIGNED])?;
+        group.push_data("low", &[base - 1.0; NUM_STOCKS_ALIGNED])?;
+        group.run()?;
+
+        let expected = 0.5 / (2.0 + 0.001);
+        let outputs = group.outputs(&[
+            ("simple_stream_test", "simple_stream"),
+            ("copy_1", "simple_stream"),
+            ("copy_2", "simple_stream"),
+        ])?;
+        assert_eq!(outputs.len(), 3);
+        for values in outputs.values() {
+            assert!(values.iter().all(|v| (v - expected).abs() < 1e-5));
+        }
+    }
+
+    // Inputs no member declares are rejected
+    match group.push_data("volume", &[0.0; NUM_STOCKS_ALIGNED]) {
+        Err(KunQuantError::BufferHandleNotFound { .. }) => {}
+        other => panic!("Expected BufferHandleNotFound, got {:?}", other),
+    }
+
+    // Names that aren't declared inputs, such as outputs, never reach the members
+    match group.push_data("simple_stream", &[0.0; NUM_STOCKS_ALIGNED]) {
+        Err(KunQuantError::InvalidBufferName { name }) => assert_eq!(name, "simple_stream"),
+        other => panic!("Expected InvalidBufferName, got {:?}", other),
+    }
+    match group.output("no_such_member", "simple_stream") {
+        Err(KunQuantError::UnknownStreamMember { name }) => assert_eq!(name, "no_such_member"),
+        other => panic!("Expected UnknownStreamMember, got {:?}", other),
+    }
+
+    // Every member runs and reports its own failure
+    group.push_data("close", &[1.0; NUM_STOCKS_ALIGNED])?;
+    match group.run() {
+        Err(KunQuantError::StreamGroupFailed { failures }) => {
+            let members: Vec<&str> = failures.iter().map(|(m, _)| m.as_str()).collect();
+            assert_eq!(members, ["simple_stream_test", "copy_1", "copy_2"]);
+            assert!(
+                failures
+                    .iter()
+                    .all(|(_, e)| matches!(e, KunQuantError::MissingStreamInputs { .. }))
+            );
+        }
+        other => panic!("Expected StreamGroupFailed, got {:?}", other),
+    }
+
+    println!("✓ Stream group test completed!");
+    Ok(())
+}
+
+#[test]
+fn test_bar_builder_pushes_closed_bars() -> Result<()> {
+    let lib_path = "test_libs/simple_stream_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!(
+            "Streaming test library not found. Please run 'python generate_test_factor.py' first"
+        );
+    }
+
+    let executor = Executor::single_thread()?;
+    let library = Library::load(lib_path)?;
+    let module = library.get_module("simple_stream_test")?;
+    let mut stream = StreamContext::new(&executor, &module, NUM_STOCKS_ALIGNED)?;
+
+    let mut bars = BarBuilder::new(NUM_STOCKS_ALIGNED, 1_000)?
+        .with_empty_policy(EmptyBarPolicy::ForwardFill)
+        .with_fields(&[
+            BarField::Open,
+            BarField::High,
+            BarField::Low,
+            BarField::Close,
+        ]);
+
+    // First bar: every stock trades 10 -> 12 -> 9 -> 11
+    for (offset, price) in [10.0, 12.0, 9.0, 11.0].into_iter().enumerate() {
+        for stock in 0..NUM_STOCKS_ALIGNED {
+            let closed = bars.on_trade(&mut stream, stock, offset as u64 * 100, price, 1.0)?;
+            assert_eq!(closed, 0);
+        }
+    }
+    assert_eq!(stream.ticks_run(), 0);
+
+    // A trade two intervals later closes the first bar and one forward-filled bar
+    let closed = bars.on_trade(&mut stream, 0, 2_500, 11.0, 1.0)?;
+    assert_eq!(closed, 2);
+    assert_eq!(stream.ticks_run(), 2);
+
+    // The forward-filled bar has high == low, so the output is 0 / 0.001
+    let output = stream.get_current_buffer("simple_stream")?;
+    assert!(output.iter().all(|v| v.abs() < 1e-5));
+
+    println!("✓ Bar builder test completed!");
+    Ok(())
+}
+
+#[test]
+fn test_symbol_stream_universe_changes() -> Result<()> {
+    let lib_path = "test_libs/simple_stream_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!(
+            "Streaming test library not found. Please run 'python generate_test_factor.py' first"
+        );
+    }
+
+    let executor = Arc::new(Executor::single_thread()?);
+    let library = Arc::new(Library::load(lib_path)?);
+    let mut stream = SymbolStream::new(
+        executor,
+        library,
+        "simple_stream_test",
+        &["AAA", "BBB", "CCC"],
+        1,
+        4,
+    )?;
+    assert_eq!(stream.capacity(), 4);
+
+    fn tick(stream: &mut SymbolStream, symbols: &[&str]) -> Result<()> {
+        let push = |stream: &mut SymbolStream, name: &str, value: f32| {
+            stream.push_symbols(name, symbols.iter().map(|s| (*s, value)))
+        };
+        push(stream, "open", 10.0)?;
+        push(stream, "close", 11.0)?;
+        push(stream, "high", 12.0)?;
+        push(stream, "low", 9.0)?;
+        stream.run()
+    }
+
+    for _ in 0..6 {
+        tick(&mut stream, &["AAA", "BBB", "CCC"])?;
+    }
+    let expected = 1.0 / (3.0 + 0.001);
+    assert!((stream.output_for("simple_stream", "BBB")? - expected).abs() < 1e-5);
+
+    // Removed symbols are fed NaN, new symbols take the spare slot
+    assert!(stream.remove_symbol("BBB"));
+    assert_eq!(stream.add_symbol("DDD")?, 3);
+    assert_eq!(stream.spare_slots(), 0);
+    tick(&mut stream, &["AAA", "CCC", "DDD"])?;
+    assert!(stream.output("simple_stream")?[1].is_nan());
+    match stream.push_symbols("close", [("BBB", 1.0)]) {
+        Err(KunQuantError::UnknownSymbol { symbol }) => assert_eq!(symbol, "BBB"),
+        other => panic!("Expected UnknownSymbol, got {:?}", other),
+    }
+
+    // No spare slot left: the context is rebuilt and the journal replayed
+    stream.add_symbol("EEE")?;
+    assert_eq!(stream.len(), 4);
+    assert_eq!(stream.capacity(), 5);
+    assert_eq!(stream.slot("AAA"), Some(0));
+    assert_eq!(stream.slot("CCC"), Some(1));
+    assert_eq!(stream.slot("DDD"), Some(2));
+    assert_eq!(stream.context().ticks_run(), 4);
+    for symbol in ["AAA", "CCC", "DDD"] {
+        assert!((stream.output_for("simple_stream", symbol)? - expected).abs() < 1e-5);
+    }
+    // The rebuilt context still requires every input
+    stream.push_symbols("open", [("AAA", 10.0)])?;
+    let mut missing = stream.context().missing_inputs();
+    missing.sort();
+    assert_eq!(missing, vec!["close", "high", "low"]);
+
+    tick(&mut stream, &["AAA", "CCC", "DDD", "EEE"])?;
+    let outputs = stream.outputs_by_symbol("simple_stream")?;
+    assert_eq!(outputs.len(), 4);
+    assert!(outputs.values().all(|v| (v - expected).abs() < 1e-5));
+
+    println!("✓ Symbol stream universe test completed!");
+    Ok(())
+}
+
+#[test]
+fn test_stream_output_history() -> Result<()> {
+    let lib_path = "test_libs/simple_stream_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!(
+            "Streaming test library not found. Please run 'python generate_test_factor.py' first"
+        );
+    }
+
+    let executor = Executor::single_thread()?;
+    let library = Library::load(lib_path)?;
+    let module = library.get_module("simple_stream_test")?;
+    let mut stream = StreamContext::new(&executor, &module, NUM_STOCKS_ALIGNED)?;
+    stream.enable_output_history("simple_stream", 3)?;
+    assert!(stream.output_history("simple_stream").unwrap().is_empty());
+
+    let mut expected = Vec::new();
+    for step in 0..5 {
+        let close = step as f32;
+        stream.push_data("open", &[0.0; NUM_STOCKS_ALIGNED])?;
+        stream.push_data("close", &[close; NUM_STOCKS_ALIGNED])?;
+        stream.push_data("high", &[1.0; NUM_STOCKS_ALIGNED])?;
+        stream.push_data("low", &[0.0; NUM_STOCKS_ALIGNED])?;
+        stream.run()?;
+        expected.push(close / 1.001);
+    }
+
+    let history = stream.output_history("simple_stream").unwrap();
+    assert_eq!(history.len(), 3);
+    assert_eq!(history.as_slice().len(), 3 * NUM_STOCKS_ALIGNED);
+    for (row, value) in history.rows().zip(&expected[2..]) {
+        assert!(row.iter().all(|v| (v - value).abs() < 1e-5));
+    }
+    assert_eq!(
+        history.lag(0).unwrap(),
+        stream.get_current_buffer("simple_stream")?
+    );
+
+    assert!(stream.disable_output_history("simple_stream"));
+    assert!(stream.output_history("simple_stream").is_none());
+
+    println!("✓ Stream output history test completed!");
+    Ok(())
+}
+
+#[test]
+fn test_stream_output_subscriptions() -> Result<()> {
+    let lib_path = "test_libs/simple_stream_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!(
+            "Streaming test library not found. Please run 'python generate_test_factor.py' first"
+        );
+    }
+
+    let executor = Executor::single_thread()?;
+    let library = Library::load(lib_path)?;
+    let module = library.get_module("simple_stream_test")?;
+    let mut stream = StreamContext::new(&executor, &module, NUM_STOCKS_ALIGNED)?;
+
+    let every_tick = Arc::new(Mutex::new(Vec::new()));
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    let sink = every_tick.clone();
+    stream.subscribe("simple_stream", move |tick, values| {
+        assert_eq!(values.len(), NUM_STOCKS_ALIGNED);
+        sink.lock().unwrap().push(tick);
+    })?;
+    let sink = changes.clone();
+    stream.subscribe_changes("simple_stream", move |tick, _, changed| {
+        sink.lock().unwrap().push((tick, changed.to_vec()));
+    })?;
+    let (id, receiver) = stream.subscribe_channel("simple_stream")?;
+
+    let mut close = [1.0; NUM_STOCKS_ALIGNED];
+    for step in 0..3 {
+        if step == 2 {
+            close[5] = 2.0;
+        }
+        stream.push_data("open", &[0.0; NUM_STOCKS_ALIGNED])?;
+        stream.push_data("close", &close)?;
+        stream.push_data("high", &[3.0; NUM_STOCKS_ALIGNED])?;
+        stream.push_data("low", &[0.0; NUM_STOCKS_ALIGNED])?;
+        stream.run()?;
+    }
+
+    let received: Vec<_> = receiver.try_iter().collect();
+    assert_eq!(received.len(), 3);
+    assert_eq!(
+        received[2].1[5],
+        stream.get_current_buffer("simple_stream")?[5]
+    );
+    assert!(stream.unsubscribe(id));
+    assert!(!stream.unsubscribe(id));
+
+    assert_eq!(*every_tick.lock().unwrap(), vec![0, 1, 2]);
+    let changes = changes.lock().unwrap();
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0].1.len(), NUM_STOCKS_ALIGNED);
+    assert_eq!(changes[1], (2, vec![5]));
+
+    println!("✓ Stream subscription test completed!");
+    Ok(())
+}
+
+#[test]
+fn test_stream_masked_push_policies() -> Result<()> {
+    let lib_path = "test_libs/simple_stream_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!(
+            "Streaming test library not found. Please run 'python generate_test_factor.py' first"
+        );
+    }
+
+    let executor = Executor::single_thread()?;
+    let library = Library::load(lib_path)?;
+    let module = library.get_module("simple_stream_test")?;
+    let mut stream = StreamContext::new(&executor, &module, NUM_STOCKS_ALIGNED)?;
+    stream.set_missing_policy("close", MissingDataPolicy::Suspend)?;
+    stream.set_missing_policy("open", MissingDataPolicy::ForwardFill)?;
+    stream.set_missing_policy("high", MissingDataPolicy::Nan)?;
+
+    // Suspended stocks are only held in resolved outputs
+    match stream.get_current_buffer("simple_stream") {
+        Err(KunQuantError::UnresolvedStreamOutput { name }) => assert_eq!(name, "simple_stream"),
+        other => panic!("Expected UnresolvedStreamOutput, got {:?}", other),
+    }
+    let output = stream.resolve("simple_stream")?;
+
+    let all_valid = [true; NUM_STOCKS_ALIGNED];
+    let mut valid = all_valid;
+    valid[3] = false;
+
+    stream.push_masked("close", &[2.0; NUM_STOCKS_ALIGNED], &all_valid)?;
+    stream.push_masked("open", &[1.0; NUM_STOCKS_ALIGNED], &all_valid)?;
+    stream.push_masked("high", &[3.0; NUM_STOCKS_ALIGNED], &all_valid)?;
+    stream.push_data("low", &[0.0; NUM_STOCKS_ALIGNED])?;
+    stream.run()?;
+    assert_eq!(stream.suspended_stocks().count(), 0);
+    let previous = stream.output(output)?.to_vec();
+
+    // Stock 3 has no new data; its entries are garbage and must not be used
+    stream.push_masked("close", &[-1e9; NUM_STOCKS_ALIGNED], &valid)?;
+    stream.push_masked("open", &[-1e9; NUM_STOCKS_ALIGNED], &valid)?;
+    stream.push_masked("high", &[-1e9; NUM_STOCKS_ALIGNED], &valid)?;
+    stream.push_data("low", &[0.0; NUM_STOCKS_ALIGNED])?;
+    stream.run()?;
+
+    assert_eq!(stream.suspended_stocks().collect::<Vec<_>>(), vec![3]);
+    assert!(stream.is_suspended(3));
+    assert!(!stream.is_suspended(4));
+    // The suspended stock keeps its previous output
+    let values = stream.get_current_buffer("simple_stream")?;
+    assert_eq!(values[3], previous[3]);
+    // Valid stocks use the pushed values, so close equals open
+    assert_eq!(values[4], 0.0);
+
+    let result = stream.push_masked("close", &[0.0; NUM_STOCKS_ALIGNED], &valid[1..]);
+    assert!(matches!(
+        result,
+        Err(KunQuantError::BufferSizeMismatch { .. })
+    ));
+
+    println!("✓ Stream masked push test completed!");
+    Ok(())
+}
+
+#[test]
+fn test_stream_latency_tracking() -> Result<()> {
+    let lib_path = "test_libs/simple_stream_lib.so";
+    if !Path::new(lib_path).exists() {
+        panic!(
+            "Streaming test library not found. Please run 'python generate_test_factor.py' first"
+        );
+    }
+
+    let executor = Executor::single_thread()?;
+    let library = Library::load(lib_path)?;
+    let module = library.get_module("simple_stream_test")?;
+    let mut stream = StreamContext::new(&executor, &module, NUM_STOCKS_ALIGNED)?;
+    assert!(stream.latency().is_none());
+    stream.enable_latency_tracking();
+
+    for _ in 0..20 {
+        for name in ["close", "open", "high", "low"] {
+            stream.push_data(name, &[1.0; NUM_STOCKS_ALIGNED])?;
+        }
+        stream.run()?;
+        stream.get_current_buffer("simple_stream")?;
+    }
+
+    let latency = stream.latency().unwrap();
+    assert_eq!(latency.histogram(StreamOperation::PushData).count(), 80);
+    assert_eq!(latency.histogram(StreamOperation::Run).count(), 20);
+    assert_eq!(
+        latency.histogram(StreamOperation::GetCurrentBuffer).count(),
+        20
+    );
+    let run = latency.histogram(StreamOperation::Run).summary();
+    assert!(run.min <= run.p50 && run.p50 <= run.p99 && run.p99 <= run.max);
+
+    let mut csv = Vec::new();
+    latency.write_csv(&mut csv)?;
+    assert_eq!(String::from_utf8(csv).unwrap().lines().count(), 4);
+
+    stream.reset_latency();
+    assert!(
+        stream
+            .latency()
+            .unwrap()
+            .histogram(StreamOperation::Run)
+            .is_empty()
+    );
+
+    // The other push paths are timed as pushes too
+    let data = [1.0; NUM_STOCKS_ALIGNED];
+    stream.push_all(
+        &StreamInputs::new()
+            .with("close", &data)
+            .with("open", &data)
+            .with("high", &data),
+    )?;
+    stream.push_masked("low", &data, &[true; NUM_STOCKS_ALIGNED])?;
+    stream.run()?;
+    let history = vec![1.0; 2 * NUM_STOCKS_ALIGNED];
+    let history: HashMap<&str, &[f32]> = ["close", "open", "high", "low"]
+        .into_iter()
+        .map(|name| (name, history.as_slice()))
+        .collect();
+    stream.warm_up(&history, 2)?;
+    let latency = stream.latency().unwrap();
+    assert_eq!(latency.histogram(StreamOperation::PushData).count(), 2 + 8);
+    assert_eq!(latency.histogram(StreamOperation::Run).count(), 3);
+    stream.disable_latency_tracking();
+    assert!(stream.latency().is_none());
+
+    println!("✓ Stream latency tracking test completed!");
+    Ok(())
+}